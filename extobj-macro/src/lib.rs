@@ -1,24 +1,38 @@
 use proc_macro::TokenStream;
 use proc_macro2::Span;
-use quote::quote;
+use quote::{format_ident, quote, quote_spanned};
 use syn::{
-    Expr, Ident, Path, Token, Type, Visibility,
-    parse::{Parse, ParseStream},
+    Attribute, Expr, GenericParam, Generics, Ident, Path, Token, Type, Visibility,
+    parse::{Parse, ParseStream, Parser},
     parse_macro_input,
+    spanned::Spanned,
 };
 
 /// Top-level input: either
-///   `extobj!(struct Name);`
+///   `extobj!(struct Name[<T: 'static, ...>]);`
 ///   or
-///   `extobj!(impl Name { vis id: ty, ... });`
+///   `extobj!(impl Name { vis id: ty [= default | = init(fn) | ...], ... });`
 /// plus the optional attribute `, crate_path = crate::path2` at the end.
 struct Input {
-    kw_struct: Option<Span>, // span of the `struct` token if present
     name: Name,
-    fields: Vec<(Visibility, Ident, Type)>,
+    fields: Vec<Field>,
     vis: Visibility,
     crate_path: Path,
     init: Option<Expr>,
+    accessors: Option<ItemName>,
+    view: Option<ItemName>,
+    register: Option<ItemName>,
+    backend: Backend,
+    /// `module = [vis] name`: the generated items are nested in a module.
+    module: Option<ItemName>,
+    /// `extobj!(struct Name, local)`: fields may be `!Send`.
+    local: bool,
+    /// `extobj!(struct Name, singleton)`: generates `Name::global()`.
+    singleton: bool,
+    /// `extobj!(struct Name, comparable)`: `ExtObj<Name>` is `Eq + Hash`.
+    comparable: bool,
+    /// `sealed` and `key = K`: who may declare the fields of the object.
+    seal: Seal,
 }
 
 impl Parse for Input {
@@ -27,17 +41,31 @@ impl Parse for Input {
         let vis: Visibility = input.parse()?;
 
         if input.peek(Token![struct]) {
-            let kw_struct: Token![struct] = input.parse()?;
-            let name = Name::Struct(input.parse()?);
-            let CratePathArg { path: crate_path } = input.parse()?;
+            let _: Token![struct] = input.parse()?;
+            let name = Name::Struct(input.parse()?, input.parse()?);
+            let StructTrailingArgs {
+                path: crate_path,
+                local,
+                singleton,
+                comparable,
+                seal,
+            } = input.parse()?;
 
             Ok(Input {
-                kw_struct: Some(kw_struct.span),
                 name,
                 fields: Vec::new(),
                 vis,
                 crate_path,
                 init: None,
+                accessors: None,
+                view: None,
+                register: None,
+                backend: Backend::Ctor,
+                module: None,
+                local,
+                singleton,
+                comparable,
+                seal,
             })
         } else {
             let _: Token![impl] = input.parse()?;
@@ -47,67 +75,354 @@ impl Parse for Input {
             let mut fields = Vec::new();
 
             while !content.is_empty() {
-                let vis: Visibility = content.parse()?;
-                let id: Ident = content.parse()?;
-                let _: Token![:] = content.parse()?;
-                let ty: Type = content.parse()?;
+                fields.push(content.parse()?);
                 let _: Option<Token![,]> = content.parse()?;
-                fields.push((vis, id, ty));
             }
 
-            let ImplTrailingArgs { crate_path, init } = input.parse()?;
+            let ImplTrailingArgs {
+                crate_path,
+                init,
+                accessors,
+                view,
+                register,
+                backend,
+                module,
+                key,
+            } = input.parse()?;
 
             Ok(Input {
-                kw_struct: None,
                 name,
                 fields,
                 vis: Visibility::Inherited,
                 init,
-                crate_path: crate_path.unwrap_or_else(|| CratePathArg::default().path),
+                accessors,
+                view,
+                register,
+                backend,
+                module,
+                local: false,
+                singleton: false,
+                comparable: false,
+                seal: Seal { sealed: false, key },
+                crate_path: crate_path.unwrap_or_else(|| StructTrailingArgs::default().path),
             })
         }
     }
 }
 
+/// One field of an impl block: `#[attrs] vis id: ty [= default | = init(fn) | ...]`.
+struct Field {
+    /// Outer attributes, forwarded to the generated static.
+    attrs: Vec<Attribute>,
+    /// `#[drop_priority(n)]`: fields are dropped by increasing priority.
+    drop_priority: Option<Expr>,
+    /// `#[optional]`: the field is an `Optional<ty>`, empty until set.
+    optional: bool,
+    /// `#[validate(f)]` where `f` is a `fn(&ty) -> Result<(), E>`.
+    validate: Option<Expr>,
+    /// `#[version(n)]`: the version of the field in binary snapshots.
+    version: Option<Expr>,
+    /// `#[zeroize]`: the values are wiped before they are dropped.
+    zeroize: bool,
+    /// `#[readonly]`: the field is published as a `ReadOnlyVar`, the `Var`
+    /// is kept in a `pub(crate)` static suffixed with `_MUT`.
+    readonly: bool,
+    vis: Visibility,
+    id: Ident,
+    ty: Type,
+    init: Option<FieldInit>,
+}
+
+/// How the value of a field is created, when not with `Default::default()`.
+enum FieldInit {
+    /// `= expr`, evaluated for every new object.
+    Default(Expr),
+    /// `= init(f)` where `f` is a `fn() -> T` or a non-capturing closure.
+    Fn(Expr),
+    /// `= try_init(f)` where `f` is a `fn() -> Result<T, BoxError>`.
+    TryFn(Expr),
+    /// `= init_with(f)` where `f` is a `fn(&dyn Any) -> T`.
+    CtxFn(Expr),
+    /// `= try_init_with(f)` where `f` is a `fn(&dyn Any) -> Result<T, BoxError>`.
+    TryCtxFn(Expr),
+    /// `= init_async(f)` where `f` is a `fn() -> BoxFuture<'static, T>`.
+    AsyncFn(Expr),
+}
+
+impl FieldInit {
+    fn from_expr(expr: Expr) -> Self {
+        let Expr::Call(call) = expr else {
+            return Self::Default(expr);
+        };
+
+        let kind: fn(Expr) -> Self = match &*call.func {
+            f if is_ident(f, "init") => Self::Fn,
+            f if is_ident(f, "try_init") => Self::TryFn,
+            f if is_ident(f, "init_with") => Self::CtxFn,
+            f if is_ident(f, "try_init_with") => Self::TryCtxFn,
+            f if is_ident(f, "init_async") => Self::AsyncFn,
+            _ => return Self::Default(Expr::Call(call)),
+        };
+
+        if call.args.len() != 1 {
+            return Self::Default(Expr::Call(call));
+        }
+
+        kind(call.args.into_iter().next().unwrap())
+    }
+}
+
+impl Field {
+    /// The `__field!` invocation describing the field of `name` to the
+    /// registry, asserting the object accepts the field type.
+    fn descriptor(
+        &self,
+        extobj: &Path,
+        name: &proc_macro2::TokenStream,
+    ) -> proc_macro2::TokenStream {
+        let Field { id, ty, .. } = self;
+        let assert = quote_spanned! {ty.span()=>
+            const _: () = #extobj::__private::assert_field::<#name, #ty>();
+        };
+
+        let field = match &self.init {
+            Some(FieldInit::Default(expr)) => quote!(#extobj::__field!(#ty, #id, || #expr)),
+            Some(FieldInit::Fn(f)) => quote!(#extobj::__field!(#ty, #id, #f)),
+            Some(FieldInit::TryFn(f)) => quote!(#extobj::__field!(#ty, #id, try_init = #f)),
+            Some(FieldInit::CtxFn(f)) => quote!(#extobj::__field!(#ty, #id, init_with = #f)),
+            Some(FieldInit::TryCtxFn(f)) => {
+                quote!(#extobj::__field!(#ty, #id, try_init_with = #f))
+            }
+            Some(FieldInit::AsyncFn(f)) => quote!(#extobj::__field!(#ty, #id, init_async = #f)),
+            None => quote!(#extobj::__field!(#ty, #id)),
+        };
+
+        let field = match &self.drop_priority {
+            Some(priority) => quote!(#field.drop_priority(#priority)),
+            None => field,
+        };
+
+        let field = if self.zeroize {
+            quote!(#field.zeroize())
+        } else {
+            field
+        };
+
+        let field = match &self.validate {
+            Some(f) => quote!(#field.validate(#f)),
+            None => field,
+        };
+
+        let field = match &self.version {
+            Some(version) => quote!(#field.version(#version)),
+            None => field,
+        };
+
+        let field = if self.readonly {
+            quote!(#field.readonly())
+        } else {
+            field
+        };
+
+        quote!({
+            #assert
+            #field
+        })
+    }
+
+    /// The static holding the `Var` of the field, which is `ID_MUT` for the
+    /// `#[readonly]` fields.
+    fn var_ident(&self) -> Ident {
+        if self.readonly {
+            format_ident!("{}_MUT", self.id)
+        } else {
+            self.id.clone()
+        }
+    }
+
+    /// The `#[cfg]` attributes of the field, repeated on every item generated
+    /// for it so that disabled fields vanish everywhere.
+    fn cfgs(&self) -> Vec<&Attribute> {
+        self.attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("cfg"))
+            .collect()
+    }
+}
+
+fn is_ident(expr: &Expr, ident: &str) -> bool {
+    matches!(expr, Expr::Path(p) if p.qself.is_none() && p.path.is_ident(ident))
+}
+
+impl Parse for Field {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let mut attrs = input.call(Attribute::parse_outer)?;
+        let mut drop_priority = None;
+
+        if let Some(pos) = attrs
+            .iter()
+            .position(|attr| attr.path().is_ident("drop_priority"))
+        {
+            drop_priority = Some(attrs.remove(pos).parse_args()?);
+        }
+
+        let validate = match attrs
+            .iter()
+            .position(|attr| attr.path().is_ident("validate"))
+        {
+            Some(pos) => Some(attrs.remove(pos).parse_args()?),
+            None => None,
+        };
+
+        let version = match attrs
+            .iter()
+            .position(|attr| attr.path().is_ident("version"))
+        {
+            Some(pos) => Some(attrs.remove(pos).parse_args()?),
+            None => None,
+        };
+
+        let zeroize = match attrs
+            .iter()
+            .position(|attr| attr.path().is_ident("zeroize"))
+        {
+            Some(pos) => {
+                attrs.remove(pos).meta.require_path_only()?;
+                true
+            }
+            None => false,
+        };
+
+        let readonly = match attrs
+            .iter()
+            .position(|attr| attr.path().is_ident("readonly"))
+        {
+            Some(pos) => {
+                attrs.remove(pos).meta.require_path_only()?;
+                true
+            }
+            None => false,
+        };
+
+        let optional = match attrs
+            .iter()
+            .position(|attr| attr.path().is_ident("optional"))
+        {
+            Some(pos) => {
+                attrs.remove(pos).meta.require_path_only()?;
+                true
+            }
+            None => false,
+        };
+
+        let vis = input.parse()?;
+        let id = input.parse()?;
+        let _: Token![:] = input.parse()?;
+        let ty = input.parse()?;
+
+        let init = if input.peek(Token![=]) {
+            let eq: Token![=] = input.parse()?;
+
+            if optional {
+                return Err(syn::Error::new(
+                    eq.span(),
+                    "`#[optional]` fields start empty and take no initializer",
+                ));
+            }
+
+            Some(FieldInit::from_expr(input.parse()?))
+        } else {
+            None
+        };
+
+        Ok(Field {
+            attrs,
+            drop_priority,
+            optional,
+            validate,
+            version,
+            zeroize,
+            readonly,
+            vis,
+            id,
+            ty,
+            init,
+        })
+    }
+}
+
 enum Name {
-    Struct(Ident), // after `struct`
-    Impl(Type),    // after `impl`
+    Struct(Ident, Generics), // after `struct`
+    Impl(Type),              // after `impl`
 }
 
-struct CratePathArg {
+/// The arguments after `extobj!(struct Name`: `crate_path = <path>`,
+/// `local`, `singleton`, `comparable`, `sealed` and `key = <type>`.
+struct StructTrailingArgs {
     path: Path,
+    local: bool,
+    singleton: bool,
+    comparable: bool,
+    seal: Seal,
+}
+
+/// Who may declare the fields of an object.
+#[derive(Default)]
+struct Seal {
+    /// `sealed`: only the declaring crate.
+    sealed: bool,
+    /// `key = K`: only the crates naming the type `K`. In an impl block, the
+    /// key given to the object.
+    key: Option<Type>,
 }
 
-impl Parse for CratePathArg {
+impl Parse for StructTrailingArgs {
     fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
-        // optional trailing comma
-        let _: Option<Token![,]> = input.parse()?;
+        let mut args = StructTrailingArgs::default();
 
-        if input.is_empty() {
-            return Ok(CratePathArg::default());
-        }
+        while !input.is_empty() {
+            let _: Token![,] = input.parse()?;
 
-        // expect `crate_path = "ident"`
-        let ident: Ident = input.parse()?; // crate_path
+            if input.is_empty() {
+                break;
+            }
 
-        if ident != "crate_path" {
-            return Err(syn::Error::new(
-                ident.span(),
-                "expected `crate_path = <ident>`",
-            ));
-        }
+            let ident: Ident = input.parse()?;
 
-        let _: Token![=] = input.parse()?;
-        let path = input.parse()?;
+            if ident == "crate_path" {
+                let _: Token![=] = input.parse()?;
+                args.path = input.parse()?;
+            } else if ident == "local" {
+                args.local = true;
+            } else if ident == "singleton" {
+                args.singleton = true;
+            } else if ident == "comparable" {
+                args.comparable = true;
+            } else if ident == "sealed" {
+                args.seal.sealed = true;
+            } else if ident == "key" {
+                let _: Token![=] = input.parse()?;
+                args.seal.key = Some(input.parse()?);
+            } else {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "expected `crate_path = <ident>`, `local`, `singleton`, `comparable`, `sealed` or `key = <type>`",
+                ));
+            }
+        }
 
-        Ok(CratePathArg { path })
+        Ok(args)
     }
 }
 
-impl Default for CratePathArg {
+impl Default for StructTrailingArgs {
     fn default() -> Self {
         Self {
-            path: Ident::new("extobj", proc_macro2::Span::call_site()).into(),
+            path: Ident::new("extobj", Span::call_site()).into(),
+            local: false,
+            singleton: false,
+            comparable: false,
+            seal: Seal::default(),
         }
     }
 }
@@ -116,6 +431,38 @@ impl Default for CratePathArg {
 struct ImplTrailingArgs {
     crate_path: Option<Path>,
     init: Option<Expr>,
+    accessors: Option<ItemName>,
+    view: Option<ItemName>,
+    register: Option<ItemName>,
+    backend: Backend,
+    module: Option<ItemName>,
+    key: Option<Type>,
+}
+
+/// How the fields are registered.
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Backend {
+    /// By constructors running before `main`.
+    #[default]
+    Ctor,
+    /// By collecting them in a `linkme` distributed slice at link time.
+    Linkme,
+}
+
+/// `[vis] Name` of a generated item, like the extension trait of
+/// `accessors = pub MyExt` or the struct of `view = pub MyView`.
+struct ItemName {
+    vis: Visibility,
+    name: Ident,
+}
+
+impl Parse for ItemName {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        Ok(ItemName {
+            vis: input.parse()?,
+            name: input.parse()?,
+        })
+    }
 }
 
 impl Parse for ImplTrailingArgs {
@@ -127,9 +474,15 @@ impl Parse for ImplTrailingArgs {
             let _: Token![,] = input.parse()?;
         }
 
-        // parse zero, one or both of:
+        // parse any of:
         //   crate_path = <path>
         //   init       = <expr>
+        //   accessors  = [vis] <ident>
+        //   view       = [vis] <ident>
+        //   register   = [vis] <ident>
+        //   backend    = ctor | linkme
+        //   module     = [vis] <ident>
+        //   key        = <type>
         while !input.is_empty() {
             let key: Ident = input.parse()?;
             if key == "crate_path" {
@@ -144,10 +497,54 @@ impl Parse for ImplTrailingArgs {
                 }
                 let _: Token![=] = input.parse()?;
                 args.init = Some(input.parse()?);
+            } else if key == "accessors" {
+                if args.accessors.is_some() {
+                    return Err(syn::Error::new(key.span(), "duplicate `accessors`"));
+                }
+                let _: Token![=] = input.parse()?;
+                args.accessors = Some(input.parse()?);
+            } else if key == "view" {
+                if args.view.is_some() {
+                    return Err(syn::Error::new(key.span(), "duplicate `view`"));
+                }
+                let _: Token![=] = input.parse()?;
+                args.view = Some(input.parse()?);
+            } else if key == "register" {
+                if args.register.is_some() {
+                    return Err(syn::Error::new(key.span(), "duplicate `register`"));
+                }
+                let _: Token![=] = input.parse()?;
+                args.register = Some(input.parse()?);
+            } else if key == "module" {
+                if args.module.is_some() {
+                    return Err(syn::Error::new(key.span(), "duplicate `module`"));
+                }
+                let _: Token![=] = input.parse()?;
+                args.module = Some(input.parse()?);
+            } else if key == "key" {
+                if args.key.is_some() {
+                    return Err(syn::Error::new(key.span(), "duplicate `key`"));
+                }
+                let _: Token![=] = input.parse()?;
+                args.key = Some(input.parse()?);
+            } else if key == "backend" {
+                let _: Token![=] = input.parse()?;
+                let backend: Ident = input.parse()?;
+
+                args.backend = if backend == "ctor" {
+                    Backend::Ctor
+                } else if backend == "linkme" {
+                    Backend::Linkme
+                } else {
+                    return Err(syn::Error::new(
+                        backend.span(),
+                        "expected `ctor` or `linkme`",
+                    ));
+                };
             } else {
                 return Err(syn::Error::new(
                     key.span(),
-                    "expected `crate_path = ...` or `init = ...`",
+                    "expected `crate_path = ...`, `init = ...`, `accessors = ...`, `view = ...`, `register = ...`, `backend = ...`, `module = ...` or `key = ...`",
                 ));
             }
 
@@ -166,6 +563,186 @@ impl Parse for ImplTrailingArgs {
 /// extobj!(impl MyObj { pub value: i32 });
 /// ```
 ///
+/// # Example generic object, one registry per instantiation.
+/// ```ignore
+/// extobj!(pub struct Ctx<T: 'static>);
+/// extobj!(impl Ctx<Request> { pub USER: Option<UserId> });
+/// ```
+///
+/// # Example object with `!Send` fields, the `ExtObj` is then `!Send + !Sync`.
+/// ```ignore
+/// extobj!(pub struct Gui, local);
+/// extobj!(impl Gui { pub WINDOW: Rc<Window> = init(Window::main) });
+/// ```
+///
+/// # Example process-wide instance.
+/// ```ignore
+/// // generates `AppCtx::global() -> &'static RwLock<ExtObj<AppCtx>>`,
+/// // created on first use
+/// extobj!(pub struct AppCtx, singleton);
+///
+/// AppCtx::global().write()[*THEME] = Theme::Dark;
+/// ```
+///
+/// # Example comparable object, the `ExtObj` is then `Eq + Hash`.
+/// ```ignore
+/// // every field must be `Eq + Hash`, checked at compile time
+/// extobj!(pub struct Key, comparable);
+/// extobj!(impl Key { pub ID: u32, pub TAGS: Vec<String> });
+///
+/// let keys: HashSet<ExtObj<Key>> = HashSet::new();
+/// ```
+///
+/// # Example sealed object, only its crate can declare fields.
+/// ```ignore
+/// extobj!(pub struct Internal, sealed);
+/// extobj!(impl Internal { pub HITS: u64 });
+///
+/// // in another crate, fails to compile
+/// extobj!(impl Internal { pub MISSES: u64 });
+/// ```
+///
+/// # Example keyed object, only the crates naming its key can declare fields.
+/// ```ignore
+/// extobj!(pub struct Host, key = plugin_api::Key);
+/// extobj!(impl Host { pub LOAD: f32 }, key = plugin_api::Key);
+/// ```
+///
+/// # Example custom default values.
+/// ```ignore
+/// extobj!(impl MyObj {
+///     pub RETRIES: u32 = 3,
+///     pub NAME: String = "anon".into(),
+///     // types without `Default` are created by an init function
+///     pub CLIENT: Client = init(|| Client::connect("localhost")),
+///     // fallible initializers are reported by `ExtObj::try_new`
+///     pub LOG: File = try_init(|| Ok(File::open("app.log")?)),
+///     // context initializers receive the argument of `ExtObj::new_with`
+///     pub PORT: u16 = init_with(|ctx| ctx.downcast_ref::<Config>().map_or(80, |c| c.port)),
+///     pub DB: Db = try_init_with(|ctx| Ok(Db::open(ctx.downcast_ref::<Config>())?)),
+///     // async initializers are awaited by `ExtObj::new_async`
+///     pub POOL: Pool = init_async(|| -> BoxFuture<'static, Pool> { Box::pin(Pool::connect()) }),
+/// });
+/// ```
+///
+/// # Example field attributes.
+/// ```ignore
+/// extobj!(impl MyObj {
+///     /// Number of requests served, forwarded to the generated static.
+///     pub REQUESTS: u64,
+///     #[cfg(feature = "metrics")]
+///     pub LATENCY: Histogram,
+/// });
+/// ```
+///
+/// # Example drop order.
+/// ```ignore
+/// // fields drop in reverse registration order, unless a priority is given:
+/// // a higher priority drops later, so the pool outlives its users
+/// extobj!(impl MyObj {
+///     #[drop_priority(1)]
+///     pub POOL: Pool,
+///     pub CONN: PooledConn = init(PooledConn::default),
+/// });
+/// ```
+///
+/// # Example optional fields.
+/// ```ignore
+/// // `Var<MyObj, Optional<Profile>>`, empty until set, without `Profile: Default`
+/// extobj!(impl MyObj {
+///     #[optional]
+///     pub PROFILE: Profile,
+/// });
+///
+/// obj[*PROFILE].set(Profile::load());
+/// let profile: Option<&Profile> = obj[*PROFILE].get();
+/// ```
+///
+/// # Example validators.
+/// ```ignore
+/// // `ExtObj::set_checked` and `ExtObj::validate_all` call the validator,
+/// // a `fn(&T) -> Result<(), E>` where `E` converts to a `BoxError`
+/// extobj!(impl MyObj {
+///     #[validate(|name: &String| if name.is_empty() { Err("empty") } else { Ok(()) })]
+///     pub NAME: String = "anon".into(),
+/// });
+/// ```
+///
+/// # Example field versions, written in binary snapshots.
+/// ```ignore
+/// // snapshots of an older version of the field are not loaded into it
+/// extobj!(impl MyObj {
+///     #[version(2)]
+///     pub SCORE: u64,
+/// });
+/// ```
+///
+/// # Example secret fields, requires the `zeroize` feature.
+/// ```ignore
+/// // the token is wiped before it is dropped
+/// extobj!(impl MyObj {
+///     #[zeroize]
+///     pub API_TOKEN: String,
+/// });
+/// ```
+///
+/// # Example read-only fields.
+/// ```ignore
+/// // other crates get `LEVEL: ReadOnlyVar<MyObj, u8>`, which only reads with
+/// // `Index`; this crate writes through `pub(crate) static LEVEL_MUT: Var<MyObj, u8>`
+/// extobj!(impl MyObj {
+///     #[readonly]
+///     pub LEVEL: u8,
+/// });
+///
+/// obj[*LEVEL_MUT] = 3;
+/// assert_eq!(obj[*LEVEL], 3);
+/// ```
+///
+/// # Example typed accessors.
+/// ```ignore
+/// // generates `trait CounterExt` implemented for `ExtObj<MyObj>` with
+/// // `hit_count()`, `hit_count_mut()` and `set_hit_count(value)`
+/// extobj!(impl MyObj { pub HIT_COUNT: AtomicUsize }, accessors = pub CounterExt);
+///
+/// obj.hit_count().fetch_add(1, Ordering::Relaxed);
+/// obj.set_hit_count(AtomicUsize::new(0));
+/// ```
+///
+/// # Example namespaced statics.
+/// ```ignore
+/// // generates `pub mod net { pub static PORT: Var<MyObj, u16>; ... }` and
+/// // `pub use self::net::NetExt;`
+/// extobj!(impl MyObj { pub PORT: u16 }, module = pub net, accessors = pub NetExt);
+///
+/// obj[*net::PORT] = 8080;
+/// ```
+///
+/// # Example borrow view.
+/// ```ignore
+/// // generates `struct Pos<'a> { pub x: &'a mut f32, pub y: &'a mut f32 }`
+/// extobj!(impl MyObj { pub X: f32, pub Y: f32 }, view = pub Pos);
+///
+/// let pos = Pos::from(&mut obj);
+/// std::mem::swap(pos.x, pos.y);
+/// ```
+///
+/// # Example explicit registration, without life-before-main.
+/// ```ignore
+/// extobj!(impl MyObj { pub HITS: u32 }, register = pub register_my_crate_fields);
+///
+/// fn main() {
+///     register_my_crate_fields();
+///     let obj = ExtObj::<MyObj>::new();
+///     assert_eq!(obj[*HITS], 0);
+/// }
+/// ```
+///
+/// # Example link-time registration, requires the `linkme` feature.
+/// ```ignore
+/// extobj!(impl MyObj { pub HITS: u32 }, backend = linkme);
+/// ```
+///
 /// # Example reexport the extobj crate.
 /// ```ignore
 /// // Custom crate name
@@ -175,50 +752,781 @@ impl Parse for ImplTrailingArgs {
 #[proc_macro]
 pub fn extobj(input: TokenStream) -> TokenStream {
     let Input {
-        kw_struct,
         name,
         fields,
         vis,
         crate_path,
         init,
+        mut accessors,
+        mut view,
+        mut register,
+        backend,
+        module,
+        local,
+        singleton,
+        comparable,
+        seal,
     } = parse_macro_input!(input as Input);
 
     let extobj = crate_path;
 
+    // `#[optional] id: ty` is stored as an `Optional<ty>`
+    let fields: Vec<Field> = fields
+        .into_iter()
+        .map(|mut field| {
+            if field.optional {
+                let ty = &field.ty;
+                field.ty = syn::parse_quote!(#extobj::Optional<#ty>);
+            }
+            if module.is_some() {
+                field.vis = in_module(field.vis);
+            }
+            field
+        })
+        .collect();
+
+    // `module = name` nests the statics in `mod name`, the accessor trait, the
+    // view and the registration function are re-exported next to it.
+    let mut reexports = Vec::new();
+
+    if let Some(module) = &module {
+        let module = &module.name;
+
+        for item in [&mut accessors, &mut view, &mut register]
+            .into_iter()
+            .flatten()
+        {
+            let ItemName { vis, name } = &*item;
+            reexports.push(quote!(#vis use self::#module::#name;));
+            item.vis = in_module(item.vis.clone());
+        }
+    }
+
+    let namespace = |items: proc_macro2::TokenStream| -> TokenStream {
+        match &module {
+            Some(ItemName { vis, name }) => quote! {
+                #vis mod #name {
+                    #[allow(unused_imports)]
+                    use super::*;
+
+                    #items
+                }
+
+                #( #reexports )*
+            },
+            None => items,
+        }
+        .into()
+    };
+
     let name = match name {
-        Name::Struct(ident) => quote!(#ident),
+        // `extobj!(struct Name);`
+        Name::Struct(ident, generics) => {
+            let args = StructTrailingArgs {
+                path: extobj,
+                local,
+                singleton,
+                comparable,
+                seal,
+            };
+            return struct_def(&vis, &ident, &generics, &args).into();
+        }
         Name::Impl(ty) => quote!(#ty),
     };
 
+    // fails to compile outside the crate of a sealed object, or without its key
+    let key = seal.key.map_or_else(|| quote!(()), |key| quote!(#key));
+    let seal = quote_spanned! {key.span()=>
+        const _: () = <#name>::__extobj_seal::<#key>();
+    };
+
     let init = init.unwrap_or_else(|| syn::parse_quote!({}));
 
-    if kw_struct.is_some() {
-        // `extobj!(struct Name);`
-        quote! {
-            #[derive(Copy, Clone)]
-            #vis struct #name;
+    // `extobj!(impl Name { vis id: ty, ... })`
+    let accessors = accessors.map(|accessors| accessors_trait(&extobj, &name, &fields, accessors));
+    let view = view.map(|view| view_struct(&extobj, &name, &fields, view));
 
-            impl #extobj::__ExtObjDef for #name {
-                #[inline(always)]
-                fn defs() -> &'static #extobj::Defs {
-                    static DEFS: #extobj::Defs = #extobj::RwLock::new(::std::vec::Vec::new());
-                    &DEFS
-                }
-            }
+    if (backend == Backend::Linkme || register.is_some())
+        && let Some(field) = fields.iter().find(|field| field.readonly)
+    {
+        return syn::Error::new(
+            field.id.span(),
+            "`#[readonly]` fields require the default `ctor` backend",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    if backend == Backend::Linkme {
+        if let Some(register) = register {
+            return syn::Error::new(register.name.span(), "`register` requires `backend = ctor`")
+                .to_compile_error()
+                .into();
         }
-    } else {
-        // `extobj!(impl Name { vis id: ty, ... })`
-        let vars = fields.into_iter().map(|(vis, id, ty)| {
-            quote! {
+
+        let vars = linked_vars(&extobj, &name, &fields, &init);
+
+        return namespace(quote! {
+            #seal
+            #vars
+            #accessors
+            #view
+        });
+    }
+
+    if let Some(register) = register {
+        let registration = registration_fn(&extobj, &name, &fields, &init, register);
+
+        return namespace(quote! {
+            #seal
+            #registration
+            #accessors
+            #view
+        });
+    }
+
+    let vars = fields.iter().map(|field| {
+        let Field {
+            attrs, vis, id, ty, ..
+        } = field;
+        let var = field.var_ident();
+        let descriptor = field.descriptor(&extobj, &name);
+
+        if !field.readonly {
+            return quote! {
+                #( #attrs )*
                 #[allow(non_upper_case_globals)]
                 #[#extobj::ctor::ctor(crate_path = #extobj::ctor)]
                 #vis static #id: #extobj::Var<#name, #ty> = {
                     #init;
-                    #extobj::Var::<#name, #ty>::__new()
+                    #extobj::Var::<#name, #ty>::__new(#descriptor)
                 };
+            };
+        }
+
+        let cfgs = field.cfgs();
+        let doc = format!("The mutable handle of the read-only field `{id}`.");
+
+        quote! {
+            #( #cfgs )*
+            #[doc = #doc]
+            #[allow(non_upper_case_globals)]
+            #[#extobj::ctor::ctor(crate_path = #extobj::ctor)]
+            pub(crate) static #var: #extobj::Var<#name, #ty> = {
+                #init;
+                #extobj::Var::<#name, #ty>::__new(#descriptor)
+            };
+
+            #( #attrs )*
+            #[allow(non_upper_case_globals)]
+            #vis static #id: #extobj::ReadOnlyVar<#name, #ty> =
+                #extobj::ReadOnlyVar::__new(|| *#var);
+        }
+    });
+    namespace(quote! {
+        #seal
+        #( #vars )*
+        #accessors
+        #view
+    })
+}
+
+/// Generates `LinkedVar` statics whose fields are collected in the `linkme`
+/// distributed slice of the extobj crate.
+fn linked_vars(
+    extobj: &Path,
+    name: &proc_macro2::TokenStream,
+    fields: &[Field],
+    init: &Expr,
+) -> proc_macro2::TokenStream {
+    let vars = fields.iter().map(|field| {
+        let Field {
+            attrs, vis, id, ty, ..
+        } = field;
+        let field = field.descriptor(extobj, name);
+        let linkme = quote!(#extobj::linkme);
+
+        quote! {
+            #( #attrs )*
+            #[allow(non_upper_case_globals)]
+            #vis static #id: #extobj::LinkedVar<#name, #ty> = {
+                #[#extobj::linkme::distributed_slice(#extobj::__private::LINKED_FIELDS)]
+                #[linkme(crate = #linkme)]
+                static ENTRY: #extobj::__private::LinkedField = #extobj::__private::LinkedField::new(
+                    ::std::any::TypeId::of::<#name>,
+                    || {
+                        #init;
+                        #extobj::Var::<#name, #ty>::__new(#field).__index()
+                    },
+                );
+
+                #extobj::LinkedVar::__new(&ENTRY)
+            };
+        }
+    });
+
+    quote! { #( #vars )* }
+}
+
+/// Generates `LazyVar` statics and the function `register` registering them,
+/// for targets without life-before-main.
+fn registration_fn(
+    extobj: &Path,
+    name: &proc_macro2::TokenStream,
+    fields: &[Field],
+    init: &Expr,
+    register: ItemName,
+) -> proc_macro2::TokenStream {
+    let ItemName {
+        vis: fn_vis,
+        name: fn_name,
+    } = register;
+    let fn_str = fn_name.to_string();
+
+    let vars = fields.iter().map(|field| {
+        let Field {
+            attrs, vis, id, ty, ..
+        } = field;
+
+        quote! {
+            #( #attrs )*
+            #[allow(non_upper_case_globals)]
+            #vis static #id: #extobj::LazyVar<#name, #ty> =
+                #extobj::LazyVar::__new(stringify!(#id), #fn_str);
+        }
+    });
+
+    let registrations = fields.iter().map(|field| {
+        let Field { id, ty, .. } = field;
+        let cfgs = field.cfgs();
+        let field = field.descriptor(extobj, name);
+
+        quote! {
+            #( #cfgs )*
+            #id.__set(#extobj::Var::<#name, #ty>::__new(#field));
+        }
+    });
+
+    let doc = format!(
+        "Registers the `{}` fields declared with `extobj!`. Must be called before they are used; calling it again does nothing.",
+        name.to_string().replace(' ', "")
+    );
+
+    quote! {
+        #( #vars )*
+
+        #[doc = #doc]
+        #fn_vis fn #fn_name() {
+            static ONCE: ::std::sync::Once = ::std::sync::Once::new();
+
+            ONCE.call_once(|| {
+                #init;
+                #( #registrations )*
+            });
+        }
+    }
+}
+
+/// Generates the marker type `ident` and its field registry.
+///
+/// A generic marker gets one registry per instantiation, so its type
+/// parameters must be `'static`.
+fn struct_def(
+    vis: &Visibility,
+    ident: &Ident,
+    generics: &Generics,
+    args: &StructTrailingArgs,
+) -> proc_macro2::TokenStream {
+    let StructTrailingArgs {
+        path: extobj,
+        local,
+        singleton,
+        comparable,
+        seal,
+    } = args;
+    let threading = threading(extobj, *local);
+    let comparability = comparability(extobj, *comparable);
+
+    if *singleton && (*local || !generics.params.is_empty()) {
+        return syn::Error::new(
+            ident.span(),
+            "`singleton` objects cannot be `local` nor generic",
+        )
+        .to_compile_error();
+    }
+
+    let singleton = singleton.then(|| {
+        quote! {
+            impl #ident {
+                /// The process-wide instance, created on first use.
+                #vis fn global() -> &'static #extobj::RwLock<#extobj::ExtObj<#ident>> {
+                    static GLOBAL: ::std::sync::LazyLock<#extobj::RwLock<#extobj::ExtObj<#ident>>> =
+                        ::std::sync::LazyLock::new(::std::default::Default::default);
+                    &GLOBAL
+                }
             }
-        });
-        quote! { #( #vars )* }
+        }
+    });
+
+    if generics.params.is_empty() {
+        let def = def_impl(extobj, ident, &threading, &comparability, seal);
+
+        return quote! {
+            #[derive(Copy, Clone)]
+            #vis struct #ident;
+
+            #def
+
+            #singleton
+        };
+    }
+
+    if let Some(lifetime) = generics.lifetimes().next() {
+        return syn::Error::new_spanned(lifetime, "extobj types cannot have lifetime parameters")
+            .to_compile_error();
+    }
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let params = generics.params.iter().filter_map(|param| match param {
+        GenericParam::Type(ty) => Some(&ty.ident),
+        _ => None,
+    });
+    let sealed = seal.sealed_const();
+    let seal_fn = seal.seal_fn(extobj);
+
+    quote! {
+        #vis struct #ident #impl_generics (
+            ::std::marker::PhantomData<fn() -> (#( #params, )*)>
+        ) #where_clause;
+
+        impl #impl_generics ::std::clone::Clone for #ident #ty_generics #where_clause {
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        impl #impl_generics ::std::marker::Copy for #ident #ty_generics #where_clause {}
+
+        impl #impl_generics #extobj::__ExtObjDef for #ident #ty_generics #where_clause {
+            const NAME: &'static str = stringify!(#ident);
+            #sealed
+            type Threading = #threading;
+            type Comparability = #comparability;
+
+            #[inline]
+            fn defs() -> &'static #extobj::Defs {
+                #extobj::Defs::generic::<Self>()
+            }
+        }
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #seal_fn
+        }
+    }
+}
+
+impl Seal {
+    /// `SEALED = true` for the objects not accepting fields registered at
+    /// runtime.
+    fn sealed_const(&self) -> Option<proc_macro2::TokenStream> {
+        (self.sealed || self.key.is_some()).then(|| {
+            quote!(
+                const SEALED: bool = true;
+            )
+        })
+    }
+
+    /// The `__extobj_seal::<K>()` function every `extobj!(impl)` block calls
+    /// with its key, or `()`. It is `pub(crate)` for sealed objects and
+    /// requires `K` to be the key of keyed objects.
+    fn seal_fn(&self, extobj: &Path) -> proc_macro2::TokenStream {
+        let vis = if self.sealed {
+            quote!(pub(crate))
+        } else {
+            quote!(pub)
+        };
+        let bound = self
+            .key
+            .as_ref()
+            .map(|key| quote!(: #extobj::__private::Is<#key>));
+
+        quote! {
+            #[doc(hidden)]
+            #[inline(always)]
+            #vis const fn __extobj_seal<K #bound>() {}
+        }
+    }
+}
+
+/// The visibility, in the module of `module = name`, of an item declared
+/// with `vis` next to the macro invocation.
+fn in_module(vis: Visibility) -> Visibility {
+    match &vis {
+        Visibility::Inherited => syn::parse_quote!(pub(super)),
+        Visibility::Restricted(r) if r.in_token.is_none() && r.path.is_ident("self") => {
+            syn::parse_quote!(pub(super))
+        }
+        Visibility::Restricted(r) if r.in_token.is_none() && r.path.is_ident("super") => {
+            syn::parse_quote!(pub(in super::super))
+        }
+        _ => vis,
+    }
+}
+
+/// The `Threading` marker of an object.
+fn threading(extobj: &Path, local: bool) -> proc_macro2::TokenStream {
+    if local {
+        quote!(#extobj::__private::Local)
+    } else {
+        quote!(#extobj::__private::Shared)
+    }
+}
+
+/// The `Comparability` marker of an object.
+fn comparability(extobj: &Path, comparable: bool) -> proc_macro2::TokenStream {
+    if comparable {
+        quote!(#extobj::__private::Comparable)
+    } else {
+        quote!(#extobj::__private::Incomparable)
+    }
+}
+
+/// Implements `__ExtObjDef` for the non-generic type `ident`, with its own
+/// field registry.
+fn def_impl(
+    extobj: &Path,
+    ident: &Ident,
+    threading: &proc_macro2::TokenStream,
+    comparability: &proc_macro2::TokenStream,
+    seal: &Seal,
+) -> proc_macro2::TokenStream {
+    let sealed = seal.sealed_const();
+    let seal_fn = seal.seal_fn(extobj);
+
+    quote! {
+        impl #extobj::__ExtObjDef for #ident {
+            const NAME: &'static str = stringify!(#ident);
+            #sealed
+            type Threading = #threading;
+            type Comparability = #comparability;
+
+            #[inline(always)]
+            fn defs() -> &'static #extobj::Defs {
+                static DEFS: #extobj::Defs = #extobj::Defs::new();
+                &DEFS
+            }
+        }
+
+        impl #ident {
+            #seal_fn
+        }
+    }
+}
+
+/// Makes a struct with named fields extensible by other crates: adds the
+/// hidden field `__ext: ExtObj<Self>`, implements the object definition for
+/// the struct and generates the `ext()` and `ext_mut()` accessors.
+///
+/// The hidden field is initialized with `Default::default()`. The arguments
+/// `local` and `crate_path = <path>` have the meaning they have for
+/// `extobj!(struct Name, ...)`.
+///
+/// # Example
+/// ```ignore
+/// #[extobj::extensible]
+/// pub struct Document {
+///     pub title: String,
+/// }
+///
+/// // in any crate
+/// extobj!(impl Document { pub WORDS: usize });
+///
+/// let mut doc = Document { title: "draft".into(), __ext: Default::default() };
+/// doc.ext_mut()[*WORDS] = 250;
+/// ```
+#[proc_macro_attribute]
+pub fn extensible(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = proc_macro2::TokenStream::from(args);
+    let StructTrailingArgs {
+        path: extobj,
+        local,
+        singleton,
+        comparable,
+        seal,
+    } = match syn::parse2(quote!(, #args)) {
+        Ok(args) => args,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let mut item = parse_macro_input!(item as syn::ItemStruct);
+    let ident = &item.ident;
+    let vis = &item.vis;
+
+    if singleton {
+        return syn::Error::new(ident.span(), "`singleton` is not supported by `extensible`")
+            .to_compile_error()
+            .into();
+    }
+
+    if !item.generics.params.is_empty() {
+        return syn::Error::new_spanned(&item.generics, "extensible structs cannot be generic")
+            .to_compile_error()
+            .into();
+    }
+
+    let field = syn::Field::parse_named.parse2(quote! {
+        #[doc(hidden)]
+        #vis __ext: #extobj::ExtObj<#ident>
+    });
+
+    match (&mut item.fields, field) {
+        (syn::Fields::Named(fields), Ok(field)) => fields.named.push(field),
+        (_, Err(e)) => return e.to_compile_error().into(),
+        _ => {
+            return syn::Error::new(ident.span(), "extensible structs must have named fields")
+                .to_compile_error()
+                .into();
+        }
+    }
+
+    let def = def_impl(
+        &extobj,
+        ident,
+        &threading(&extobj, local),
+        &comparability(&extobj, comparable),
+        &seal,
+    );
+
+    quote! {
+        #item
+
+        #def
+
+        impl #ident {
+            /// The extension fields attached by other crates.
+            #[inline]
+            #vis fn ext(&self) -> &#extobj::ExtObj<Self> {
+                &self.__ext
+            }
+
+            /// Mutably borrows the extension fields attached by other crates.
+            #[inline]
+            #vis fn ext_mut(&mut self) -> &mut #extobj::ExtObj<Self> {
+                &mut self.__ext
+            }
+        }
     }
     .into()
 }
+
+/// Generates the extension trait `accessors` with a getter, a `_mut` getter
+/// and a `set_` setter for every field, implemented for `ExtObj<name>` and
+/// `TrackedExtObj<name>`.
+///
+/// The setters go through `set`, or `set_checked` for the fields with a
+/// validator, so writes are validated, observed and tracked in one place.
+fn accessors_trait(
+    extobj: &Path,
+    name: &proc_macro2::TokenStream,
+    fields: &[Field],
+    accessors: ItemName,
+) -> proc_macro2::TokenStream {
+    let ItemName {
+        vis,
+        name: trait_name,
+    } = accessors;
+    let mut decls = Vec::new();
+    let mut impls = Vec::new();
+
+    for field in fields {
+        let Field { id, ty, .. } = field;
+        let cfgs = field.cfgs();
+        let id_str = id.to_string();
+        let get = snake_ident(id);
+        let get_mut = format_ident!("{}_mut", get);
+        let set = format_ident!("set_{}", get);
+
+        let get_doc = format!("Returns a reference to the `{id_str}` field.");
+
+        // the trait is public, read-only fields only get a getter
+        if field.readonly {
+            let var = field.var_ident();
+
+            decls.push(quote! {
+                #( #cfgs )*
+                #[doc = #get_doc]
+                fn #get(&self) -> &#ty;
+            });
+
+            impls.push(quote! {
+                #( #cfgs )*
+                #[inline]
+                #[track_caller]
+                fn #get(&self) -> &#ty {
+                    self.get(*#var)
+                }
+            });
+
+            continue;
+        }
+
+        let get_mut_doc = format!("Returns a mutable reference to the `{id_str}` field.");
+        let (set_ret, set_body, set_doc) = match field.validate {
+            Some(_) => (
+                quote!(-> ::core::result::Result<(), #extobj::ValidationError>),
+                quote!(self.set_checked(*#id, value)),
+                format!(
+                    "Sets the `{id_str}` field if its validator accepts the value, dropping the previous value."
+                ),
+            ),
+            None => (
+                quote!(),
+                quote!(self.set(*#id, value)),
+                format!("Sets the `{id_str}` field, dropping the previous value."),
+            ),
+        };
+
+        decls.push(quote! {
+            #( #cfgs )*
+            #[doc = #get_doc]
+            fn #get(&self) -> &#ty;
+
+            #( #cfgs )*
+            #[doc = #get_mut_doc]
+            fn #get_mut(&mut self) -> &mut #ty;
+
+            #( #cfgs )*
+            #[doc = #set_doc]
+            fn #set(&mut self, value: #ty) #set_ret;
+        });
+
+        impls.push(quote! {
+            #( #cfgs )*
+            #[inline]
+            #[track_caller]
+            fn #get(&self) -> &#ty {
+                self.get(*#id)
+            }
+
+            #( #cfgs )*
+            #[inline]
+            #[track_caller]
+            fn #get_mut(&mut self) -> &mut #ty {
+                self.get_mut(*#id)
+            }
+
+            #( #cfgs )*
+            #[inline]
+            #[track_caller]
+            fn #set(&mut self, value: #ty) #set_ret {
+                #set_body
+            }
+        });
+    }
+
+    let trait_doc = format!(
+        "Typed accessors of the `{}` fields declared with `extobj!`.",
+        name.to_string().replace(' ', "")
+    );
+
+    quote! {
+        #[doc = #trait_doc]
+        #vis trait #trait_name {
+            #( #decls )*
+        }
+
+        impl #trait_name for #extobj::ExtObj<#name> {
+            #( #impls )*
+        }
+
+        impl #trait_name for #extobj::TrackedExtObj<#name> {
+            #( #impls )*
+        }
+    }
+}
+
+/// Generates the struct `view` holding a mutable reference to every field,
+/// created with `From<&mut ExtObj<name>>`.
+fn view_struct(
+    extobj: &Path,
+    name: &proc_macro2::TokenStream,
+    fields: &[Field],
+    view: ItemName,
+) -> proc_macro2::TokenStream {
+    let ItemName {
+        vis,
+        name: view_name,
+    } = view;
+    let members = fields.iter().map(|field| {
+        let Field { vis, id, ty, .. } = field;
+        let cfgs = field.cfgs();
+        let member = snake_ident(id);
+
+        // read-only fields are only mutable in the declaring crate
+        let vis = if field.readonly {
+            quote!(pub(crate))
+        } else {
+            quote!(#vis)
+        };
+
+        quote!(#( #cfgs )* #vis #member: &'a mut #ty)
+    });
+    let borrows = fields.iter().map(|field| {
+        let var = field.var_ident();
+        let cfgs = field.cfgs();
+        let member = snake_ident(&field.id);
+        quote!(#( #cfgs )* #member: borrows.take(*#var))
+    });
+    let doc = format!(
+        "Mutable borrows of the `{}` fields declared with `extobj!`.",
+        name.to_string().replace(' ', "")
+    );
+
+    quote! {
+        #[doc = #doc]
+        #vis struct #view_name<'a> {
+            #( #members, )*
+        }
+
+        impl<'a> ::std::convert::From<&'a mut #extobj::ExtObj<#name>> for #view_name<'a> {
+            #[track_caller]
+            fn from(obj: &'a mut #extobj::ExtObj<#name>) -> Self {
+                let mut borrows = #extobj::__private::Borrows::new(obj);
+                Self {
+                    #( #borrows, )*
+                }
+            }
+        }
+    }
+}
+
+/// The `snake_case` method or member name of a field.
+fn snake_ident(id: &Ident) -> Ident {
+    let id_str = id.to_string();
+
+    match id_str.strip_prefix("r#") {
+        Some(raw) => Ident::new_raw(&snake_case(raw), id.span()),
+        None => Ident::new(&snake_case(&id_str), id.span()),
+    }
+}
+
+/// Converts a field identifier (usually `SCREAMING_CASE`) to `snake_case`.
+fn snake_case(id: &str) -> String {
+    let chars: Vec<char> = id.chars().collect();
+    let mut out = String::with_capacity(id.len() + 4);
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_lower) {
+                out.push('_');
+            }
+        }
+
+        out.extend(c.to_lowercase());
+    }
+
+    out
+}
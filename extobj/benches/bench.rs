@@ -12,6 +12,10 @@ fn ext_obj_benchmark(c: &mut Criterion) {
 
     c.bench_function("ext_obj::get_i32", |b| b.iter(|| black_box(&obj).get(*V)));
 
+    c.bench_function("ext_obj::get_unchecked_i32", |b| {
+        b.iter(|| unsafe { black_box(&obj).get_unchecked(*V) })
+    });
+
     c.bench_function("ext_obj::get_mut_i32", |b| {
         b.iter(|| {
             let _ = black_box(black_box(&mut obj).get_mut(*V));
@@ -0,0 +1,107 @@
+use core::{alloc::Layout, ptr::NonNull};
+
+/// Allocates the memory of the fields of an [`ExtObj`](crate::ExtObj).
+///
+/// Implement this trait to route the field allocations through a tracking or
+/// pooling allocator, then create the objects with
+/// [`ExtObj::new_in`](crate::ExtObj::new_in). With the `allocator_api`
+/// feature (nightly), every [`std::alloc::Allocator`] is a `FieldAllocator`.
+///
+/// # Safety
+/// `alloc` must return memory valid for reads and writes of `layout` until it
+/// is passed to `dealloc`. Zero-sized layouts must be supported.
+///
+/// # Example
+///
+/// ```
+/// use extobj::{ExtObj, FieldAllocator, Global};
+/// use std::{
+///     alloc::Layout,
+///     ptr::NonNull,
+///     sync::atomic::{AtomicUsize, Ordering},
+/// };
+///
+/// #[derive(Default)]
+/// struct Tracking(AtomicUsize);
+///
+/// unsafe impl FieldAllocator for &Tracking {
+///     fn alloc(&self, layout: Layout) -> NonNull<u8> {
+///         self.0.fetch_add(layout.size(), Ordering::Relaxed);
+///         Global.alloc(layout)
+///     }
+///
+///     unsafe fn dealloc(&self, ptr: NonNull<u8>, layout: Layout) {
+///         self.0.fetch_sub(layout.size(), Ordering::Relaxed);
+///         unsafe { Global.dealloc(ptr, layout) }
+///     }
+/// }
+///
+/// extobj::extobj!(struct Session);
+/// extobj::extobj!(impl Session { pub TOKEN: [u64; 4] });
+///
+/// let tracking = Tracking::default();
+/// let session = ExtObj::<Session, _>::new_in(&tracking);
+/// assert_eq!(tracking.0.load(Ordering::Relaxed), 32);
+///
+/// drop(session);
+/// assert_eq!(tracking.0.load(Ordering::Relaxed), 0);
+/// ```
+pub unsafe trait FieldAllocator {
+    /// Allocates memory for `layout`, aborting if the allocation fails.
+    fn alloc(&self, layout: Layout) -> NonNull<u8>;
+
+    /// Releases memory returned by [`FieldAllocator::alloc`].
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator with `layout`.
+    unsafe fn dealloc(&self, ptr: NonNull<u8>, layout: Layout);
+}
+
+/// The global allocator, used by default.
+#[cfg(not(feature = "allocator_api"))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Global;
+
+#[cfg(feature = "allocator_api")]
+pub use alloc::alloc::Global;
+
+#[cfg(not(feature = "allocator_api"))]
+unsafe impl FieldAllocator for Global {
+    #[inline]
+    fn alloc(&self, layout: Layout) -> NonNull<u8> {
+        if layout.size() == 0 {
+            // a dangling, well-aligned pointer, as used by `Box` for ZSTs
+            return unsafe {
+                NonNull::new_unchecked(core::ptr::without_provenance_mut(layout.align()))
+            };
+        }
+
+        match NonNull::new(unsafe { alloc::alloc::alloc(layout) }) {
+            Some(ptr) => ptr,
+            None => alloc::alloc::handle_alloc_error(layout),
+        }
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() != 0 {
+            unsafe { alloc::alloc::dealloc(ptr.as_ptr(), layout) }
+        }
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+unsafe impl<A: core::alloc::Allocator> FieldAllocator for A {
+    #[inline]
+    fn alloc(&self, layout: Layout) -> NonNull<u8> {
+        match self.allocate(layout) {
+            Ok(ptr) => ptr.cast(),
+            Err(_) => alloc::alloc::handle_alloc_error(layout),
+        }
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { self.deallocate(ptr, layout) }
+    }
+}
@@ -0,0 +1,49 @@
+use crate::ExtObj;
+#[cfg(not(feature = "allocator_api"))]
+use crate::FieldAllocator;
+use bumpalo::Bump;
+#[cfg(not(feature = "allocator_api"))]
+use std::{alloc::Layout, ptr::NonNull};
+
+/// An extension object whose fields are allocated in a [`Bump`] arena,
+/// created with `ExtObj::new_in(&bump)`.
+///
+/// Dropping the object drops its fields, the arena frees their memory when it
+/// is reset or dropped.
+///
+/// # Example
+///
+/// ```
+/// use bumpalo::Bump;
+/// use extobj::ArenaExtObj;
+///
+/// extobj::extobj!(struct Particle);
+/// extobj::extobj!(impl Particle { pub SPEED: f32 = 1.0 });
+///
+/// let mut frame = Bump::new();
+///
+/// for _ in 0..3 {
+///     let particles: Vec<ArenaExtObj<Particle>> = (0..100)
+///         .map(|_| ArenaExtObj::new_in(&frame))
+///         .collect();
+///
+///     assert_eq!(particles[0][*SPEED], 1.0);
+///     drop(particles);
+///     frame.reset();
+/// }
+/// ```
+pub type ArenaExtObj<'bump, O> = ExtObj<O, &'bump Bump>;
+
+// With `allocator_api`, `&Bump` implements `Allocator` instead.
+#[cfg(not(feature = "allocator_api"))]
+unsafe impl FieldAllocator for &Bump {
+    #[inline]
+    fn alloc(&self, layout: Layout) -> NonNull<u8> {
+        self.alloc_layout(layout)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, _ptr: NonNull<u8>, _layout: Layout) {
+        // freed when the arena is reset or dropped
+    }
+}
@@ -0,0 +1,79 @@
+use crate::{__ExtObjDef, ExtObj, Extended, Var};
+use bevy_ecs::{
+    change_detection::{DetectChangesMut, Mut},
+    component::{Component, StorageType},
+};
+
+/// Extension objects are components, so engine plugins can add fields to
+/// the components of other crates. Objects declared `local` are not.
+impl<O: __ExtObjDef> Component for ExtObj<O>
+where
+    Self: Send + Sync,
+{
+    const STORAGE_TYPE: StorageType = StorageType::Table;
+}
+
+impl<T: Send + Sync + 'static, O: __ExtObjDef> Component for Extended<T, O>
+where
+    ExtObj<O>: Send + Sync,
+{
+    const STORAGE_TYPE: StorageType = StorageType::Table;
+}
+
+/// Field-level change detection for the extension objects stored as
+/// components.
+///
+/// Bevy tracks the changes of a component as a whole: any mutable access
+/// marks it changed. Setting a field through this trait only does when the
+/// value differs, so `Changed<ExtObj<O>>` filters skip the components whose
+/// fields were rewritten with the same values.
+///
+/// # Example
+///
+/// ```
+/// use extobj::{DetectFieldChanges, ExtObj, bevy_ecs::prelude::*};
+///
+/// extobj::extobj!(struct Stats);
+/// extobj::extobj!(impl Stats { pub ARMOR: u32 });
+///
+/// let mut world = World::new();
+/// let entity = world.spawn(ExtObj::<Stats>::new()).id();
+///
+/// let mut stats = world.get_mut::<ExtObj<Stats>>(entity).unwrap();
+/// assert!(!stats.set_field_if_neq(*ARMOR, 0));
+/// assert!(stats.set_field_if_neq(*ARMOR, 5));
+/// ```
+pub trait DetectFieldChanges<O: __ExtObjDef> {
+    /// Sets the value of `var`, calling its observers and marking the
+    /// component changed, unless it is already equal to `value`. Returns
+    /// whether the value was set.
+    fn set_field_if_neq<T: PartialEq>(&mut self, var: Var<O, T>, value: T) -> bool;
+}
+
+impl<O: __ExtObjDef> DetectFieldChanges<O> for Mut<'_, ExtObj<O>>
+where
+    ExtObj<O>: Component,
+{
+    fn set_field_if_neq<T: PartialEq>(&mut self, var: Var<O, T>, value: T) -> bool {
+        if self.bypass_change_detection()[var] == value {
+            return false;
+        }
+
+        ExtObj::set(self, var, value);
+        true
+    }
+}
+
+impl<U, O: __ExtObjDef> DetectFieldChanges<O> for Mut<'_, Extended<U, O>>
+where
+    Extended<U, O>: Component,
+{
+    fn set_field_if_neq<T: PartialEq>(&mut self, var: Var<O, T>, value: T) -> bool {
+        if self.bypass_change_detection().ext()[var] == value {
+            return false;
+        }
+
+        self.ext_mut().set(var, value);
+        true
+    }
+}
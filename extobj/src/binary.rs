@@ -0,0 +1,245 @@
+use crate::{__ExtObjDef, BinaryError, ExtObj, FieldAllocator, error::BoxError};
+use parking_lot::RwLock;
+use serde::{Serialize, de::DeserializeOwned};
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    io::{Read, Write},
+    mem,
+};
+
+/// The version of the snapshot layout, written first.
+const FORMAT: u8 = 1;
+
+/// A field of a snapshot: its stable key, its version and its value encoded
+/// on its own, so unknown fields can be skipped.
+type Entry<K> = (K, u32, Vec<u8>);
+
+/// The migrations of the fields of every object, by object type, stable key
+/// and version migrated from.
+type Migrations = HashMap<(TypeId, String, u32), Migration>;
+
+static MIGRATIONS: RwLock<Option<Migrations>> = RwLock::new(None);
+
+/// A type-erased `fn(Old) -> New` converting the encoded value of a field
+/// from one version to another.
+#[derive(Clone, Copy)]
+struct Migration {
+    to: u32,
+    call: MigrateFn,
+    f: fn(),
+}
+
+/// Casts a migration back to its typed signature and calls it with a decoded
+/// value, returning the encoded result.
+type MigrateFn = unsafe fn(fn(), &[u8]) -> Result<Vec<u8>, BoxError>;
+
+/// Registers the conversion of the values of a field saved with the version
+/// `from` to the version `to`, applied by [`ExtObj::load`].
+///
+/// `key` is the stable key of the field, `crate::NAME`. Migrations are
+/// chained, so a value of version 1 is loaded in a field of version 3 with
+/// the migrations from 1 to 2 and from 2 to 3. Values without a path to the
+/// current version are skipped.
+///
+/// # Panics
+/// Panics if `to` is not greater than `from`.
+///
+/// # Example
+///
+/// ```
+/// mod v1 {
+///     extobj::extobj!(pub struct Player);
+///     extobj::extobj!(impl Player { #[version(1)] pub SCORE: u8 });
+/// }
+///
+/// mod v2 {
+///     extobj::extobj!(pub struct Player);
+///     extobj::extobj!(impl Player { #[version(2)] pub SCORE: u32 });
+/// }
+///
+/// let mut old = extobj::ExtObj::<v1::Player>::new();
+/// old[*v1::SCORE] = 7;
+///
+/// let mut bytes = Vec::new();
+/// old.save(&mut bytes).unwrap();
+///
+/// // scores are now counted in hundreds
+/// let key = extobj::registry::<v2::Player>().next().unwrap().key();
+/// extobj::register_migration::<v2::Player, u8, u32>(&key, 1, 2, |score| score as u32 * 100);
+///
+/// let new = extobj::ExtObj::<v2::Player>::load(&bytes[..]).unwrap();
+/// assert_eq!(new[*v2::SCORE], 700);
+/// ```
+pub fn register_migration<O: __ExtObjDef, Old: DeserializeOwned, New: Serialize>(
+    key: &str,
+    from: u32,
+    to: u32,
+    migrate: fn(Old) -> New,
+) {
+    unsafe fn call<Old: DeserializeOwned, New: Serialize>(
+        f: fn(),
+        bytes: &[u8],
+    ) -> Result<Vec<u8>, BoxError> {
+        let f: fn(Old) -> New = unsafe { mem::transmute(f) };
+        Ok(postcard::to_allocvec(&f(postcard::from_bytes(bytes)?))?)
+    }
+
+    assert!(
+        to > from,
+        "cannot migrate field `{key}` from version {from} to {to}"
+    );
+
+    let migration = Migration {
+        to,
+        call: call::<Old, New>,
+        // SAFETY: transmuted back by `call::<Old, New>`
+        f: unsafe { mem::transmute::<fn(Old) -> New, fn()>(migrate) },
+    };
+
+    MIGRATIONS
+        .write()
+        .get_or_insert_with(HashMap::new)
+        .insert((TypeId::of::<O>(), key.to_owned(), from), migration);
+}
+
+/// Converts the encoded value of the field `key` from `version` to `current`
+/// with the registered migrations, or returns `None` when there is no path.
+fn migrate<O: 'static>(
+    key: &str,
+    mut version: u32,
+    current: u32,
+    mut value: Vec<u8>,
+) -> Option<Result<Vec<u8>, BoxError>> {
+    while version != current {
+        // the lock is released before calling the migration
+        let migration =
+            *MIGRATIONS
+                .read()
+                .as_ref()?
+                .get(&(TypeId::of::<O>(), key.to_owned(), version))?;
+
+        if migration.to > current {
+            return None;
+        }
+
+        // SAFETY: the migration was registered with the types of its versions
+        match unsafe { (migration.call)(migration.f, &value) } {
+            Ok(migrated) => value = migrated,
+            Err(e) => return Some(Err(e)),
+        }
+
+        version = migration.to;
+    }
+
+    Some(Ok(value))
+}
+
+impl<O: __ExtObjDef, A: FieldAllocator> ExtObj<O, A> {
+    /// Writes a binary snapshot of the serializable fields, read back with
+    /// [`ExtObj::load`].
+    ///
+    /// The snapshot starts with the name of the object and lists the fields
+    /// by stable key, `crate::NAME`, and version, see `#[version(n)]`. Fields
+    /// whose type does not implement `Serialize` are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extobj::extobj!(struct Save);
+    /// extobj::extobj!(impl Save { pub LEVEL: u32, pub HERO: String });
+    ///
+    /// let mut save = extobj::ExtObj::<Save>::new();
+    /// save[*LEVEL] = 3;
+    /// save[*HERO] = "Ada".into();
+    ///
+    /// let mut bytes = Vec::new();
+    /// save.save(&mut bytes).unwrap();
+    ///
+    /// let loaded = extobj::ExtObj::<Save>::load(&bytes[..]).unwrap();
+    /// assert_eq!(loaded[*LEVEL], 3);
+    /// assert_eq!(loaded[*HERO], "Ada");
+    /// ```
+    pub fn save(&self, mut writer: impl Write) -> Result<(), BinaryError> {
+        let fields = O::defs().with(|defs| {
+            defs.iter()
+                .zip(&self.0)
+                .filter_map(|(def, slot)| {
+                    let serialize = def.serialize?;
+
+                    // SAFETY: the serializer was registered with the field type
+                    let value = unsafe { &*serialize(def.value(slot)) };
+                    let bytes = postcard::to_allocvec(value).map_err(BinaryError::encoding);
+
+                    Some(bytes.map(|bytes| (def.key().to_string(), def.version, bytes)))
+                })
+                .collect::<Result<Vec<Entry<String>>, _>>()
+        })?;
+
+        let bytes =
+            postcard::to_allocvec(&(FORMAT, O::NAME, fields)).map_err(BinaryError::encoding)?;
+        writer.write_all(&bytes).map_err(BinaryError::io)
+    }
+}
+
+impl<O: __ExtObjDef> ExtObj<O> {
+    /// Reads a snapshot written by [`ExtObj::save`].
+    ///
+    /// Fields saved with an older `#[version(n)]` are converted by the
+    /// migrations registered with [`register_migration`]. Unknown fields, and
+    /// fields of another version which cannot be migrated, are skipped.
+    /// Fields missing from the snapshot keep their default value. As with
+    /// deserialization, the values are not validated until
+    /// [`ExtObj::validate_all`] is called.
+    pub fn load(mut reader: impl Read) -> Result<Self, BinaryError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).map_err(BinaryError::io)?;
+
+        let (format, object, fields): (u8, &str, Vec<Entry<&str>>) =
+            postcard::from_bytes(&bytes).map_err(BinaryError::encoding)?;
+
+        if format != FORMAT {
+            return Err(BinaryError::format(format));
+        }
+
+        if object != O::NAME {
+            return Err(BinaryError::object(O::NAME, object));
+        }
+
+        let mut obj = Self::new();
+
+        for (key, version, value) in fields {
+            // the lock is released before deserializing the value, which may
+            // construct other objects.
+            let field = O::defs().with(|defs| {
+                defs.iter()
+                    .zip(&mut obj.0)
+                    .find(|(def, _)| def.key().matches(key))
+                    .and_then(|(def, slot)| {
+                        Some((
+                            unsafe { def.value_mut(slot) },
+                            def.deserialize?,
+                            def.version,
+                        ))
+                    })
+            });
+
+            let Some((ptr, deserialize, current)) = field else {
+                continue;
+            };
+
+            let value = match migrate::<O>(key, version, current, value) {
+                Some(value) => value.map_err(|e| BinaryError::field(key, e))?,
+                None => continue,
+            };
+
+            let mut de = postcard::Deserializer::from_bytes(&value);
+            let mut erased = <dyn erased_serde::Deserializer>::erase(&mut de);
+
+            // SAFETY: the deserializer was registered with the field type
+            unsafe { deserialize(ptr, &mut erased) }.map_err(|e| BinaryError::field(key, e))?;
+        }
+
+        Ok(obj)
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{__ExtObjDef, DebugField, ExtObj, Var, missing_field, registry::Slot};
+use std::{
+    cell::{BorrowError, BorrowMutError, Ref, RefCell, RefMut, UnsafeCell},
+    fmt::{self, Debug, Formatter},
+};
+
+/// An extension object whose fields are borrowed like `RefCell`s, each one on
+/// its own.
+///
+/// Code holding a shared reference can mutate different fields at the same
+/// time, without a lock over the whole object. Like `RefCell`, borrowing a
+/// field already mutably borrowed panics, and the `try_` methods return an
+/// error instead. Writes through [`ExtObjCell::borrow_mut`] do not call the
+/// observers of the field.
+///
+/// # Example
+///
+/// ```
+/// use extobj::ExtObjCell;
+///
+/// extobj::extobj!(struct Frame);
+/// extobj::extobj!(impl Frame {
+///     pub INPUT: Vec<u8>,
+///     pub OUTPUT: Vec<u8>,
+/// });
+///
+/// let frame = ExtObjCell::<Frame>::new();
+/// frame.borrow_mut(*INPUT).extend([1, 2, 3]);
+///
+/// let input = frame.borrow(*INPUT);
+/// let mut output = frame.borrow_mut(*OUTPUT);
+/// output.extend(input.iter().map(|b| b * 2));
+///
+/// assert!(frame.try_borrow_mut(*OUTPUT).is_err());
+/// drop(output);
+/// assert_eq!(*frame.borrow(*OUTPUT), [2, 4, 6]);
+/// ```
+pub struct ExtObjCell<O: __ExtObjDef> {
+    obj: UnsafeCell<ExtObj<O>>,
+    /// The borrow state of each field, guarding the slot at the same index.
+    borrows: Box<[RefCell<()>]>,
+}
+
+impl<O: __ExtObjDef> ExtObjCell<O> {
+    /// Creates a new `ExtObjCell` with every registered field initialized to
+    /// its default value.
+    ///
+    /// # Panics
+    /// Panics if a fallible initializer fails, see [`ExtObj::try_new`].
+    pub fn new() -> Self {
+        Self::from(ExtObj::new())
+    }
+
+    /// Immutably borrows the value of the given variable, until the returned
+    /// `Ref` is dropped.
+    ///
+    /// # Panics
+    /// Panics if the field is mutably borrowed, or if the variable was
+    /// registered after this object was created.
+    #[track_caller]
+    pub fn borrow<T>(&self, var: Var<O, T>) -> Ref<'_, T> {
+        let borrow = self.borrow_state(var, false).borrow();
+
+        // SAFETY: the shared borrow of the field is tracked by `borrow`
+        Ref::map(borrow, |_| unsafe { &*self.value(var) })
+    }
+
+    /// Mutably borrows the value of the given variable, until the returned
+    /// `RefMut` is dropped.
+    ///
+    /// # Panics
+    /// Panics if the field is borrowed, or if the variable was registered
+    /// after this object was created.
+    #[track_caller]
+    pub fn borrow_mut<T>(&self, var: Var<O, T>) -> RefMut<'_, T> {
+        let borrow = self.borrow_state(var, true).borrow_mut();
+
+        // SAFETY: the exclusive borrow of the field is tracked by `borrow`
+        RefMut::map(borrow, |_| unsafe { &mut *self.value(var) })
+    }
+
+    /// Immutably borrows the value of the given variable, or returns an error
+    /// if the field is mutably borrowed.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
+    #[track_caller]
+    pub fn try_borrow<T>(&self, var: Var<O, T>) -> Result<Ref<'_, T>, BorrowError> {
+        let borrow = self.borrow_state(var, false).try_borrow()?;
+
+        // SAFETY: the shared borrow of the field is tracked by `borrow`
+        Ok(Ref::map(borrow, |_| unsafe { &*self.value(var) }))
+    }
+
+    /// Mutably borrows the value of the given variable, or returns an error if
+    /// the field is borrowed.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
+    #[track_caller]
+    pub fn try_borrow_mut<T>(&self, var: Var<O, T>) -> Result<RefMut<'_, T>, BorrowMutError> {
+        let borrow = self.borrow_state(var, true).try_borrow_mut()?;
+
+        // SAFETY: the exclusive borrow of the field is tracked by `borrow`
+        Ok(RefMut::map(borrow, |_| unsafe { &mut *self.value(var) }))
+    }
+
+    /// Mutably borrows the whole object, which is statically known to have
+    /// no borrowed field.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut ExtObj<O> {
+        self.obj.get_mut()
+    }
+
+    /// Consumes the cell, returning the object.
+    #[inline]
+    pub fn into_inner(self) -> ExtObj<O> {
+        self.obj.into_inner()
+    }
+
+    #[track_caller]
+    fn borrow_state<T>(&self, var: Var<O, T>, _write: bool) -> &RefCell<()> {
+        #[cfg(feature = "metrics")]
+        O::defs().count_access(var.0, _write);
+
+        match self.borrows.get(var.0) {
+            Some(borrow) => borrow,
+            None => missing_field(var.0),
+        }
+    }
+
+    /// The address of the value of the field at `index`, without borrowing the
+    /// other fields.
+    ///
+    /// # Safety
+    /// `index` must be in bounds.
+    #[inline]
+    unsafe fn slot(&self, index: usize) -> *mut Slot {
+        // `as_mut_ptr` does not invalidate the references to the other slots
+        unsafe { (*self.obj.get()).0.as_mut_ptr().add(index) }
+    }
+
+    /// # Safety
+    /// The field of `var` must be borrowed through its borrow state.
+    #[inline]
+    unsafe fn value<T>(&self, var: Var<O, T>) -> *mut T {
+        // SAFETY: the slot was created for a field of type `T`
+        unsafe { Slot::raw::<T>(self.slot(var.0)) }
+    }
+}
+
+impl<O: __ExtObjDef> Default for ExtObjCell<O> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<O: __ExtObjDef> From<ExtObj<O>> for ExtObjCell<O> {
+    fn from(obj: ExtObj<O>) -> Self {
+        Self {
+            borrows: obj.0.iter().map(|_| RefCell::new(())).collect(),
+            obj: UnsafeCell::new(obj),
+        }
+    }
+}
+
+/// Mutably borrowed fields are displayed as `<borrowed>`.
+impl<O: __ExtObjDef> Debug for ExtObjCell<O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        O::defs().with(|defs| {
+            let mut s = f.debug_struct(O::NAME);
+            let mut non_exhaustive = false;
+
+            for (index, (def, borrow)) in defs.iter().zip(&*self.borrows).enumerate() {
+                match (def.debug, borrow.try_borrow()) {
+                    (Some(debug), Ok(_borrow)) => {
+                        // SAFETY: the field is borrowed until the end of the arm
+                        let value = unsafe { def.value(&*self.slot(index)) };
+                        s.field(def.name, &DebugField(value, debug));
+                    }
+                    (Some(_), Err(_)) => {
+                        s.field(def.name, &format_args!("<borrowed>"));
+                    }
+                    (None, _) => non_exhaustive = true,
+                }
+            }
+
+            if non_exhaustive {
+                s.finish_non_exhaustive()
+            } else {
+                s.finish()
+            }
+        })
+    }
+}
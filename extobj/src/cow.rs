@@ -0,0 +1,240 @@
+use crate::{
+    __ExtObjDef, DebugField, ExtObj, Global, Var, missing_field,
+    registry::{FieldDef, Slot, drop_order},
+    unsupported,
+};
+use std::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    mem::{self, ManuallyDrop},
+    ops::{Index, IndexMut},
+    sync::Arc,
+};
+
+/// A copy-on-write extension object: cloning it shares every field, and a
+/// shared field is cloned on its first mutation.
+///
+/// This makes snapshots of large objects cheap, the copies only paying for the
+/// fields where they diverge. Mutating a shared field whose type does not
+/// implement `Clone` panics.
+///
+/// # Example
+///
+/// ```
+/// use extobj::CowExtObj;
+///
+/// extobj::extobj!(struct Document);
+/// extobj::extobj!(impl Document {
+///     pub TITLE: String,
+///     pub LINES: Vec<String>,
+/// });
+///
+/// let mut draft = CowExtObj::<Document>::new();
+/// draft[*LINES].push("hello".into());
+///
+/// let snapshot = draft.clone();
+/// draft[*TITLE].push_str("draft");
+///
+/// assert!(draft.is_shared(*LINES));
+/// assert!(!draft.is_shared(*TITLE));
+/// assert_eq!(snapshot[*TITLE], "");
+/// ```
+pub struct CowExtObj<O: __ExtObjDef> {
+    slots: Vec<Arc<Shared>>,
+    _marker: PhantomData<(O, O::Threading)>,
+}
+
+/// A field value shared by copies, owning its slot.
+struct Shared {
+    slot: Slot,
+    def: FieldDef,
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        // SAFETY: the slot was created by the definition with `Global`
+        unsafe { self.def.destroy(self.slot, &Global) }
+    }
+}
+
+impl<O: __ExtObjDef> CowExtObj<O> {
+    /// Creates a new object with every registered field initialized to its
+    /// default value.
+    ///
+    /// # Panics
+    /// Panics if a fallible initializer fails.
+    pub fn new() -> Self {
+        ExtObj::new().into()
+    }
+
+    /// Immutably borrows the value of the given variable.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
+    #[inline]
+    #[track_caller]
+    pub fn get<T>(&self, var: Var<O, T>) -> &T {
+        match self.try_get(var) {
+            Some(v) => v,
+            None => missing_field(var.0),
+        }
+    }
+
+    /// Immutably borrows the value of the given variable, or returns `None` if
+    /// the variable was registered after this object was created.
+    #[inline]
+    pub fn try_get<T>(&self, var: Var<O, T>) -> Option<&T> {
+        self.slots
+            .get(var.0)
+            .map(|shared| unsafe { shared.slot.get() })
+    }
+
+    /// Mutably borrows the value of the given variable, cloning it first when
+    /// it is shared with a copy of this object.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created,
+    /// or if the value is shared and its type does not implement `Clone`.
+    #[track_caller]
+    pub fn get_mut<T>(&mut self, var: Var<O, T>) -> &mut T {
+        let Some(shared) = self.slots.get_mut(var.0) else {
+            missing_field(var.0)
+        };
+
+        if Arc::get_mut(shared).is_none() {
+            let def = shared.def;
+
+            let Some(slot) = (unsafe { def.clone_slot(&shared.slot, &Global) }) else {
+                unsupported::<O>(def.name, "Clone")
+            };
+
+            *shared = Arc::new(Shared { slot, def });
+        }
+
+        match Arc::get_mut(shared) {
+            Some(shared) => unsafe { shared.slot.get_mut() },
+            None => unreachable!("the value was just cloned"),
+        }
+    }
+
+    /// Returns `true` when the value of the given variable is shared with a
+    /// copy of this object.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
+    #[track_caller]
+    pub fn is_shared<T>(&self, var: Var<O, T>) -> bool {
+        match self.slots.get(var.0) {
+            Some(shared) => Arc::strong_count(shared) > 1,
+            None => missing_field(var.0),
+        }
+    }
+
+    /// Converts this object to an `ExtObj`, cloning the shared values.
+    ///
+    /// # Panics
+    /// Panics if a shared value does not implement `Clone`.
+    pub fn into_ext_obj(mut self) -> ExtObj<O> {
+        let mut obj = ExtObj::from_slots(Vec::with_capacity(self.slots.len()), Global);
+
+        for shared in mem::take(&mut self.slots) {
+            let slot = match Arc::try_unwrap(shared) {
+                // the value is moved to the object, which drops it
+                Ok(shared) => ManuallyDrop::new(shared).slot,
+                Err(shared) => match unsafe { shared.def.clone_slot(&shared.slot, &Global) } {
+                    Some(slot) => slot,
+                    None => unsupported::<O>(shared.def.name, "Clone"),
+                },
+            };
+
+            obj.0.push(slot);
+        }
+
+        obj
+    }
+}
+
+impl<O: __ExtObjDef> From<ExtObj<O>> for CowExtObj<O> {
+    fn from(mut obj: ExtObj<O>) -> Self {
+        let slots = mem::take(&mut obj.0);
+
+        let slots = O::defs().with(|defs| {
+            defs.iter()
+                .zip(slots)
+                .map(|(&def, slot)| Arc::new(Shared { slot, def }))
+                .collect()
+        });
+
+        Self {
+            slots,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Shares every field with the copy.
+impl<O: __ExtObjDef> Clone for CowExtObj<O> {
+    fn clone(&self) -> Self {
+        Self {
+            slots: self.slots.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<O: __ExtObjDef> Default for CowExtObj<O> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<O: __ExtObjDef> Debug for CowExtObj<O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct(O::NAME);
+        let mut non_exhaustive = false;
+
+        for shared in &self.slots {
+            match shared.def.debug {
+                Some(debug) => {
+                    let value = unsafe { shared.def.value(&shared.slot) };
+                    s.field(shared.def.name, &DebugField(value, debug));
+                }
+                None => non_exhaustive = true,
+            }
+        }
+
+        if non_exhaustive {
+            s.finish_non_exhaustive()
+        } else {
+            s.finish()
+        }
+    }
+}
+
+impl<O: __ExtObjDef> Drop for CowExtObj<O> {
+    fn drop(&mut self) {
+        let mut slots: Vec<_> = mem::take(&mut self.slots).into_iter().map(Some).collect();
+
+        // the slots do not lock the registry when dropped
+        O::defs().with(|defs| drop_order(defs, slots.len(), |i| drop(slots[i].take())));
+    }
+}
+
+impl<O: __ExtObjDef, T> Index<Var<O, T>> for CowExtObj<O> {
+    type Output = T;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, index: Var<O, T>) -> &Self::Output {
+        self.get(index)
+    }
+}
+
+impl<O: __ExtObjDef, T> IndexMut<Var<O, T>> for CowExtObj<O> {
+    #[inline]
+    #[track_caller]
+    fn index_mut(&mut self, index: Var<O, T>) -> &mut Self::Output {
+        self.get_mut(index)
+    }
+}
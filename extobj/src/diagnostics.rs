@@ -0,0 +1,40 @@
+use crate::{__ExtObjDef, registry::loaded_defs};
+
+/// The lifecycle counters of an object type, see [`stats`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    /// The objects currently alive.
+    pub live: usize,
+    /// The objects created since the start of the process.
+    pub created: usize,
+    /// The objects dropped since the start of the process.
+    pub dropped: usize,
+}
+
+/// Returns the number of `ExtObj<O>` created and dropped so far.
+///
+/// A `live` count growing over time in a long-running service points to
+/// leaked objects.
+///
+/// # Example
+///
+/// ```
+/// extobj::extobj!(struct Request);
+/// extobj::extobj!(impl Request { pub USER: String });
+///
+/// let request = extobj::ExtObj::<Request>::new();
+/// drop(extobj::ExtObj::<Request>::new());
+///
+/// let stats = extobj::stats::<Request>();
+/// assert_eq!((stats.live, stats.created, stats.dropped), (1, 2, 1));
+/// # drop(request);
+/// ```
+pub fn stats<O: __ExtObjDef>() -> Stats {
+    let (created, dropped) = loaded_defs::<O>().counts();
+
+    Stats {
+        live: created.saturating_sub(dropped),
+        created,
+        dropped,
+    }
+}
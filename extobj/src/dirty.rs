@@ -0,0 +1,223 @@
+use crate::{
+    __ExtObjDef, ExtObj, ExtObjInitError, FieldAllocator, Global, ValidationError, Var, VarId,
+};
+use std::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    mem,
+    ops::{Deref, Index, IndexMut},
+};
+
+/// An [`ExtObj`] recording which fields were mutably borrowed since the last
+/// [`TrackedExtObj::clear_dirty`], for incremental persistence or network
+/// replication.
+///
+/// Reads go through `Deref<Target = ExtObj<O>>`; every mutable access marks
+/// the field dirty, whether or not the value changes. Fields with interior
+/// mutability are marked with [`TrackedExtObj::mark_dirty`].
+///
+/// # Example
+///
+/// ```
+/// use extobj::TrackedExtObj;
+///
+/// extobj::extobj!(struct Player);
+/// extobj::extobj!(impl Player {
+///     pub HEALTH: u32 = 100,
+///     pub NAME: String,
+/// });
+///
+/// let mut player = TrackedExtObj::<Player>::new();
+/// player[*HEALTH] -= 10;
+///
+/// assert!(player.is_dirty(*HEALTH));
+/// assert!(!player.is_dirty(*NAME));
+/// assert_eq!(player.dirty_vars().count(), 1);
+///
+/// player.clear_dirty();
+/// assert!(!player.is_dirty(*HEALTH));
+/// ```
+pub struct TrackedExtObj<O: __ExtObjDef, A: FieldAllocator = Global> {
+    obj: ExtObj<O, A>,
+    /// One bit per field, by registration index.
+    dirty: Vec<u64>,
+}
+
+impl<O: __ExtObjDef> TrackedExtObj<O> {
+    /// Creates a new object with every registered field initialized to its
+    /// default value, and no dirty field.
+    ///
+    /// # Panics
+    /// Panics if a fallible initializer fails, see [`TrackedExtObj::try_new`].
+    pub fn new() -> Self {
+        ExtObj::new().into()
+    }
+
+    /// Creates a new object, reporting the field whose initializer failed.
+    pub fn try_new() -> Result<Self, ExtObjInitError> {
+        ExtObj::try_new().map(Into::into)
+    }
+}
+
+impl<O: __ExtObjDef, A: FieldAllocator> TrackedExtObj<O, A> {
+    /// Mutably borrows the value of the given variable, marking it dirty.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
+    #[inline]
+    #[track_caller]
+    pub fn get_mut<T>(&mut self, var: Var<O, T>) -> &mut T {
+        let value = self.obj.get_mut(var);
+        set_bit(&mut self.dirty, var.0);
+        value
+    }
+
+    /// Mutably borrows the value of the given variable, marking it dirty, or
+    /// returns `None` if the variable was registered after this object was
+    /// created.
+    #[inline]
+    pub fn try_get_mut<T>(&mut self, var: Var<O, T>) -> Option<&mut T> {
+        let value = self.obj.try_get_mut(var)?;
+        set_bit(&mut self.dirty, var.0);
+        Some(value)
+    }
+
+    /// Sets the value of the given variable, marking it dirty, then calls the
+    /// observers registered with [`on_change`](crate::on_change).
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
+    #[inline]
+    #[track_caller]
+    pub fn set<T>(&mut self, var: Var<O, T>, value: T) {
+        self.obj.set(var, value);
+        set_bit(&mut self.dirty, var.0);
+    }
+
+    /// Sets the value of the given variable if its validator accepts it, see
+    /// [`ExtObj::set_checked`], marking it dirty.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
+    #[track_caller]
+    pub fn set_checked<T>(&mut self, var: Var<O, T>, value: T) -> Result<(), ValidationError> {
+        self.obj.set_checked(var, value)?;
+        set_bit(&mut self.dirty, var.0);
+        Ok(())
+    }
+
+    /// Replaces the value of the given variable, marking it dirty, and
+    /// returns the previous one.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
+    #[inline]
+    #[track_caller]
+    pub fn replace<T>(&mut self, var: Var<O, T>, value: T) -> T {
+        mem::replace(self.get_mut(var), value)
+    }
+
+    /// Marks the given variable dirty, for fields mutated through a shared
+    /// reference.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
+    #[track_caller]
+    pub fn mark_dirty<T>(&mut self, var: Var<O, T>) {
+        self.get_mut(var);
+    }
+
+    /// Returns `true` when the given variable was mutably borrowed since the
+    /// last [`TrackedExtObj::clear_dirty`].
+    #[inline]
+    pub fn is_dirty<T>(&self, var: Var<O, T>) -> bool {
+        self.dirty
+            .get(var.0 / 64)
+            .is_some_and(|word| word & (1 << (var.0 % 64)) != 0)
+    }
+
+    /// The dirty variables, in registration order.
+    pub fn dirty_vars(&self) -> impl Iterator<Item = VarId<O>> + '_ {
+        self.dirty.iter().enumerate().flat_map(|(i, &word)| {
+            (0..64)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| VarId(i * 64 + bit, PhantomData))
+        })
+    }
+
+    /// Marks every field clean.
+    #[inline]
+    pub fn clear_dirty(&mut self) {
+        self.dirty.fill(0);
+    }
+
+    /// Returns the object, dropping the dirty marks.
+    #[inline]
+    pub fn into_inner(self) -> ExtObj<O, A> {
+        self.obj
+    }
+}
+
+#[inline]
+fn set_bit(bits: &mut Vec<u64>, index: usize) {
+    let word = index / 64;
+
+    if word >= bits.len() {
+        bits.resize(word + 1, 0);
+    }
+
+    bits[word] |= 1 << (index % 64);
+}
+
+impl<O: __ExtObjDef, A: FieldAllocator> From<ExtObj<O, A>> for TrackedExtObj<O, A> {
+    fn from(obj: ExtObj<O, A>) -> Self {
+        Self {
+            obj,
+            dirty: Vec::new(),
+        }
+    }
+}
+
+impl<O: __ExtObjDef, A: FieldAllocator + Default> Default for TrackedExtObj<O, A> {
+    #[inline]
+    fn default() -> Self {
+        ExtObj::default().into()
+    }
+}
+
+impl<O: __ExtObjDef, A: FieldAllocator> Debug for TrackedExtObj<O, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrackedExtObj")
+            .field("obj", &self.obj)
+            .field("dirty", &self.dirty_vars().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Read-only access to the object, mutable access marks fields dirty.
+impl<O: __ExtObjDef, A: FieldAllocator> Deref for TrackedExtObj<O, A> {
+    type Target = ExtObj<O, A>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.obj
+    }
+}
+
+impl<O: __ExtObjDef, A: FieldAllocator, T> Index<Var<O, T>> for TrackedExtObj<O, A> {
+    type Output = T;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, index: Var<O, T>) -> &Self::Output {
+        self.obj.get(index)
+    }
+}
+
+impl<O: __ExtObjDef, A: FieldAllocator, T> IndexMut<Var<O, T>> for TrackedExtObj<O, A> {
+    #[inline]
+    #[track_caller]
+    fn index_mut(&mut self, index: Var<O, T>) -> &mut Self::Output {
+        self.get_mut(index)
+    }
+}
@@ -1,4 +1,14 @@
-use std::marker::PhantomData;
+use crate::{FieldAllocator, Global};
+use alloc::boxed::Box;
+use core::{
+    alloc::Layout,
+    any::type_name,
+    fmt::{self, Debug, DebugStruct, Formatter},
+    marker::PhantomData,
+    mem::ManuallyDrop,
+    ops::Deref,
+    ptr::{self, NonNull},
+};
 
 /// A type-erased, owned value.
 ///
@@ -8,6 +18,9 @@ use std::marker::PhantomData;
 /// be re-supplied by the caller when accessing the value (via `get`,
 /// `get_mut`, or `into_inner`).
 ///
+/// The value is allocated by `A`, the global allocator by default (see
+/// `new_in`).
+///
 /// # Safety
 ///
 /// * The object must **always** be dropped with the same type `T` that was
@@ -16,7 +29,8 @@ use std::marker::PhantomData;
 ///   created with `new::<T>`) results in **instant undefined behavior**.
 /// * In `debug_assertions` builds, the actual `TypeId` is stored, and an
 ///   assertion will catch mismatched types, but this safety check is **not present**
-///   in release builds to optimize performance.
+///   in release builds to optimize performance, unless the `checked` feature
+///   is enabled.
 ///
 /// # Example
 ///
@@ -28,27 +42,38 @@ use std::marker::PhantomData;
 /// assert_eq!(s, "hello world");
 /// ```
 #[repr(C)] // Ensures predictable memory layout for compatibility with raw pointers
-pub struct DynObj {
+pub struct DynObj<A: FieldAllocator = Global> {
     /// Pointer to the heap-allocated value.
     ///
     /// * Type is erased to `*mut ()` to hide the concrete type `T` in the struct.
     /// * Must be cast back to the original type (`*mut T`) before dereferencing.
-    /// * Points to memory allocated by `Box::into_raw`.
+    /// * Points to memory allocated by `alloc` with `layout`.
     data: *mut (),
 
     /// Type-erased destructor function pointer.
     ///
-    /// * Stores a function that reconstructs the original `Box<T>` and drops it.
-    /// * Ensures proper cleanup of the heap-allocated value when `DynObj` is dropped.
+    /// * Stores a function that drops the value of type `T` in place.
+    /// * The memory is released separately, with `alloc`.
     drop: unsafe fn(*mut ()),
 
+    /// Layout of `T`, needed to release the memory.
+    layout: Layout,
+
+    /// Adds the type name and the value to the `Debug` output, for the
+    /// objects created with `new_debug`.
+    debug: Option<unsafe fn(*const (), &mut DebugStruct<'_, '_>)>,
+
+    /// The allocator owning the memory of the value.
+    alloc: A,
+
     /// Stores the `TypeId` of the value in `data` for type safety checks.
     ///
-    /// * Only included when `debug_assertions` is enabled (debug builds).
+    /// * Only included when `debug_assertions` is enabled (debug builds), or
+    ///   with the `checked` feature.
     /// * Used to verify that the type `T` provided in `get`, `get_mut`, or `into_inner`
     ///   matches the type used in `new`.
-    #[cfg(debug_assertions)]
-    tid: std::any::TypeId,
+    #[cfg(any(debug_assertions, feature = "checked"))]
+    tid: core::any::TypeId,
 
     /// Marker to indicate ownership of a heap-allocated value.
     ///
@@ -62,7 +87,7 @@ pub struct DynObj {
 impl DynObj {
     /// Constructs a new `DynObj` that owns `val`.
     ///
-    /// * Moves the provided value `val` onto the heap using `Box`.
+    /// * Moves the provided value `val` onto the heap using the global allocator.
     /// * Erases the type `T` at compile time, storing only a raw pointer and a destructor.
     /// * The caller must remember the type `T` for later access via `get`, `get_mut`, or `into_inner`.
     ///
@@ -79,32 +104,214 @@ impl DynObj {
     where
         T: Send + Sync + 'static,
     {
-        // Allocate the value on the heap and convert to a raw pointer, erasing the type
-        let b = Box::into_raw(Box::new(val)) as *mut ();
+        Self::new_in(val, Global)
+    }
+
+    /// Constructs a new `DynObj` that owns `val`, whose `Debug` output shows
+    /// the type name and the value, to log type-erased payloads.
+    ///
+    /// Objects created with `new` are formatted as `DynObj { .. }`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let obj = extobj::DynObj::new_debug(7u8);
+    /// assert_eq!(format!("{obj:?}"), r#"DynObj { type: "u8", value: 7 }"#);
+    /// ```
+    pub fn new_debug<T>(val: T) -> Self
+    where
+        T: Debug + Send + Sync + 'static,
+    {
+        Self::new_debug_in(val, Global)
+    }
+
+    /// Consumes `DynObj` and returns the value in its `Box`, without moving
+    /// it, for APIs expecting a `Box`.
+    ///
+    /// # Safety
+    /// * Same requirements as `get`: the type `T` must match the type used in `new`.
+    /// * Using the wrong type causes **undefined behavior**.
+    /// * In debug builds, a `TypeId` check ensures type safety; no check in release builds
+    ///   without the `checked` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let obj = extobj::DynObj::from(Box::new([0u8; 1024]));
+    /// let b: Box<[u8; 1024]> = unsafe { obj.into_box() };
+    /// assert_eq!(b.len(), 1024);
+    /// ```
+    pub unsafe fn into_box<T>(self) -> Box<T>
+    where
+        T: Send + 'static,
+    {
+        // Check type safety in debug builds, or with the `checked` feature
+        #[cfg(any(debug_assertions, feature = "checked"))]
+        assert_eq!(
+            self.tid,
+            core::any::TypeId::of::<T>(),
+            "Type mismatch in DynObj::into_box"
+        );
+
+        // The memory now belongs to the box, `Global` allocates like `Box`
+        let this = ManuallyDrop::new(self);
+        unsafe { Box::from_raw(this.data as *mut T) }
+    }
+
+    /// Consumes `DynObj` and returns the pointer to the value with a function
+    /// dropping it and releasing its memory, so a C host can hold the value
+    /// and drop it later. The value is rebuilt with `from_raw_parts`.
+    ///
+    /// # Safety
+    /// * Same requirements as `get`: the type `T` must match the type used in `new`.
+    /// * Using the wrong type causes **undefined behavior**.
+    /// * In debug builds, a `TypeId` check ensures type safety; no check in release builds
+    ///   without the `checked` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let obj = extobj::DynObj::new(String::from("owned by C"));
+    /// let (data, release) = unsafe { obj.into_raw_parts::<String>() };
+    ///
+    /// // later, on the C side
+    /// unsafe { release(data) };
+    /// ```
+    pub unsafe fn into_raw_parts<T>(self) -> (*mut (), unsafe extern "C" fn(*mut ()))
+    where
+        T: Send + 'static,
+    {
+        /// Drops the value and releases its memory, like its `Box`.
+        ///
+        /// # Safety
+        /// * `p` must come from `into_raw_parts::<T>`, and not be used afterwards.
+        unsafe extern "C" fn release<T>(p: *mut ()) {
+            drop(unsafe { Box::from_raw(p as *mut T) })
+        }
+
+        (
+            Box::into_raw(unsafe { self.into_box::<T>() }) as *mut (),
+            release::<T>,
+        )
+    }
+
+    /// Rebuilds a `DynObj` from the pointer returned by `into_raw_parts`,
+    /// taking back the ownership of the value. The release function is not
+    /// needed, the type is named instead.
+    ///
+    /// # Safety
+    /// * `data` must come from `into_raw_parts::<T>` and not have been released.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let obj = extobj::DynObj::new(5u64);
+    /// let (data, _) = unsafe { obj.into_raw_parts::<u64>() };
+    ///
+    /// let obj = unsafe { extobj::DynObj::from_raw_parts::<u64>(data) };
+    /// assert_eq!(unsafe { *obj.get::<u64>() }, 5);
+    /// ```
+    pub unsafe fn from_raw_parts<T>(data: *mut ()) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        // SAFETY: `Global` allocates like `Box`
+        unsafe { Self::from_ptr(data as *mut T, Global) }
+    }
+}
+
+/// Takes ownership of a boxed value without moving it out of its allocation.
+impl<T> From<Box<T>> for DynObj
+where
+    T: Send + Sync + 'static,
+{
+    fn from(val: Box<T>) -> Self {
+        // SAFETY: `Global` allocates like `Box`
+        unsafe { Self::from_ptr(Box::into_raw(val), Global) }
+    }
+}
 
+impl<A: FieldAllocator> DynObj<A> {
+    /// Constructs a new `DynObj` that owns `val`, allocated by `alloc`.
+    ///
+    /// * Same as `new`, but the memory of the value comes from `alloc` and is
+    ///   returned to it when the `DynObj` is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let obj = extobj::DynObj::new_in(7u8, extobj::Global);
+    /// assert_eq!(unsafe { *obj.get::<u8>() }, 7);
+    /// ```
+    pub fn new_in<T>(val: T, alloc: A) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        Self::erase_in(val, alloc)
+    }
+
+    /// Moves `val` into memory from `alloc`, erasing its type. The `Send` and
+    /// `Sync` bounds are left to the callers.
+    fn erase_in<T>(val: T, alloc: A) -> Self
+    where
+        T: 'static,
+    {
+        // Allocate memory for `T` and move the value in, erasing the type
+        let b = alloc.alloc(Layout::new::<T>()).as_ptr() as *mut T;
+        unsafe { ptr::write(b, val) };
+
+        // SAFETY: `b` holds a `T` allocated by `alloc`
+        unsafe { Self::from_ptr(b, alloc) }
+    }
+
+    /// Takes ownership of the `T` at `data`.
+    ///
+    /// # Safety
+    /// * `data` must point to a live `T` allocated by `alloc` with the layout
+    ///   of `T`.
+    unsafe fn from_ptr<T>(data: *mut T, alloc: A) -> Self
+    where
+        T: 'static,
+    {
         /// Type-erased drop function for `T`.
         ///
-        /// * Takes a raw pointer, casts it back to `*mut T`, and drops it as a `Box<T>`.
-        /// * Ensures proper cleanup of the heap-allocated value.
+        /// * Takes a raw pointer, casts it back to `*mut T`, and drops the value in place.
+        /// * The memory is not released, see `Drop for DynObj`.
         ///
         /// # Safety
-        /// * `p` must be the same pointer returned by `Box::into_raw::<T>` during construction.
+        /// * `p` must point to a live `T` written during construction.
         unsafe fn dropper<T>(p: *mut ()) {
-            unsafe {
-                // Reconstruct the `Box<T>` from the raw pointer and drop it
-                drop(Box::from_raw(p as *mut T));
-            }
+            unsafe { ptr::drop_in_place(p as *mut T) }
         }
 
         Self {
-            data: b,            // Store the raw pointer to the heap-allocated value
-            drop: dropper::<T>, // Store the type-specific drop function
-            #[cfg(debug_assertions)]
-            tid: std::any::TypeId::of::<T>(), // Store the TypeId for debug type checking
-            _marker: PhantomData, // Initialize the ownership marker
+            data: data as *mut (),      // Store the raw pointer to the heap-allocated value
+            drop: dropper::<T>,         // Store the type-specific drop function
+            layout: Layout::new::<T>(), // Store the layout to release the memory
+            debug: None,                // Only known for the values created with `new_debug`
+            alloc,                      // Keep the allocator owning the memory
+            #[cfg(any(debug_assertions, feature = "checked"))]
+            tid: core::any::TypeId::of::<T>(), // Store the TypeId for debug type checking
+            _marker: PhantomData,       // Initialize the ownership marker
         }
     }
 
+    /// Constructs a new `DynObj` that owns `val`, allocated by `alloc`, whose
+    /// `Debug` output shows the type name and the value.
+    pub fn new_debug_in<T>(val: T, alloc: A) -> Self
+    where
+        T: Debug + Send + Sync + 'static,
+    {
+        let mut obj = Self::new_in(val, alloc);
+        obj.debug = Some(debugger::<T>);
+        obj
+    }
+
+    /// The allocator owning the memory of the value.
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
     /// Immutably borrows the contained value as a reference of type `T`.
     ///
     /// * Returns a reference to the heap-allocated value, cast to `&T`.
@@ -112,7 +319,8 @@ impl DynObj {
     /// # Safety
     /// * The caller must ensure `T` matches the type used in `new`.
     /// * Using the wrong type causes **undefined behavior**.
-    /// * In debug builds, a `TypeId` check ensures type safety; no check occurs in release builds.
+    /// * In debug builds, a `TypeId` check ensures type safety; no check occurs in release builds
+    ///   without the `checked` feature.
     ///
     /// # Examples
     ///
@@ -125,11 +333,11 @@ impl DynObj {
     where
         T: Send + 'static,
     {
-        // Check type safety in debug builds
-        #[cfg(debug_assertions)]
-        debug_assert_eq!(
+        // Check type safety in debug builds, or with the `checked` feature
+        #[cfg(any(debug_assertions, feature = "checked"))]
+        assert_eq!(
             self.tid,
-            std::any::TypeId::of::<T>(),
+            core::any::TypeId::of::<T>(),
             "Type mismatch in DynObj::get"
         );
 
@@ -144,7 +352,8 @@ impl DynObj {
     /// # Safety
     /// * Same requirements as `get`: the type `T` must match the type used in `new`.
     /// * Using the wrong type causes **undefined behavior**.
-    /// * In debug builds, a `TypeId` check ensures type safety; no check in release builds.
+    /// * In debug builds, a `TypeId` check ensures type safety; no check in release builds
+    ///   without the `checked` feature.
     ///
     /// # Examples
     ///
@@ -157,11 +366,11 @@ impl DynObj {
     where
         T: Send + 'static,
     {
-        // Check type safety in debug builds
-        #[cfg(debug_assertions)]
-        debug_assert_eq!(
+        // Check type safety in debug builds, or with the `checked` feature
+        #[cfg(any(debug_assertions, feature = "checked"))]
+        assert_eq!(
             self.tid,
-            std::any::TypeId::of::<T>(),
+            core::any::TypeId::of::<T>(),
             "Type mismatch in DynObj::get_mut"
         );
 
@@ -177,7 +386,8 @@ impl DynObj {
     /// # Safety
     /// * Same requirements as `get`: the type `T` must match the type used in `new`.
     /// * Using the wrong type causes **undefined behavior**.
-    /// * In debug builds, a `TypeId` check ensures type safety; no check in release builds.
+    /// * In debug builds, a `TypeId` check ensures type safety; no check in release builds
+    ///   without the `checked` feature.
     ///
     /// # Examples
     ///
@@ -190,36 +400,84 @@ impl DynObj {
     where
         T: Send + 'static,
     {
-        // Check type safety in debug builds
-        #[cfg(debug_assertions)]
-        debug_assert_eq!(
+        // Check type safety in debug builds, or with the `checked` feature
+        #[cfg(any(debug_assertions, feature = "checked"))]
+        assert_eq!(
             self.tid,
-            std::any::TypeId::of::<T>(),
+            core::any::TypeId::of::<T>(),
             "Type mismatch in DynObj::into_inner"
         );
 
-        // Reconstruct the value from the raw pointer and move it to the stack
-        let out = unsafe { *Box::from_raw(self.data as *mut T) };
+        unsafe { self.take() }
+    }
 
+    /// Moves the value out and releases its memory.
+    ///
+    /// # Safety
+    /// * `T` must be the type of the value.
+    unsafe fn take<T>(self) -> T {
         // Prevent the destructor from running to avoid double-free
-        std::mem::forget(self);
+        let this = ManuallyDrop::new(self);
+
+        unsafe {
+            // Move the value to the stack, then release its memory
+            let out = ptr::read(this.data as *const T);
+            let alloc = ptr::read(&this.alloc);
+            alloc.dealloc(NonNull::new_unchecked(this.data as *mut u8), this.layout);
+            out
+        }
+    }
+}
+
+impl<A: FieldAllocator> DynObj<A> {
+    /// Formats the object as the struct `name`, with the type name and the
+    /// value when they were recorded.
+    fn fmt_as(&self, name: &str, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct(name);
+
+        match self.debug {
+            Some(debug) => {
+                // SAFETY: `debug` was recorded for the type of the value
+                unsafe { debug(self.data, &mut s) };
+                s.finish()
+            }
+            None => s.finish_non_exhaustive(),
+        }
+    }
+}
 
-        out
+impl<A: FieldAllocator> Debug for DynObj<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_as("DynObj", f)
     }
 }
 
+/// Adds the type name and the value of `T` to a `Debug` output.
+///
+/// # Safety
+/// * `p` must point to a live `T`.
+unsafe fn debugger<T: Debug>(p: *const (), s: &mut DebugStruct<'_, '_>) {
+    s.field("type", &type_name::<T>())
+        .field("value", unsafe { &*(p as *const T) });
+}
+
 /// Implements the `Drop` trait to clean up the heap-allocated value.
 ///
-/// * Calls the type-erased destructor stored in `self.drop` to free the memory.
-impl Drop for DynObj {
+/// * Calls the type-erased destructor stored in `self.drop`, then returns the
+///   memory to the allocator.
+impl<A: FieldAllocator> Drop for DynObj<A> {
     /// Runs the type-erased destructor stored in `self.drop`.
     ///
     /// # Safety
     /// * The pointer `self.data` is guaranteed to be valid for the original
-    ///   type because `new` paired it with the correct `drop` function.
+    ///   type because `new_in` paired it with the correct `drop` function.
     fn drop(&mut self) {
         // Call the stored destructor function with the raw pointer
-        unsafe { (self.drop)(self.data) }
+        unsafe {
+            (self.drop)(self.data);
+            self.alloc
+                .dealloc(NonNull::new_unchecked(self.data as *mut u8), self.layout);
+        }
     }
 }
 
@@ -227,7 +485,295 @@ impl Drop for DynObj {
 ///
 /// * Safe because the value in `data` is required to implement `Send` in `new`.
 /// * The raw pointer and destructor function are thread-safe as long as the
-///   value itself is `Send`.
-unsafe impl Send for DynObj {}
+///   value itself is `Send`, and so is the allocator.
+unsafe impl<A: FieldAllocator + Send> Send for DynObj<A> {}
 
-unsafe impl Sync for DynObj {}
+unsafe impl<A: FieldAllocator + Sync> Sync for DynObj<A> {}
+
+impl DynObj {
+    /// Constructs a [`CloneDynObj`] owning `val`, which implements `Clone`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let obj = extobj::DynObj::new_cloneable(vec![1, 2]);
+    /// let copy = obj.clone();
+    /// assert_eq!(unsafe { copy.get::<Vec<i32>>() }, &[1, 2]);
+    /// ```
+    pub fn new_cloneable<T>(val: T) -> CloneDynObj
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        CloneDynObj::new_in(val, Global)
+    }
+}
+
+/// A [`DynObj`] which can be cloned, to duplicate heterogeneous registries.
+///
+/// It dereferences to its `DynObj` for reading, and records the clone
+/// function of the value at construction.
+pub struct CloneDynObj<A: FieldAllocator = Global> {
+    obj: DynObj<A>,
+
+    /// Clones the value at the first pointer into the uninitialized memory at
+    /// the second one.
+    clone: unsafe fn(*const (), *mut ()),
+}
+
+impl<A: FieldAllocator> CloneDynObj<A> {
+    /// Constructs a new `CloneDynObj` that owns `val`, allocated by `alloc`.
+    pub fn new_in<T>(val: T, alloc: A) -> Self
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        /// # Safety
+        /// * `src` must point to a live `T` and `dst` to memory for a `T`.
+        unsafe fn cloner<T: Clone>(src: *const (), dst: *mut ()) {
+            unsafe { ptr::write(dst as *mut T, (*(src as *const T)).clone()) }
+        }
+
+        Self {
+            obj: DynObj::new_in(val, alloc),
+            clone: cloner::<T>,
+        }
+    }
+
+    /// Mutably borrows the contained value, see [`DynObj::get_mut`].
+    ///
+    /// # Safety
+    /// * The type `T` must match the type used in `new_in`.
+    pub unsafe fn get_mut<T>(&mut self) -> &mut T
+    where
+        T: Send + 'static,
+    {
+        unsafe { self.obj.get_mut() }
+    }
+
+    /// Consumes the object and returns the owned value, see
+    /// [`DynObj::into_inner`].
+    ///
+    /// # Safety
+    /// * The type `T` must match the type used in `new_in`.
+    pub unsafe fn into_inner<T>(self) -> T
+    where
+        T: Send + 'static,
+    {
+        unsafe { self.obj.into_inner() }
+    }
+
+    /// Forgets the clone function, returning the plain `DynObj`.
+    pub fn into_dyn(self) -> DynObj<A> {
+        self.obj
+    }
+}
+
+impl<A: FieldAllocator> Debug for CloneDynObj<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.obj.fmt(f)
+    }
+}
+
+impl<A: FieldAllocator + Clone> Clone for CloneDynObj<A> {
+    fn clone(&self) -> Self {
+        let alloc = self.obj.alloc.clone();
+        let data = alloc.alloc(self.obj.layout).as_ptr() as *mut ();
+
+        // SAFETY: `clone` was recorded for the type of the value, and `data`
+        // has its layout
+        unsafe { (self.clone)(self.obj.data, data) };
+
+        Self {
+            obj: DynObj {
+                data,
+                drop: self.obj.drop,
+                layout: self.obj.layout,
+                debug: self.obj.debug,
+                alloc,
+                #[cfg(any(debug_assertions, feature = "checked"))]
+                tid: self.obj.tid,
+                _marker: PhantomData,
+            },
+            clone: self.clone,
+        }
+    }
+}
+
+/// Only gives shared access: swapping the `DynObj` out would pair the clone
+/// function with a value of another type.
+impl<A: FieldAllocator> Deref for CloneDynObj<A> {
+    type Target = DynObj<A>;
+
+    #[inline]
+    fn deref(&self) -> &DynObj<A> {
+        &self.obj
+    }
+}
+
+/// A [`DynObj`] for values which are not `Send` or `Sync`, such as an `Rc` or
+/// a GUI handle, in single-threaded code.
+///
+/// It has the same API as `DynObj`, but is neither `Send` nor `Sync` itself.
+///
+/// # Example
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// let shared = Rc::new(5);
+/// let obj = extobj::LocalDynObj::new(Rc::clone(&shared));
+/// assert_eq!(unsafe { **obj.get::<Rc<i32>>() }, 5);
+///
+/// drop(obj);
+/// assert_eq!(Rc::strong_count(&shared), 1);
+/// ```
+pub struct LocalDynObj<A: FieldAllocator = Global> {
+    obj: DynObj<A>,
+
+    /// Opts out of the `Send` and `Sync` implementations of `DynObj`.
+    _local: PhantomData<*const ()>,
+}
+
+impl LocalDynObj {
+    /// Constructs a new `LocalDynObj` that owns `val`, see [`DynObj::new`].
+    pub fn new<T>(val: T) -> Self
+    where
+        T: 'static,
+    {
+        Self::new_in(val, Global)
+    }
+
+    /// Constructs a new `LocalDynObj` that owns `val`, whose `Debug` output
+    /// shows the type name and the value, see [`DynObj::new_debug`].
+    pub fn new_debug<T>(val: T) -> Self
+    where
+        T: Debug + 'static,
+    {
+        Self::new_debug_in(val, Global)
+    }
+
+    /// Consumes the object and returns the value in its `Box`, see
+    /// [`DynObj::into_box`].
+    ///
+    /// # Safety
+    /// * The type `T` must match the type used in `new`.
+    pub unsafe fn into_box<T>(self) -> Box<T>
+    where
+        T: 'static,
+    {
+        // Check type safety in debug builds, or with the `checked` feature
+        #[cfg(any(debug_assertions, feature = "checked"))]
+        assert_eq!(
+            self.obj.tid,
+            core::any::TypeId::of::<T>(),
+            "Type mismatch in LocalDynObj::into_box"
+        );
+
+        // The memory now belongs to the box, `Global` allocates like `Box`
+        let this = ManuallyDrop::new(self.obj);
+        unsafe { Box::from_raw(this.data as *mut T) }
+    }
+}
+
+/// Takes ownership of a boxed value without moving it out of its allocation.
+impl<T: 'static> From<Box<T>> for LocalDynObj {
+    fn from(val: Box<T>) -> Self {
+        Self {
+            // SAFETY: `Global` allocates like `Box`
+            obj: unsafe { DynObj::from_ptr(Box::into_raw(val), Global) },
+            _local: PhantomData,
+        }
+    }
+}
+
+impl<A: FieldAllocator> LocalDynObj<A> {
+    /// Constructs a new `LocalDynObj` that owns `val`, allocated by `alloc`.
+    pub fn new_in<T>(val: T, alloc: A) -> Self
+    where
+        T: 'static,
+    {
+        Self {
+            obj: DynObj::erase_in(val, alloc),
+            _local: PhantomData,
+        }
+    }
+
+    /// Constructs a new `LocalDynObj` that owns `val`, allocated by `alloc`,
+    /// whose `Debug` output shows the type name and the value.
+    pub fn new_debug_in<T>(val: T, alloc: A) -> Self
+    where
+        T: Debug + 'static,
+    {
+        let mut obj = Self::new_in(val, alloc);
+        obj.obj.debug = Some(debugger::<T>);
+        obj
+    }
+
+    /// The allocator owning the memory of the value.
+    pub fn allocator(&self) -> &A {
+        &self.obj.alloc
+    }
+
+    /// Immutably borrows the contained value, see [`DynObj::get`].
+    ///
+    /// # Safety
+    /// * The type `T` must match the type used in `new`.
+    pub unsafe fn get<T>(&self) -> &T
+    where
+        T: 'static,
+    {
+        // Check type safety in debug builds, or with the `checked` feature
+        #[cfg(any(debug_assertions, feature = "checked"))]
+        assert_eq!(
+            self.obj.tid,
+            core::any::TypeId::of::<T>(),
+            "Type mismatch in LocalDynObj::get"
+        );
+
+        unsafe { &*(self.obj.data as *const T) }
+    }
+
+    /// Mutably borrows the contained value, see [`DynObj::get_mut`].
+    ///
+    /// # Safety
+    /// * The type `T` must match the type used in `new`.
+    pub unsafe fn get_mut<T>(&mut self) -> &mut T
+    where
+        T: 'static,
+    {
+        // Check type safety in debug builds, or with the `checked` feature
+        #[cfg(any(debug_assertions, feature = "checked"))]
+        assert_eq!(
+            self.obj.tid,
+            core::any::TypeId::of::<T>(),
+            "Type mismatch in LocalDynObj::get_mut"
+        );
+
+        unsafe { &mut *(self.obj.data as *mut T) }
+    }
+
+    /// Consumes the object and returns the owned value, see
+    /// [`DynObj::into_inner`].
+    ///
+    /// # Safety
+    /// * The type `T` must match the type used in `new`.
+    pub unsafe fn into_inner<T>(self) -> T
+    where
+        T: 'static,
+    {
+        // Check type safety in debug builds, or with the `checked` feature
+        #[cfg(any(debug_assertions, feature = "checked"))]
+        assert_eq!(
+            self.obj.tid,
+            core::any::TypeId::of::<T>(),
+            "Type mismatch in LocalDynObj::into_inner"
+        );
+
+        unsafe { self.obj.take() }
+    }
+}
+
+impl<A: FieldAllocator> Debug for LocalDynObj<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.obj.fmt_as("LocalDynObj", f)
+    }
+}
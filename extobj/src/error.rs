@@ -0,0 +1,250 @@
+use std::{
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+};
+
+/// The error returned by fallible field initializers.
+pub type BoxError = Box<dyn Error + Send + Sync>;
+
+/// The error returned by [`ExtObj::try_new`](crate::ExtObj::try_new) when the
+/// initializer of a field fails.
+pub struct ExtObjInitError {
+    object: &'static str,
+    field: &'static str,
+    source: BoxError,
+}
+
+impl ExtObjInitError {
+    pub(crate) fn new(object: &'static str, field: &'static str, source: BoxError) -> Self {
+        Self {
+            object,
+            field,
+            source,
+        }
+    }
+
+    /// The name of the field which failed to initialize.
+    pub fn field(&self) -> &'static str {
+        self.field
+    }
+
+    /// Consumes the error, returning the error of the initializer.
+    pub fn into_source(self) -> Box<dyn Error + Send + Sync> {
+        self.source
+    }
+}
+
+impl Debug for ExtObjInitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtObjInitError")
+            .field("object", &self.object)
+            .field("field", &self.field)
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+impl Display for ExtObjInitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to initialize field `{}` of `{}`: {}",
+            self.field, self.object, self.source
+        )
+    }
+}
+
+impl Error for ExtObjInitError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+/// The error returned by [`ExtObj::set_checked`](crate::ExtObj::set_checked)
+/// and [`ExtObj::validate_all`](crate::ExtObj::validate_all) when the
+/// validator of a field rejects its value.
+pub struct ValidationError {
+    object: &'static str,
+    field: &'static str,
+    source: BoxError,
+}
+
+impl ValidationError {
+    pub(crate) fn new(object: &'static str, field: &'static str, source: BoxError) -> Self {
+        Self {
+            object,
+            field,
+            source,
+        }
+    }
+
+    /// The name of the field whose value was rejected.
+    pub fn field(&self) -> &'static str {
+        self.field
+    }
+
+    /// Consumes the error, returning the error of the validator.
+    pub fn into_source(self) -> Box<dyn Error + Send + Sync> {
+        self.source
+    }
+}
+
+impl Debug for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ValidationError")
+            .field("object", &self.object)
+            .field("field", &self.field)
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid value for field `{}` of `{}`: {}",
+            self.field, self.object, self.source
+        )
+    }
+}
+
+impl Error for ValidationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+/// The error returned by [`ExtObj::apply_patch`](crate::ExtObj::apply_patch)
+/// with the fields of the patch which could not be applied.
+#[cfg(feature = "json")]
+pub struct PatchError {
+    object: &'static str,
+    fields: Vec<(String, BoxError)>,
+}
+
+#[cfg(feature = "json")]
+impl PatchError {
+    pub(crate) fn new(object: &'static str, fields: Vec<(String, BoxError)>) -> Self {
+        Self { object, fields }
+    }
+
+    /// The keys of the patch which could not be applied, sorted, with the
+    /// reason.
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &(dyn Error + Send + Sync))> {
+        self.fields.iter().map(|(key, e)| (key.as_str(), &**e))
+    }
+}
+
+#[cfg(feature = "json")]
+impl Debug for PatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PatchError")
+            .field("object", &self.object)
+            .field("fields", &self.fields)
+            .finish()
+    }
+}
+
+#[cfg(feature = "json")]
+impl Display for PatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot patch `{}`", self.object)?;
+
+        for (i, (key, e)) in self.fields.iter().enumerate() {
+            let sep = if i == 0 { ": " } else { ", " };
+            write!(f, "{sep}`{key}`: {e}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "json")]
+impl Error for PatchError {}
+
+/// The error returned by [`ExtObj::save`](crate::ExtObj::save) and
+/// [`ExtObj::load`](crate::ExtObj::load).
+#[cfg(feature = "binary")]
+#[derive(Debug)]
+pub struct BinaryError(BinaryErrorKind);
+
+#[cfg(feature = "binary")]
+#[derive(Debug)]
+enum BinaryErrorKind {
+    Io(std::io::Error),
+    Encoding(postcard::Error),
+    /// The snapshot was written in an unknown layout.
+    Format(u8),
+    /// The snapshot is the one of another object.
+    Object {
+        expected: &'static str,
+        found: String,
+    },
+    /// The value of a field cannot be decoded.
+    Field {
+        key: String,
+        source: BoxError,
+    },
+}
+
+#[cfg(feature = "binary")]
+impl BinaryError {
+    pub(crate) fn io(e: std::io::Error) -> Self {
+        Self(BinaryErrorKind::Io(e))
+    }
+
+    pub(crate) fn encoding(e: postcard::Error) -> Self {
+        Self(BinaryErrorKind::Encoding(e))
+    }
+
+    pub(crate) fn format(format: u8) -> Self {
+        Self(BinaryErrorKind::Format(format))
+    }
+
+    pub(crate) fn object(expected: &'static str, found: &str) -> Self {
+        Self(BinaryErrorKind::Object {
+            expected,
+            found: found.to_owned(),
+        })
+    }
+
+    pub(crate) fn field(key: &str, source: impl Into<BoxError>) -> Self {
+        Self(BinaryErrorKind::Field {
+            key: key.to_owned(),
+            source: source.into(),
+        })
+    }
+}
+
+#[cfg(feature = "binary")]
+impl Display for BinaryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            BinaryErrorKind::Io(e) => write!(f, "cannot read or write the snapshot: {e}"),
+            BinaryErrorKind::Encoding(e) => write!(f, "invalid snapshot: {e}"),
+            BinaryErrorKind::Format(format) => write!(f, "unknown snapshot format {format}"),
+            BinaryErrorKind::Object { expected, found } => {
+                write!(f, "snapshot of `{found}` loaded as `{expected}`")
+            }
+            BinaryErrorKind::Field { key, source } => {
+                write!(
+                    f,
+                    "invalid value for field `{key}` in the snapshot: {source}"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "binary")]
+impl Error for BinaryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.0 {
+            BinaryErrorKind::Io(e) => Some(e),
+            BinaryErrorKind::Encoding(e) => Some(e),
+            BinaryErrorKind::Field { source, .. } => Some(&**source),
+            BinaryErrorKind::Format(_) | BinaryErrorKind::Object { .. } => None,
+        }
+    }
+}
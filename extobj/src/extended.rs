@@ -0,0 +1,157 @@
+use crate::{
+    __ExtObjDef, __private, ExtObj, ExtObjInitError, Global, registry::PartialSlots, unsupported,
+};
+use std::{
+    fmt::{self, Debug, Formatter},
+    ops::{Deref, DerefMut},
+};
+
+/// A value of type `T` paired with the extension object `O` other crates
+/// attach data to.
+///
+/// It dereferences to the value, the extensions are reached with
+/// [`Extended::ext`] and [`Extended::ext_mut`].
+///
+/// # Example
+///
+/// ```
+/// use extobj::Extended;
+///
+/// struct User {
+///     name: String,
+/// }
+///
+/// extobj::extobj!(struct UserExt);
+/// extobj::extobj!(impl UserExt { pub LOGINS: u32 });
+///
+/// let mut user = Extended::<User, UserExt>::new(User { name: "ada".into() });
+/// user.ext_mut()[*LOGINS] += 1;
+///
+/// assert_eq!(user.name, "ada");
+/// assert_eq!(user.ext()[*LOGINS], 1);
+/// ```
+pub struct Extended<T, O: __ExtObjDef> {
+    value: T,
+    ext: ExtObj<O>,
+}
+
+impl<T, O: __ExtObjDef> Extended<T, O> {
+    /// Pairs `value` with a new extension object.
+    ///
+    /// # Panics
+    /// Panics if a fallible initializer fails, see [`Extended::try_new`].
+    pub fn new(value: T) -> Self {
+        Self::from_parts(value, ExtObj::new())
+    }
+
+    /// Pairs `value` with a new extension object, reporting the field whose
+    /// initializer failed.
+    pub fn try_new(value: T) -> Result<Self, ExtObjInitError> {
+        Ok(Self::from_parts(value, ExtObj::try_new()?))
+    }
+
+    /// Pairs `value` with an existing extension object.
+    #[inline]
+    pub fn from_parts(value: T, ext: ExtObj<O>) -> Self {
+        Self { value, ext }
+    }
+
+    /// Returns the value and its extension object.
+    #[inline]
+    pub fn into_parts(self) -> (T, ExtObj<O>) {
+        (self.value, self.ext)
+    }
+
+    /// Returns the value, dropping its extensions.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// The extension object.
+    #[inline]
+    pub fn ext(&self) -> &ExtObj<O> {
+        &self.ext
+    }
+
+    /// Mutably borrows the extension object.
+    #[inline]
+    pub fn ext_mut(&mut self) -> &mut ExtObj<O> {
+        &mut self.ext
+    }
+}
+
+/// Clones the value and every extension field.
+///
+/// # Panics
+/// Panics if the type of an extension field does not implement `Clone`.
+impl<T: Clone, O: __ExtObjDef> Clone for Extended<T, O> {
+    fn clone(&self) -> Self {
+        let slots = O::defs().with(|defs| {
+            let mut slots = PartialSlots::new(defs, &Global);
+
+            for (def, slot) in defs.iter().zip(&self.ext.0) {
+                match unsafe { def.clone_slot(slot, &Global) } {
+                    Some(slot) => slots.push(slot),
+                    None => unsupported::<O>(def.name, "Clone"),
+                }
+            }
+
+            slots.finish()
+        });
+
+        Self {
+            value: self.value.clone(),
+            ext: ExtObj::from_slots(slots, Global),
+        }
+    }
+}
+
+impl<T: Debug, O: __ExtObjDef> Debug for Extended<T, O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extended")
+            .field("value", &self.value)
+            .field("ext", &self.ext)
+            .finish()
+    }
+}
+
+impl<T: Default, O: __ExtObjDef> Default for Extended<T, O> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T, O> PartialEq for Extended<T, O>
+where
+    T: PartialEq,
+    O: __ExtObjDef<Comparability = __private::Comparable>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value && self.ext == other.ext
+    }
+}
+
+impl<T, O: __ExtObjDef> From<T> for Extended<T, O> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T, O: __ExtObjDef> Deref for Extended<T, O> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T, O: __ExtObjDef> DerefMut for Extended<T, O> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
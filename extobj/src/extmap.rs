@@ -0,0 +1,234 @@
+use crate::{__ExtObjDef, ExtObj};
+use std::{
+    borrow::Borrow,
+    collections::hash_map::{self, Entry, HashMap, RandomState},
+    fmt::{self, Debug, Formatter},
+    hash::{BuildHasher, Hash},
+};
+
+/// A map attaching an extension object to keys of a type you do not own, such
+/// as identifiers or addresses.
+///
+/// The objects are created on demand with [`ExtMap::get_or_create`] or the
+/// [`ExtMap::entry`] API.
+///
+/// # Example
+///
+/// ```
+/// use extobj::ExtMap;
+///
+/// extobj::extobj!(struct Peer);
+/// extobj::extobj!(impl Peer { pub PINGS: u32 });
+///
+/// let mut peers = ExtMap::<u64, Peer>::new();
+/// peers.get_or_create(&7)[*PINGS] += 1;
+/// peers.get_or_create(&7)[*PINGS] += 1;
+///
+/// assert_eq!(peers.get(&7).map(|peer| peer[*PINGS]), Some(2));
+/// assert!(peers.get(&8).is_none());
+/// ```
+pub struct ExtMap<K, O: __ExtObjDef, S = RandomState> {
+    map: HashMap<K, ExtObj<O>, S>,
+}
+
+impl<K, O: __ExtObjDef> ExtMap<K, O> {
+    /// Creates an empty map.
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<K, O: __ExtObjDef, S> ExtMap<K, O, S> {
+    /// Creates an empty map hashing the keys with `hasher`.
+    #[inline]
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            map: HashMap::with_hasher(hasher),
+        }
+    }
+
+    /// The number of objects.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` when the map holds no objects.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Iterates over the keys and their objects, in arbitrary order.
+    #[inline]
+    pub fn iter(&self) -> hash_map::Iter<'_, K, ExtObj<O>> {
+        self.map.iter()
+    }
+
+    /// Iterates over the keys and mutable borrows of their objects, in
+    /// arbitrary order.
+    #[inline]
+    pub fn iter_mut(&mut self) -> hash_map::IterMut<'_, K, ExtObj<O>> {
+        self.map.iter_mut()
+    }
+
+    /// Iterates over the keys, in arbitrary order.
+    #[inline]
+    pub fn keys(&self) -> hash_map::Keys<'_, K, ExtObj<O>> {
+        self.map.keys()
+    }
+
+    /// Iterates over the objects, in arbitrary order.
+    #[inline]
+    pub fn values(&self) -> hash_map::Values<'_, K, ExtObj<O>> {
+        self.map.values()
+    }
+
+    /// Iterates over mutable borrows of the objects, in arbitrary order.
+    #[inline]
+    pub fn values_mut(&mut self) -> hash_map::ValuesMut<'_, K, ExtObj<O>> {
+        self.map.values_mut()
+    }
+
+    /// Keeps only the objects for which `f` returns `true`.
+    #[inline]
+    pub fn retain(&mut self, f: impl FnMut(&K, &mut ExtObj<O>) -> bool) {
+        self.map.retain(f)
+    }
+
+    /// Removes every object.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+}
+
+impl<K: Eq + Hash, O: __ExtObjDef, S: BuildHasher> ExtMap<K, O, S> {
+    /// Borrows the object of `key`, if any.
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> Option<&ExtObj<O>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.get(key)
+    }
+
+    /// Mutably borrows the object of `key`, if any.
+    #[inline]
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut ExtObj<O>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.get_mut(key)
+    }
+
+    /// Mutably borrows the object of `key`, creating it with its default
+    /// values first if needed.
+    ///
+    /// # Panics
+    /// Panics if a fallible initializer fails.
+    pub fn get_or_create<Q>(&mut self, key: &Q) -> &mut ExtObj<O>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ToOwned<Owned = K> + ?Sized,
+    {
+        // looked up first, so the key is only cloned when inserting
+        if !self.map.contains_key(key) {
+            self.map.insert(key.to_owned(), ExtObj::new());
+        }
+
+        match self.map.get_mut(key) {
+            Some(obj) => obj,
+            None => unreachable!("the object was just inserted"),
+        }
+    }
+
+    /// The entry of `key`, for in-place manipulation. Use
+    /// `Entry::or_default` to create the object.
+    #[inline]
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, ExtObj<O>> {
+        self.map.entry(key)
+    }
+
+    /// Returns `true` when `key` has an object.
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// Attaches `obj` to `key`, returning the object it replaces.
+    #[inline]
+    pub fn insert(&mut self, key: K, obj: ExtObj<O>) -> Option<ExtObj<O>> {
+        self.map.insert(key, obj)
+    }
+
+    /// Detaches the object of `key`, if any.
+    #[inline]
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<ExtObj<O>>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.map.remove(key)
+    }
+}
+
+impl<K, O: __ExtObjDef, S: Default> Default for ExtMap<K, O, S> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Debug, O: __ExtObjDef, S> Debug for ExtMap<K, O, S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(&self.map).finish()
+    }
+}
+
+impl<K, O: __ExtObjDef, S> IntoIterator for ExtMap<K, O, S> {
+    type Item = (K, ExtObj<O>);
+    type IntoIter = hash_map::IntoIter<K, ExtObj<O>>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_iter()
+    }
+}
+
+impl<'a, K, O: __ExtObjDef, S> IntoIterator for &'a ExtMap<K, O, S> {
+    type Item = (&'a K, &'a ExtObj<O>);
+    type IntoIter = hash_map::Iter<'a, K, ExtObj<O>>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.iter()
+    }
+}
+
+impl<'a, K, O: __ExtObjDef, S> IntoIterator for &'a mut ExtMap<K, O, S> {
+    type Item = (&'a K, &'a mut ExtObj<O>);
+    type IntoIter = hash_map::IterMut<'a, K, ExtObj<O>>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.iter_mut()
+    }
+}
+
+impl<K: Eq + Hash, O: __ExtObjDef, S: BuildHasher + Default> FromIterator<(K, ExtObj<O>)>
+    for ExtMap<K, O, S>
+{
+    fn from_iter<I: IntoIterator<Item = (K, ExtObj<O>)>>(iter: I) -> Self {
+        Self {
+            map: iter.into_iter().collect(),
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use crate::{
+    __ExtObjDef, ExtObjInitError, Var, missing_field,
+    registry::{FieldDef, drop_order, loaded_defs},
+};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::{
+    any::Any,
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    ops::{Index, IndexMut},
+};
+
+/// A collection of extension objects stored as columns: every field is a
+/// contiguous `Vec<T>`, so processing one field of many objects is
+/// cache-friendly.
+///
+/// Objects are identified by their index, like in a `Vec`.
+///
+/// # Example
+///
+/// ```
+/// use extobj::ExtObjVec;
+///
+/// extobj::extobj!(struct Entity);
+/// extobj::extobj!(impl Entity {
+///     pub POSITION: f32,
+///     pub VELOCITY: f32 = 2.0,
+/// });
+///
+/// let mut entities = ExtObjVec::<Entity>::new();
+/// let first = entities.push();
+/// entities.push();
+///
+/// for position in entities.column_mut(*POSITION) {
+///     *position += 2.0;
+/// }
+///
+/// assert_eq!(entities[(first, *POSITION)], 2.0);
+/// assert_eq!(entities.column(*VELOCITY), [2.0, 2.0]);
+/// ```
+pub struct ExtObjVec<O: __ExtObjDef> {
+    /// One boxed `Vec<T>` per field, in registration order.
+    columns: Vec<usize>,
+    len: usize,
+    _marker: PhantomData<(O, O::Threading)>,
+}
+
+impl<O: __ExtObjDef> ExtObjVec<O> {
+    /// Creates an empty collection with a column per registered field.
+    pub fn new() -> Self {
+        let mut vec = Self {
+            columns: Vec::new(),
+            len: 0,
+            _marker: PhantomData,
+        };
+
+        vec.upgrade();
+        vec
+    }
+
+    /// The number of objects.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` when the collection holds no objects.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends an object with every field set to its default value and
+    /// returns its index.
+    ///
+    /// # Panics
+    /// Panics if a fallible initializer fails, see [`ExtObjVec::try_push`].
+    pub fn push(&mut self) -> usize {
+        self.try_push().unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Appends an object with every field set to its default value, reporting
+    /// the field whose initializer failed. The collection is unchanged on
+    /// failure.
+    pub fn try_push(&mut self) -> Result<usize, ExtObjInitError> {
+        self.try_push_with(&())
+    }
+
+    /// Appends an object, passing `ctx` to the context initializers.
+    ///
+    /// See [`ExtObj::new_with`](crate::ExtObj::new_with).
+    pub fn try_push_with(&mut self, ctx: &dyn Any) -> Result<usize, ExtObjInitError> {
+        self.upgrade();
+
+        loaded_defs::<O>().with(|defs| {
+            // removes the values already pushed if an initializer fails or panics
+            let mut row = PartialRow {
+                defs,
+                columns: &self.columns,
+                len: self.len,
+                pushed: 0,
+            };
+
+            for (def, &column) in defs.iter().zip(&self.columns) {
+                unsafe { def.push_column(column, ctx) }
+                    .map_err(|e| ExtObjInitError::new(O::NAME, def.name, e))?;
+                row.pushed += 1;
+            }
+
+            row.pushed = 0;
+            Ok(())
+        })?;
+
+        self.len += 1;
+        Ok(self.len - 1)
+    }
+
+    /// Removes the object at `index`, replacing it with the last one.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    #[track_caller]
+    pub fn swap_remove(&mut self, index: usize) {
+        assert!(
+            index < self.len,
+            "index {index} out of bounds for {} objects",
+            self.len
+        );
+
+        O::defs().with(|defs| {
+            for (def, &column) in defs.iter().zip(&self.columns) {
+                unsafe { def.swap_remove_column(column, index) };
+            }
+        });
+
+        self.len -= 1;
+    }
+
+    /// Removes every object, keeping the columns.
+    pub fn clear(&mut self) {
+        O::defs().with(|defs| {
+            drop_order(defs, self.columns.len(), |i| unsafe {
+                defs[i].truncate_column(self.columns[i], 0)
+            });
+        });
+
+        self.len = 0;
+    }
+
+    /// Adds a column for every field registered after this collection was
+    /// created, filled with default values.
+    ///
+    /// [`ExtObjVec::push`] calls this first, so columns are only missing for
+    /// the fields registered since the last push.
+    ///
+    /// # Panics
+    /// Panics if a fallible initializer fails.
+    pub fn upgrade(&mut self) {
+        loaded_defs::<O>().with(|defs| {
+            for def in &defs[self.columns.len()..] {
+                let column = def.new_column();
+                self.columns.push(column);
+
+                for _ in 0..self.len {
+                    if let Err(e) = unsafe { def.push_column(column, &()) } {
+                        panic!("{}", ExtObjInitError::new(O::NAME, def.name, e));
+                    }
+                }
+            }
+        });
+    }
+
+    /// The values of a field for every object, by index.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after the last push.
+    #[inline]
+    #[track_caller]
+    pub fn column<T>(&self, var: Var<O, T>) -> &[T] {
+        match self.columns.get(var.0) {
+            Some(&column) => unsafe { &*(column as *const Vec<T>) },
+            None => missing_field(var.0),
+        }
+    }
+
+    /// Mutably borrows the values of a field for every object, by index.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after the last push.
+    #[inline]
+    #[track_caller]
+    pub fn column_mut<T>(&mut self, var: Var<O, T>) -> &mut [T] {
+        match self.columns.get(var.0) {
+            Some(&column) => unsafe { &mut *(column as *mut Vec<T>) },
+            None => missing_field(var.0),
+        }
+    }
+
+    /// Immutably borrows the value of a field of the object at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds or if the variable was registered
+    /// after the last push.
+    #[inline]
+    #[track_caller]
+    pub fn get<T>(&self, index: usize, var: Var<O, T>) -> &T {
+        &self.column(var)[index]
+    }
+
+    /// Mutably borrows the value of a field of the object at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds or if the variable was registered
+    /// after the last push.
+    #[inline]
+    #[track_caller]
+    pub fn get_mut<T>(&mut self, index: usize, var: Var<O, T>) -> &mut T {
+        &mut self.column_mut(var)[index]
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<O: __ExtObjDef> ExtObjVec<O> {
+    /// A parallel iterator over the values of a field for every object.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after the last push.
+    #[track_caller]
+    pub fn par_column<T: Sync>(&self, var: Var<O, T>) -> rayon::slice::Iter<'_, T> {
+        self.column(var).par_iter()
+    }
+
+    /// A parallel iterator over mutable borrows of the values of a field for
+    /// every object.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after the last push.
+    #[track_caller]
+    pub fn par_column_mut<T: Send>(&mut self, var: Var<O, T>) -> rayon::slice::IterMut<'_, T> {
+        self.column_mut(var).par_iter_mut()
+    }
+
+    /// Calls `f` in parallel with the index and the value of a field of every
+    /// object.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after the last push.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extobj::extobj!(struct Body);
+    /// extobj::extobj!(impl Body { pub HEIGHT: f64 = 1000.0 });
+    ///
+    /// let mut bodies = extobj::ExtObjVec::<Body>::new();
+    /// (0..1000).for_each(|_| {
+    ///     bodies.push();
+    /// });
+    ///
+    /// bodies.par_for_each(*HEIGHT, |i, height| *height -= i as f64);
+    /// assert_eq!(bodies[(100, *HEIGHT)], 900.0);
+    /// ```
+    #[track_caller]
+    pub fn par_for_each<T: Send>(
+        &mut self,
+        var: Var<O, T>,
+        f: impl Fn(usize, &mut T) + Send + Sync,
+    ) {
+        self.par_column_mut(var)
+            .enumerate()
+            .for_each(|(index, value)| f(index, value));
+    }
+}
+
+impl<O: __ExtObjDef> Default for ExtObjVec<O> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<O: __ExtObjDef> Debug for ExtObjVec<O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtObjVec")
+            .field("object", &O::NAME)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl<O: __ExtObjDef> Drop for ExtObjVec<O> {
+    fn drop(&mut self) {
+        O::defs().with(|defs| {
+            drop_order(defs, self.columns.len(), |i| unsafe {
+                defs[i].drop_column(self.columns[i])
+            });
+        });
+    }
+}
+
+impl<O: __ExtObjDef, T> Index<(usize, Var<O, T>)> for ExtObjVec<O> {
+    type Output = T;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, (index, var): (usize, Var<O, T>)) -> &T {
+        self.get(index, var)
+    }
+}
+
+impl<O: __ExtObjDef, T> IndexMut<(usize, Var<O, T>)> for ExtObjVec<O> {
+    #[inline]
+    #[track_caller]
+    fn index_mut(&mut self, (index, var): (usize, Var<O, T>)) -> &mut T {
+        self.get_mut(index, var)
+    }
+}
+
+/// The values pushed for a new object, removed unless the push completes.
+struct PartialRow<'a> {
+    defs: &'a [FieldDef],
+    columns: &'a [usize],
+    len: usize,
+    pushed: usize,
+}
+
+impl Drop for PartialRow<'_> {
+    fn drop(&mut self) {
+        for (def, &column) in self.defs.iter().zip(self.columns).take(self.pushed) {
+            unsafe { def.truncate_column(column, self.len) };
+        }
+    }
+}
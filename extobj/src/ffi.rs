@@ -0,0 +1,190 @@
+use crate::{__ExtObjDef, ExtObj, registry::find_field};
+use std::{
+    any::TypeId,
+    ffi::{CStr, c_char, c_void},
+    mem::ManuallyDrop,
+    ptr,
+};
+
+/// An `ExtObj` handed to C or C++ code as an opaque pointer.
+///
+/// The host converts an object with [`ExtObjHandle::into_raw`]; plugins then
+/// read and write its fields by name with `extobj_get` and `extobj_set`, and
+/// release it with `extobj_destroy`, or give it back to the host with
+/// [`ExtObjHandle::from_raw`]. Fields are found like [`var_by_name`], by name
+/// or by stable key `crate::NAME`.
+///
+/// [`var_by_name`]: crate::var_by_name
+///
+/// # Example
+///
+/// ```
+/// use extobj::{ExtObj, ExtObjHandle, extobj_get, extobj_set};
+///
+/// extobj::extobj!(struct Document);
+/// extobj::extobj!(impl Document { pub PAGES: u32 = 1 });
+///
+/// let handle = ExtObjHandle::into_raw(ExtObj::<Document>::new());
+///
+/// // on the C side
+/// unsafe {
+///     let pages = extobj_get(handle, c"PAGES".as_ptr()) as *mut u32;
+///     *pages += 1;
+///
+///     let value: u32 = 10;
+///     assert!(extobj_set(handle, c"PAGES".as_ptr(), (&raw const value).cast(), 4));
+/// }
+///
+/// let doc = unsafe { ExtObjHandle::from_raw::<Document>(handle) };
+/// assert_eq!(doc[*PAGES], 10);
+/// ```
+pub struct ExtObjHandle {
+    /// The boxed `ExtObj<O>`.
+    obj: *mut (),
+    type_id: TypeId,
+    get: unsafe fn(*mut (), &str) -> *mut c_void,
+    set: unsafe fn(*mut (), &str, *const c_void, usize) -> bool,
+    drop: unsafe fn(*mut ()),
+}
+
+impl ExtObjHandle {
+    /// Moves `obj` into a new handle, released with `extobj_destroy` or
+    /// [`ExtObjHandle::from_raw`].
+    pub fn into_raw<O: __ExtObjDef>(obj: ExtObj<O>) -> *mut ExtObjHandle {
+        let handle = Self {
+            obj: Box::into_raw(Box::new(obj)) as *mut (),
+            type_id: TypeId::of::<O>(),
+            get: get::<O>,
+            set: set::<O>,
+            drop: drop_obj::<O>,
+        };
+
+        Box::into_raw(Box::new(handle))
+    }
+
+    /// Takes back the object of a handle created by
+    /// [`ExtObjHandle::into_raw`].
+    ///
+    /// # Panics
+    /// Panics if the handle holds an object of another type.
+    ///
+    /// # Safety
+    /// `handle` must come from `into_raw`, and not be used afterwards.
+    pub unsafe fn from_raw<O: __ExtObjDef>(handle: *mut ExtObjHandle) -> ExtObj<O> {
+        let handle = ManuallyDrop::new(*unsafe { Box::from_raw(handle) });
+
+        assert_eq!(
+            handle.type_id,
+            TypeId::of::<O>(),
+            "the handle does not hold an `ExtObj<{}>`",
+            O::NAME
+        );
+
+        *unsafe { Box::from_raw(handle.obj as *mut ExtObj<O>) }
+    }
+}
+
+impl Drop for ExtObjHandle {
+    fn drop(&mut self) {
+        // SAFETY: `drop` was recorded for the type of the object
+        unsafe { (self.drop)(self.obj) }
+    }
+}
+
+/// Returns the address of the value of the field `key` of `handle`, or null
+/// when the object has no such field.
+///
+/// # Safety
+/// `handle` must come from [`ExtObjHandle::into_raw`] and `key` must be a
+/// nul-terminated string. The value must be accessed with the layout of the
+/// field type.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn extobj_get(handle: *mut ExtObjHandle, key: *const c_char) -> *mut c_void {
+    let handle = unsafe { &*handle };
+
+    match unsafe { CStr::from_ptr(key) }.to_str() {
+        Ok(key) => unsafe { (handle.get)(handle.obj, key) },
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Drops the value of the field `key` of `handle` and moves the `size` bytes
+/// at `value` in its place, then calls the observers of the field.
+///
+/// Returns `false`, leaving the field untouched, when the object has no such
+/// field, when the field is `#[readonly]` or when `size` is not the size of
+/// the field type.
+///
+/// # Safety
+/// `handle` must come from [`ExtObjHandle::into_raw`] and `key` must be a
+/// nul-terminated string. The bytes at `value` must be a valid value of the
+/// field type, which is moved: it must not be dropped afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn extobj_set(
+    handle: *mut ExtObjHandle,
+    key: *const c_char,
+    value: *const c_void,
+    size: usize,
+) -> bool {
+    let handle = unsafe { &*handle };
+
+    match unsafe { CStr::from_ptr(key) }.to_str() {
+        Ok(key) => unsafe { (handle.set)(handle.obj, key, value, size) },
+        Err(_) => false,
+    }
+}
+
+/// Drops the object of `handle`.
+///
+/// # Safety
+/// `handle` must come from [`ExtObjHandle::into_raw`], and not be used
+/// afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn extobj_destroy(handle: *mut ExtObjHandle) {
+    drop(unsafe { Box::from_raw(handle) });
+}
+
+/// # Safety
+/// `obj` must point to a live `ExtObj<O>`.
+unsafe fn get<O: __ExtObjDef>(obj: *mut (), key: &str) -> *mut c_void {
+    let obj = unsafe { &mut *(obj as *mut ExtObj<O>) };
+
+    O::defs().with(|defs| match find_field(defs, key) {
+        // SAFETY: the slot was created by the definition at the same index
+        Some(i) if i < obj.0.len() => unsafe { defs[i].value_mut(&mut obj.0[i]) as *mut c_void },
+        _ => ptr::null_mut(),
+    })
+}
+
+/// # Safety
+/// `obj` must point to a live `ExtObj<O>`, and `value` to a value of the
+/// field type when `size` matches.
+unsafe fn set<O: __ExtObjDef>(obj: *mut (), key: &str, value: *const c_void, size: usize) -> bool {
+    let obj = unsafe { &mut *(obj as *mut ExtObj<O>) };
+
+    let field = O::defs().with(|defs| {
+        find_field(defs, key)
+            .filter(|&i| i < obj.0.len() && !defs[i].readonly && defs[i].size == size)
+            .map(|i| (i, defs[i]))
+    });
+
+    let Some((index, def)) = field else {
+        return false;
+    };
+
+    // the lock is released before dropping the previous value
+    unsafe {
+        let dst = def.value_mut(&mut obj.0[index]);
+        def.drop_at(dst);
+        ptr::copy_nonoverlapping(value as *const u8, dst as *mut u8, size);
+    }
+
+    obj.notify(index);
+    true
+}
+
+/// # Safety
+/// `obj` must point to a boxed `ExtObj<O>`, not used afterwards.
+unsafe fn drop_obj<O: __ExtObjDef>(obj: *mut ()) {
+    drop(unsafe { Box::from_raw(obj as *mut ExtObj<O>) });
+}
@@ -0,0 +1,85 @@
+/// Declares a plain struct with one named field per extension field of an
+/// object, converted from and into its `ExtObj`.
+///
+/// Once the set of fields of an object is known, typically in the final
+/// binary crate, a frozen struct gives release builds real fields with fixed
+/// offsets and full compiler optimization, while dev builds keep the dynamic
+/// registry. Each field is listed with the `Var` it mirrors.
+///
+/// Converting an `ExtObj` moves its values out with [`ExtObj::take`], so the
+/// field types must implement `Default`. In debug builds, the conversion
+/// checks the struct against the registry: it panics if a field is listed
+/// twice, or if the object has fields which are not listed, since their
+/// values would be lost.
+///
+/// [`ExtObj::take`]: crate::ExtObj::take
+///
+/// # Example
+///
+/// ```
+/// use extobj::ExtObj;
+///
+/// extobj::extobj!(struct Sprite);
+/// extobj::extobj!(impl Sprite { pub X: f32, pub NAME: String });
+///
+/// extobj::frozen_struct!(
+///     #[derive(Debug)]
+///     pub struct FrozenSprite: Sprite {
+///         pub x: f32 = X,
+///         pub name: String = NAME,
+///     }
+/// );
+///
+/// let mut sprite = ExtObj::<Sprite>::new();
+/// sprite[*X] = 2.5;
+///
+/// let mut frozen = FrozenSprite::from(sprite);
+/// frozen.name.push_str("hero");
+///
+/// let sprite = ExtObj::<Sprite>::from(frozen);
+/// assert_eq!(sprite[*X], 2.5);
+/// assert_eq!(sprite[*NAME], "hero");
+/// ```
+#[macro_export]
+macro_rules! frozen_struct {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident: $obj:ty {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident: $ty:ty = $var:expr
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                $field_vis $field: $ty,
+            )*
+        }
+
+        impl ::std::convert::From<$crate::ExtObj<$obj>> for $name {
+            fn from(mut obj: $crate::ExtObj<$obj>) -> Self {
+                if ::std::cfg!(debug_assertions) {
+                    $crate::__private::check_frozen::<$obj>(
+                        ::std::stringify!($name),
+                        &[$($crate::Var::untyped(*$var)),*],
+                    );
+                }
+
+                Self {
+                    $($field: obj.take::<$ty>(*$var),)*
+                }
+            }
+        }
+
+        impl ::std::convert::From<$name> for $crate::ExtObj<$obj> {
+            fn from(frozen: $name) -> Self {
+                let mut obj = Self::new();
+                $(obj[*$var] = frozen.$field;)*
+                obj
+            }
+        }
+    };
+}
@@ -0,0 +1,116 @@
+use crate::{
+    __ExtObjDef, ExtObj, ExtObjInitError, Global,
+    registry::{FieldDef, PartialSlots, loaded_defs},
+};
+use std::{
+    any::Any,
+    cell::Cell,
+    future::{Future, poll_fn},
+    pin::Pin,
+    task::Poll,
+};
+
+/// The future returned by the initializers registered with `= init_async(f)`.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// The value resolved by the asynchronous initializer of a field, passed as
+/// the context of its synchronous initializer.
+pub(crate) struct Resolved(Cell<Option<Box<dyn Any + Send>>>);
+
+impl Resolved {
+    pub(crate) fn take(&self) -> Option<Box<dyn Any + Send>> {
+        self.0.take()
+    }
+}
+
+impl<O: __ExtObjDef> ExtObj<O> {
+    /// Creates a new `ExtObj`, awaiting the fields registered with
+    /// `= init_async(f)` where `f` is a `fn() -> BoxFuture<'static, T>`.
+    ///
+    /// The futures of the fields are polled concurrently, on the task
+    /// awaiting the object; the other fields are created once they are all
+    /// resolved. Context initializers receive `&()`.
+    ///
+    /// Objects with asynchronous fields can only be created this way:
+    /// [`ExtObj::new`], [`ExtObj::reset`] and the other synchronous
+    /// constructors fail to initialize these fields.
+    ///
+    /// # Panics
+    /// Panics if a fallible initializer fails, see [`ExtObj::try_new_async`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use extobj::{BoxFuture, ExtObj};
+    ///
+    /// async fn connect() -> String {
+    ///     "postgres://localhost".into()
+    /// }
+    ///
+    /// extobj::extobj!(struct Request);
+    /// extobj::extobj!(impl Request {
+    ///     DB: String = init_async(|| -> BoxFuture<'static, String> { Box::pin(connect()) }),
+    /// });
+    ///
+    /// # async fn run() {
+    /// let request = ExtObj::<Request>::new_async().await;
+    /// assert_eq!(request[*DB], "postgres://localhost");
+    /// # }
+    /// ```
+    pub async fn new_async() -> Self {
+        Self::try_new_async()
+            .await
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Creates a new `ExtObj` with its asynchronous fields, reporting the
+    /// field whose initializer failed.
+    ///
+    /// See [`ExtObj::new_async`] and [`ExtObj::try_new`].
+    pub async fn try_new_async() -> Result<Self, ExtObjInitError> {
+        // the registry lock is not held across await points
+        let defs = loaded_defs::<O>().with(<[FieldDef]>::to_vec);
+        let mut futures: Vec<_> = defs.iter().map(FieldDef::start).collect();
+        let mut values: Vec<_> = defs.iter().map(|_| None).collect();
+
+        poll_fn(|cx| {
+            let mut ready = true;
+
+            for (future, value) in futures.iter_mut().zip(&mut values) {
+                if let Some(f) = future {
+                    match f.as_mut().poll(cx) {
+                        Poll::Ready(v) => {
+                            *value = Some(v);
+                            *future = None;
+                        }
+                        Poll::Pending => ready = false,
+                    }
+                }
+            }
+
+            if ready {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+
+        // drops the fields already created if an initializer fails or panics
+        let mut slots = PartialSlots::new(&defs, &Global);
+
+        for (def, value) in defs.iter().zip(values) {
+            let slot = match value {
+                Some(value) => unsafe { def.create(&Resolved(Cell::new(Some(value))), &Global) },
+                None => unsafe { def.create(&(), &Global) },
+            };
+
+            match slot {
+                Ok(slot) => slots.push(slot),
+                Err(e) => return Err(ExtObjInitError::new(O::NAME, def.name, e)),
+            }
+        }
+
+        Ok(Self::from_slots(slots.finish(), Global))
+    }
+}
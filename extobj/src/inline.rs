@@ -0,0 +1,277 @@
+use crate::{
+    __ExtObjDef, ExtObjInitError, Var, missing_field,
+    registry::{FieldDef, drop_order, loaded_defs},
+};
+use std::{
+    any::Any,
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    ops::{Index, IndexMut},
+};
+
+/// The alignment of the buffer of an [`ExtObjInline`], the largest alignment
+/// its fields may have.
+const ALIGN: usize = 16;
+
+/// The storage of the fields of an [`ExtObjInline`].
+#[repr(C, align(16))]
+struct Buf<const N: usize>([MaybeUninit<u8>; N]);
+
+impl<const N: usize> Buf<N> {
+    #[inline]
+    fn addr(&self, offset: usize) -> usize {
+        self.0.as_ptr() as usize + offset
+    }
+}
+
+/// An extension object keeping all its fields in a buffer of `N` bytes,
+/// without any allocation.
+///
+/// The fields are laid out one after the other, in registration order, at
+/// offsets computed when they are registered. The buffer must hold every
+/// field registered when the object is created, see
+/// [`ExtObjInline::required_size`], and the alignment of the fields is
+/// limited to 16 bytes. Fields allocating on their own, like `String`, still
+/// do.
+///
+/// Accessing a field reads its offset in the registry, which takes a shared
+/// lock until the registry is frozen, see [`freeze`](crate::freeze).
+///
+/// # Example
+///
+/// ```
+/// use extobj::ExtObjInline;
+///
+/// extobj::extobj!(struct Sensor);
+/// extobj::extobj!(impl Sensor {
+///     pub READING: i16,
+///     pub SAMPLES: [i16; 4],
+///     pub GAIN: u16 = 1,
+/// });
+///
+/// assert_eq!(ExtObjInline::<Sensor, 32>::required_size(), 12);
+///
+/// let mut sensor = ExtObjInline::<Sensor, 32>::new();
+/// sensor[*READING] = -40;
+/// assert_eq!(sensor[*GAIN], 1);
+/// ```
+pub struct ExtObjInline<O: __ExtObjDef, const N: usize> {
+    /// The number of fields created in `buf`.
+    len: usize,
+    buf: Buf<N>,
+    _marker: PhantomData<(O, O::Threading)>,
+}
+
+impl<O: __ExtObjDef, const N: usize> ExtObjInline<O, N> {
+    /// Creates a new `ExtObjInline` with every registered field initialized
+    /// to its default value.
+    ///
+    /// # Panics
+    /// Panics if the fields do not fit in `N` bytes, or if a fallible
+    /// initializer fails, see [`ExtObjInline::try_new`].
+    #[track_caller]
+    pub fn new() -> Self {
+        Self::try_new_with(&()).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Creates a new `ExtObjInline`, reporting the field which does not fit
+    /// in the buffer or whose initializer failed.
+    pub fn try_new() -> Result<Self, ExtObjInitError> {
+        Self::try_new_with(&())
+    }
+
+    /// Creates a new `ExtObjInline`, passing `ctx` to the fields registered
+    /// with `= init_with(f)`, see [`ExtObj::new_with`](crate::ExtObj::new_with).
+    pub fn try_new_with(ctx: &dyn Any) -> Result<Self, ExtObjInitError> {
+        let mut buf = Buf([MaybeUninit::uninit(); N]);
+
+        let len = loaded_defs::<O>().with(|defs| {
+            // drops the fields already created if an initializer fails or panics
+            let mut partial = Partial {
+                defs,
+                buf: &mut buf,
+                len: 0,
+            };
+
+            for def in defs {
+                if let Err(e) = fits::<N>(def) {
+                    return Err(ExtObjInitError::new(O::NAME, def.name, e.into()));
+                }
+
+                // SAFETY: the field fits in the buffer, at its offset
+                unsafe { def.init_at(ctx, partial.buf.addr(def.offset)) }
+                    .map_err(|e| ExtObjInitError::new(O::NAME, def.name, e))?;
+
+                partial.len += 1;
+            }
+
+            Ok(mem::take(&mut partial.len))
+        })?;
+
+        #[cfg(feature = "diagnostics")]
+        O::defs().count_created();
+
+        Ok(Self {
+            len,
+            buf,
+            _marker: PhantomData,
+        })
+    }
+
+    /// The number of bytes needed to hold the fields registered so far.
+    pub fn required_size() -> usize {
+        loaded_defs::<O>().with(|defs| defs.last().map_or(0, |def| def.offset + def.size))
+    }
+
+    /// Immutably borrows the value of the given variable.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
+    #[inline]
+    #[track_caller]
+    pub fn get<T>(&self, var: Var<O, T>) -> &T {
+        match self.try_get(var) {
+            Some(v) => v,
+            None => missing_field(var.0),
+        }
+    }
+
+    /// Mutably borrows the value of the given variable.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
+    #[inline]
+    #[track_caller]
+    pub fn get_mut<T>(&mut self, var: Var<O, T>) -> &mut T {
+        match self.try_get_mut(var) {
+            Some(v) => v,
+            None => missing_field(var.0),
+        }
+    }
+
+    /// Immutably borrows the value of the given variable, or returns `None` if
+    /// the variable was registered after this object was created.
+    #[inline]
+    pub fn try_get<T>(&self, var: Var<O, T>) -> Option<&T> {
+        #[cfg(feature = "metrics")]
+        O::defs().count_access(var.0, false);
+
+        let addr = self.buf.addr(self.offset(var.0)?);
+
+        // SAFETY: the field at this index holds a `T`
+        Some(unsafe { &*(addr as *const T) })
+    }
+
+    /// Mutably borrows the value of the given variable, or returns `None` if
+    /// the variable was registered after this object was created.
+    #[inline]
+    pub fn try_get_mut<T>(&mut self, var: Var<O, T>) -> Option<&mut T> {
+        #[cfg(feature = "metrics")]
+        O::defs().count_access(var.0, true);
+
+        let addr = self.buf.addr(self.offset(var.0)?);
+
+        // SAFETY: the field at this index holds a `T`
+        Some(unsafe { &mut *(addr as *mut T) })
+    }
+
+    /// The offset of the field at `index`, if it was created in this object.
+    #[inline]
+    fn offset(&self, index: usize) -> Option<usize> {
+        if index >= self.len {
+            return None;
+        }
+
+        Some(O::defs().with(|defs| defs[index].offset))
+    }
+}
+
+impl<O: __ExtObjDef, const N: usize> Debug for ExtObjInline<O, N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtObjInline")
+            .field("object", &O::NAME)
+            .field("fields", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<O: __ExtObjDef, const N: usize> Default for ExtObjInline<O, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<O: __ExtObjDef, const N: usize> Drop for ExtObjInline<O, N> {
+    fn drop(&mut self) {
+        O::defs().with(|defs| {
+            // SAFETY: the first `len` fields were created in the buffer
+            unsafe { drop_fields(defs, &mut self.buf, self.len) }
+        });
+
+        #[cfg(feature = "diagnostics")]
+        O::defs().count_dropped();
+    }
+}
+
+impl<O: __ExtObjDef, const N: usize, T> Index<Var<O, T>> for ExtObjInline<O, N> {
+    type Output = T;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, index: Var<O, T>) -> &Self::Output {
+        self.get(index)
+    }
+}
+
+impl<O: __ExtObjDef, const N: usize, T> IndexMut<Var<O, T>> for ExtObjInline<O, N> {
+    #[inline]
+    #[track_caller]
+    fn index_mut(&mut self, index: Var<O, T>) -> &mut Self::Output {
+        self.get_mut(index)
+    }
+}
+
+/// Checks the value of `def` fits in a buffer of `N` bytes, at its offset.
+fn fits<const N: usize>(def: &FieldDef) -> Result<(), String> {
+    let align = def.layout().align();
+
+    if align > ALIGN {
+        return Err(format!(
+            "its alignment of {align} bytes exceeds the {ALIGN} bytes of `ExtObjInline`"
+        ));
+    }
+
+    if def.offset + def.size > N {
+        return Err(format!(
+            "it does not fit in the {N} bytes of `ExtObjInline`, {} are needed",
+            def.offset + def.size
+        ));
+    }
+
+    Ok(())
+}
+
+/// Drops the first `len` fields of `buf`, see [`drop_order`].
+///
+/// # Safety
+/// The first `len` definitions must have created their value in `buf`.
+unsafe fn drop_fields<const N: usize>(defs: &[FieldDef], buf: &mut Buf<N>, len: usize) {
+    drop_order(defs, len, |i| unsafe {
+        defs[i].drop_at(buf.addr(defs[i].offset))
+    });
+}
+
+/// The fields of an object under construction, dropped unless finished.
+struct Partial<'a, const N: usize> {
+    defs: &'a [FieldDef],
+    buf: &'a mut Buf<N>,
+    len: usize,
+}
+
+impl<const N: usize> Drop for Partial<'_, N> {
+    fn drop(&mut self) {
+        // SAFETY: the first `len` fields were created in the buffer
+        unsafe { drop_fields(self.defs, self.buf, self.len) };
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{__ExtObjDef, DebugField, ExtObj, FieldAllocator, PatchError, registry::find_field};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+impl<O: __ExtObjDef, A: FieldAllocator> ExtObj<O, A> {
+    /// Deserializes the values of a patch into the matching fields, like an
+    /// admin API tweaking live extension state.
+    ///
+    /// The patch is keyed like [`var_by_name`](crate::var_by_name), by field
+    /// name or by `crate::NAME`. Each field is applied on its own: the fields
+    /// which cannot be applied are reported together in the error, and keep
+    /// their value while the others are updated. As with deserialization, the
+    /// values are not validated until [`ExtObj::validate_all`] is called.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// extobj::extobj!(struct Server);
+    /// extobj::extobj!(impl Server { pub PORT: u16 = 80, pub HOST: String });
+    ///
+    /// let mut server = extobj::ExtObj::<Server>::new();
+    /// let patch = HashMap::from([
+    ///     ("PORT".to_owned(), serde_json::json!(8080)),
+    ///     ("HOST".to_owned(), serde_json::json!(42)),
+    /// ]);
+    ///
+    /// let err = server.apply_patch(&patch).unwrap_err();
+    /// assert_eq!(server[*PORT], 8080);
+    /// assert_eq!(err.fields().map(|(key, _)| key).collect::<Vec<_>>(), ["HOST"]);
+    /// ```
+    pub fn apply_patch(&mut self, patch: &HashMap<String, Value>) -> Result<(), PatchError> {
+        let mut errors = Vec::new();
+
+        for (key, value) in patch {
+            // the lock is released before deserializing the value, which may
+            // construct other objects.
+            let field = O::defs().with(|defs| {
+                let index = find_field(defs, key)?;
+                Some((index, defs[index]))
+            });
+
+            let Some((slot, def)) =
+                field.and_then(|(index, def)| Some((self.0.get_mut(index)?, def)))
+            else {
+                errors.push((key.clone(), "unknown field".into()));
+                continue;
+            };
+
+            if def.readonly {
+                errors.push((key.clone(), "the field is read-only".into()));
+                continue;
+            }
+
+            let Some(deserialize) = def.deserialize else {
+                errors.push((key.clone(), "the field is not deserializable".into()));
+                continue;
+            };
+
+            let mut erased = <dyn erased_serde::Deserializer>::erase(value);
+
+            // SAFETY: the deserializer was registered with the field type
+            if let Err(e) = unsafe { deserialize(def.value_mut(slot), &mut erased) } {
+                errors.push((key.clone(), e.into()));
+            }
+        }
+
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        errors.sort_by(|a, b| a.0.cmp(&b.0));
+        Err(PatchError::new(O::NAME, errors))
+    }
+
+    /// A JSON map of the `crate::NAME` keys of the fields to their `Debug`
+    /// representation, for logs and bug reports. It includes the fields whose
+    /// type cannot be named by the caller.
+    ///
+    /// Fields which do not implement `Debug` are serialized instead, and the
+    /// others are represented by their type name in angle brackets.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extobj::extobj!(struct Job);
+    /// extobj::extobj!(impl Job { pub TRIES: u8 = 2, pub TAG: Option<String> });
+    ///
+    /// let job = extobj::ExtObj::<Job>::new();
+    /// let json = job.to_debug_json().to_string();
+    ///
+    /// assert!(json.contains(r#"::TRIES":"2""#));
+    /// assert!(json.contains(r#"::TAG":"None""#));
+    /// ```
+    pub fn to_debug_json(&self) -> Value {
+        O::defs().with(|defs| {
+            let fields = defs.iter().zip(&self.0).map(|(def, slot)| {
+                // SAFETY: the slot was created by its definition
+                let ptr = unsafe { def.value(slot) };
+
+                let value = match (def.debug, def.serialize) {
+                    (Some(debug), _) => Value::String(format!("{:?}", DebugField(ptr, debug))),
+                    // SAFETY: the serializer was registered with the field type
+                    (None, Some(serialize)) => serde_json::to_value(unsafe { &*serialize(ptr) })
+                        .unwrap_or_else(|e| Value::String(e.to_string())),
+                    (None, None) => Value::String(format!("<{}>", def.type_name)),
+                };
+
+                (def.key().to_string(), value)
+            });
+
+            Value::Object(fields.collect::<Map<_, _>>())
+        })
+    }
+}
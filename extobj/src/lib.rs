@@ -29,7 +29,8 @@
 //!
 //! Every field becomes a `static Var<Player, T>` singleton.
 //! `ExtObj<Player>` is a grow-only vector whose slot *i* stores a `T`
-//! created by the corresponding `Var`.  Construction / destruction are routed
+//! created by the corresponding `Var`, either in the slot itself when `T` fits
+//! in a `usize` or in its own allocation.  Construction / destruction are routed
 //! through an internal v-table generated by the macro.  All public APIs are
 //! safe; the unsafe internals are confined to the crate.
 //!
@@ -51,101 +52,1302 @@
 //! }
 //! ```
 //!
+//! ## Thread safety
+//!
+//! `ExtObj` is `Send + Sync`, so every field type must be `Send + Sync`,
+//! which is checked at compile time. An object declared `local` accepts any
+//! field type, but its [`LocalExtObj`] is neither `Send` nor `Sync`.
+//!
+//! ```
+//! use std::{cell::RefCell, rc::Rc};
+//!
+//! extobj::extobj!(struct Gui, local);
+//! extobj::extobj!(impl Gui { pub HISTORY: Rc<RefCell<Vec<String>>> });
+//!
+//! let gui = extobj::LocalExtObj::<Gui>::new();
+//! gui[*HISTORY].borrow_mut().push("open".into());
+//! ```
+//!
+//! ```compile_fail
+//! extobj::extobj!(struct Shared);
+//! extobj::extobj!(impl Shared { pub HISTORY: std::rc::Rc<u32> });
+//! ```
+//!
+//! ## Equality
+//!
+//! The `ExtObj` of an object declared `comparable` implements `Eq` and
+//! `Hash`, so it can be deduplicated in sets or used as a map key. Every
+//! field type must then implement `Eq` and `Hash`, which is checked at
+//! compile time.
+//!
+//! ```
+//! use std::collections::HashSet;
+//!
+//! extobj::extobj!(struct Route, comparable);
+//! extobj::extobj!(impl Route { pub PATH: String, pub PORT: u16 });
+//!
+//! let routes: HashSet<_> = [extobj::ExtObj::<Route>::new(), extobj::ExtObj::new()].into();
+//! assert_eq!(routes.len(), 1);
+//! ```
+//!
+//! ```compile_fail
+//! extobj::extobj!(struct Ratio, comparable);
+//! extobj::extobj!(impl Ratio { pub VALUE: f32 });
+//! ```
+//!
+//! ## Custom default values
+//!
+//! Fields are initialized with `Default::default()` unless a default
+//! expression is given.
+//!
+//! ```
+//! extobj::extobj!(struct Conn);
+//! extobj::extobj!(impl Conn {
+//!     pub RETRIES: u32 = 3,
+//!     pub NAME: String = "anon".into(),
+//! });
+//!
+//! let conn = extobj::ExtObj::<Conn>::new();
+//! assert_eq!(conn[*RETRIES], 3);
+//! assert_eq!(conn[*NAME], "anon");
+//! ```
+//!
+//! Field types which do not implement `Default` are created by an init
+//! function, either a path or a non-capturing closure.
+//!
+//! ```
+//! use std::sync::mpsc::{Sender, channel};
+//!
+//! extobj::extobj!(struct Worker);
+//! extobj::extobj!(impl Worker {
+//!     pub JOBS: Sender<u32> = init(|| channel().0),
+//!     pub ID: WorkerId = init(WorkerId::next),
+//! });
+//!
+//! pub struct WorkerId(u32);
+//!
+//! impl WorkerId {
+//!     fn next() -> Self {
+//!         WorkerId(1)
+//!     }
+//! }
+//!
+//! let worker = extobj::ExtObj::<Worker>::new();
+//! assert_eq!(worker[*ID].0, 1);
+//! ```
+//!
+//! ## Typed accessors
+//!
+//! `accessors = TraitName` generates a documented extension trait implemented
+//! for `ExtObj<Foo>` and `TrackedExtObj<Foo>`, with a `snake_case` getter, a
+//! `_mut` getter and a `set_` setter per field. Downstream crates typically
+//! name it after themselves and export it, so their fields are discoverable
+//! as methods.
+//!
+//! The setters are the interception point of every write: they call the
+//! observers registered with [`on_change`], mark the field dirty on a
+//! [`TrackedExtObj`], and return a `Result` for the fields with a
+//! `#[validate]`, rejecting the values refused by the validator. Writes
+//! through the `_mut` getter or `IndexMut` are not intercepted.
+//!
+//! ```
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//!
+//! extobj::extobj!(struct Page);
+//! extobj::extobj!(impl Page { pub HIT_COUNT: AtomicUsize }, accessors = pub PageExt);
+//!
+//! let mut page = extobj::ExtObj::<Page>::new();
+//! page.hit_count().fetch_add(1, Ordering::Relaxed);
+//! assert_eq!(page[*HIT_COUNT].load(Ordering::Relaxed), 1);
+//!
+//! page.set_hit_count(AtomicUsize::new(10));
+//! assert_eq!(page.hit_count().load(Ordering::Relaxed), 10);
+//! ```
+//!
+//! ## Borrow views
+//!
+//! `view = Name` generates a struct holding a mutable reference to every
+//! field of the block, so related fields can be modified together.
+//!
+//! ```
+//! extobj::extobj!(struct Sprite);
+//! extobj::extobj!(impl Sprite { pub X: f32, pub Y: f32 = 5.0 }, view = pub Pos);
+//!
+//! let mut sprite = extobj::ExtObj::<Sprite>::new();
+//! let pos = Pos::from(&mut sprite);
+//! *pos.x += *pos.y;
+//! assert_eq!(sprite[*X], 5.0);
+//! ```
+//!
+//! ## Namespaced statics
+//!
+//! `module = name` nests the generated statics in a module, so impl blocks of
+//! different objects can declare fields with the same name in one module. The
+//! accessor trait, the view and the registration function are re-exported
+//! next to the module.
+//!
+//! ```
+//! extobj::extobj!(struct Cat);
+//! extobj::extobj!(struct Dog);
+//! extobj::extobj!(impl Cat { pub NAME: String }, module = pub cat, accessors = pub CatExt);
+//! extobj::extobj!(impl Dog { pub NAME: String }, module = pub dog);
+//!
+//! # fn main() {
+//! let mut cat = extobj::ExtObj::<Cat>::new();
+//! cat.set_name("Tom".into());
+//! assert_eq!(cat[*cat::NAME], "Tom");
+//! assert!(extobj::ExtObj::<Dog>::new()[*dog::NAME].is_empty());
+//! # }
+//! ```
+//!
+//! ## Field attributes
+//!
+//! Outer attributes are forwarded to the generated statics, so fields can be
+//! documented or conditionally compiled. A field disabled by `#[cfg]` is not
+//! registered and is left out of the generated accessors and views.
+//!
+//! ```
+//! extobj::extobj!(struct Service);
+//! extobj::extobj!(impl Service {
+//!     /// Number of requests served.
+//!     pub REQUESTS: u64,
+//!     #[cfg(feature = "metrics")]
+//!     pub LATENCY: Vec<u64>,
+//! });
+//! ```
+//!
+//! ## Optional fields
+//!
+//! An `#[optional]` field is declared as `T` but stored as an [`Optional<T>`],
+//! empty until it is set. `T` does not need to implement `Default`, and the
+//! objects which never set the field do not allocate for it.
+//!
+//! ```
+//! struct Certificate(Vec<u8>);
+//!
+//! extobj::extobj!(struct Listener);
+//! extobj::extobj!(impl Listener {
+//!     #[optional]
+//!     pub TLS: Certificate,
+//! });
+//!
+//! let mut listener = extobj::ExtObj::<Listener>::new();
+//! assert!(listener[*TLS].get().is_none());
+//!
+//! listener[*TLS].set(Certificate(vec![0x30]));
+//! assert_eq!(listener[*TLS].get().map(|c| c.0.len()), Some(1));
+//! assert!(listener[*TLS].take().is_some());
+//! ```
+//!
+//! ## Read-only fields
+//!
+//! A `#[readonly]` field is published as a [`ReadOnlyVar`], which reads the
+//! field with `Index` but does not implement `IndexMut` nor `get_mut`. The
+//! declaring crate writes it through the `pub(crate)` static named after the
+//! field with a `_MUT` suffix, so extension authors can publish data without
+//! allowing other crates to mutate it. The field cannot be written by name
+//! either: [`var_by_name`] only downcasts it to a [`ReadVar`], and the JSON
+//! patches, the C API and the scripting bindings refuse to set it.
+//!
+//! ```
+//! extobj::extobj!(struct Download);
+//! extobj::extobj!(impl Download {
+//!     #[readonly]
+//!     pub RECEIVED: u64,
+//! });
+//!
+//! let mut download = extobj::ExtObj::<Download>::new();
+//! download[*RECEIVED_MUT] += 512;
+//! assert_eq!(download[*RECEIVED], 512);
+//! ```
+//!
+//! ## Validation
+//!
+//! `#[validate(f)]` attaches a `fn(&T) -> Result<(), E>` to a field, where
+//! `E` converts to a `Box<dyn Error + Send + Sync>`.
+//! [`ExtObj::set_checked`] rejects the values it refuses, and
+//! [`ExtObj::validate_all`] checks every field, for instance after
+//! deserializing user-supplied configuration.
+//!
+//! ```
+//! extobj::extobj!(struct Config);
+//! extobj::extobj!(impl Config {
+//!     #[validate(|n: &usize| if *n > 0 { Ok(()) } else { Err("no workers") })]
+//!     pub WORKERS: usize = 4,
+//! });
+//!
+//! let mut config = extobj::ExtObj::<Config>::new();
+//! assert!(config.set_checked(*WORKERS, 0).is_err());
+//!
+//! config[*WORKERS] = 0;
+//! assert!(config.validate_all().is_err());
+//! ```
+//!
+//! ## Drop order
+//!
+//! Fields are dropped in reverse registration order. As registration order
+//! depends on the order constructors run, fields referencing each other can
+//! declare `#[drop_priority(n)]`: fields are dropped by increasing priority
+//! (0 by default), so a shared resource outlives its users.
+//!
+//! ```
+//! extobj::extobj!(struct Db);
+//! extobj::extobj!(impl Db {
+//!     #[drop_priority(1)]
+//!     pub POOL: Vec<u8>,
+//!     pub CURSOR: usize,
+//! });
+//! ```
+//!
+//! ## Generic objects
+//!
+//! A marker type can be generic over `'static` types. Every instantiation has
+//! its own field registry.
+//!
+//! ```
+//! extobj::extobj!(pub struct Scope<T: 'static>);
+//! extobj::extobj!(impl Scope<u8> { pub SMALL: u32 });
+//! extobj::extobj!(impl Scope<String> { pub NAME: String });
+//!
+//! let scope = extobj::ExtObj::<Scope<String>>::new();
+//! assert_eq!(scope[*NAME], "");
+//! ```
+//!
+//! ## Singletons
+//!
+//! The `singleton` option generates a lazily created, process-wide instance
+//! behind a [`RwLock`].
+//!
+//! ```
+//! extobj::extobj!(pub struct AppCtx, singleton);
+//! extobj::extobj!(impl AppCtx { pub VERBOSE: bool });
+//!
+//! AppCtx::global().write()[*VERBOSE] = true;
+//! assert!(AppCtx::global().read()[*VERBOSE]);
+//! ```
+//!
+//! ## Sealed objects
+//!
+//! `extobj!(struct Name, sealed)` declares an object whose fields can only be
+//! declared by its own crate: an `extobj!(impl Name { ... })` block in another
+//! crate fails to compile, and [`register_field`] panics. This keeps the
+//! layout of hot objects under the control of their crate.
+//!
+//! ```
+//! extobj::extobj!(pub struct Frame, sealed);
+//! extobj::extobj!(impl Frame { pub TICKS: u64 });
+//!
+//! let frame = extobj::ExtObj::<Frame>::new();
+//! assert_eq!(frame[*TICKS], 0);
+//! ```
+//!
+//! `extobj!(struct Name, key = Key)` opens the object to the crates that can
+//! name the type `Key` instead, like the plugins of a curated ecosystem: their
+//! impl blocks end with `key = path::to::Key`, and the ones without it fail to
+//! compile.
+//!
+//! ```
+//! pub mod plugins {
+//!     pub struct Key;
+//! }
+//!
+//! extobj::extobj!(pub struct Host, key = plugins::Key);
+//! extobj::extobj!(impl Host { pub LOAD: f32 }, key = plugins::Key);
+//!
+//! let host = extobj::ExtObj::<Host>::new();
+//! assert_eq!(host[*LOAD], 0.0);
+//! ```
+//!
+//! ## Extended values
+//!
+//! [`Extended<T, O>`](Extended) pairs a value with an extension object: it
+//! dereferences to the value, and `ext()` gives access to the extensions.
+//!
+//! ## Foreign keys
+//!
+//! [`ExtMap`] attaches an extension object to keys of a type you do not own,
+//! such as identifiers, creating it on first access.
+//!
+//! ## Extensible structs
+//!
+//! The [`extensible`] attribute embeds the extension object in a struct of
+//! your own, which then serves as the object type.
+//!
+//! ```
+//! #[extobj::extensible]
+//! pub struct Document {
+//!     pub title: String,
+//! }
+//!
+//! extobj::extobj!(impl Document { pub WORDS: usize });
+//!
+//! let mut doc = Document { title: "draft".into(), __ext: Default::default() };
+//! doc.ext_mut()[*WORDS] = 250;
+//! assert_eq!(doc.ext()[*WORDS], 250);
+//! ```
+//!
+//! ## Thread-local instances
+//!
+//! [`thread_local()`] gives each thread its own lazily created instance of an
+//! object, for per-thread scratch state in worker pools.
+//!
+//! ## Live instances
+//!
+//! Objects created with [`ExtObj::new_live`] are shared behind a lock and
+//! listed by [`instances()`], so plugins can visit every existing object, for
+//! instance to [`upgrade`](ExtObj::upgrade) it after registering a field.
+//!
+//! ## Explicit registration
+//!
+//! Fields are registered by constructors running before `main`, which some
+//! targets (wasm, embedded) do not support. With `register = fn_name`, the
+//! macro generates `fn_name()` instead, which must be called before the
+//! fields are used; using a field before panics.
+//!
+//! ```
+//! extobj::extobj!(struct Device);
+//! extobj::extobj!(impl Device { pub IRQ: u8 = 3 }, register = register_fields);
+//!
+//! register_fields();
+//!
+//! let device = extobj::ExtObj::<Device>::new();
+//! assert_eq!(device[*IRQ], 3);
+//! ```
+//!
+//! ## Frozen registry
+//!
+//! Once every crate has registered its fields, `extobj::freeze::<Foo>()`
+//! turns the registry into an immutable snapshot so constructing and
+//! dropping objects no longer takes a lock.
+//!
+//! ## Frozen structs
+//!
+//! [`frozen_struct!`] declares a plain struct mirroring the fields of an
+//! object, converted from and into its `ExtObj`, so the final binary can
+//! switch to real fields in release builds.
+//!
+//! ## Memory usage
+//!
+//! [`ExtObj::memory_usage`] reports the bytes used by each field, including
+//! its heap allocations when its type implements [`HeapSize`], to find the
+//! crates attaching large data to hot objects.
+//!
+//! ## Object pools
+//!
+//! [`ExtObjPool`] recycles objects for high-churn workloads: a released object
+//! is reset to its default values in place, reusing the allocations of its
+//! fields.
+//!
+//! ## Save points
+//!
+//! [`ExtObj::snapshot`] clones the fields whose type implements `Clone`, and
+//! [`ExtObj::restore`] writes them back, so editors and games get save points
+//! without copying each field by hand.
+//!
+//! ## Transactions
+//!
+//! [`ExtObj::transaction`] stages the changes made by a closure, keeping them
+//! if it returns `Ok` and rolling them back if it returns `Err` or panics.
+//!
+//! ## Dirty tracking
+//!
+//! [`TrackedExtObj`] records the fields mutably borrowed since the last
+//! `clear_dirty()`, for incremental persistence and network replication.
+//!
+//! ## Change observers
+//!
+//! [`on_change`] registers a callback fired with the new value of a field
+//! each time it is written with [`ExtObj::set`] or [`ExtObj::update`], so
+//! reactive code can invalidate the state derived from it.
+//!
+//! ## Copy-on-write objects
+//!
+//! Cloning a [`CowExtObj`] shares its fields; a shared field is cloned the
+//! first time it is mutated, so snapshots of large objects are cheap.
+//!
+//! ## Column storage
+//!
+//! [`ExtObjVec`] stores many objects as one contiguous column per field, for
+//! bulk processing of a field across thousands of objects.
+//!
+//! ## Inline objects
+//!
+//! [`ExtObjInline`] keeps all the fields of an object in a fixed buffer of
+//! `N` bytes instead of allocating the fields which are not stored inline,
+//! for embedded targets.
+//!
+//! ## Interior mutability
+//!
+//! [`ExtObjCell`] borrows each field like a `RefCell`, so code holding a
+//! shared reference can mutate different fields at the same time without a
+//! lock over the whole object.
+//!
 //! ## Cargo features
 //!
-//! None – only depends on `std`.
+//! * `allocator_api` (nightly): implements [`FieldAllocator`] for every
+//!   `std::alloc::Allocator`, and [`Global`] becomes `std::alloc::Global`, so
+//!   crates naming it also need `#![feature(allocator_api)]`.
+//! * `bevy`: implements the `Component` trait of
+//!   [`bevy_ecs`](https://docs.rs/bevy_ecs) for `ExtObj` and `Extended`, so
+//!   plugins can add fields to the components of other crates, and adds
+//!   `DetectFieldChanges`, which only marks a component changed when a field
+//!   is set to a different value.
+//! * `binary`: enables `serde` and adds [`ExtObj::save`] and
+//!   [`ExtObj::load`], a compact [`postcard`](https://docs.rs/postcard)
+//!   snapshot of the serializable fields for save games and crash dumps. The
+//!   fields are written with their stable key and their `#[version(n)]`;
+//!   values of older versions are converted by the migrations registered
+//!   with [`register_migration`], unknown fields and fields which cannot be
+//!   migrated are skipped when loading, missing fields keep their default
+//!   value.
+//! * `bumpalo`: adds `ArenaExtObj`, whose fields are allocated in a
+//!   [`bumpalo`](https://docs.rs/bumpalo) arena, for many short-lived objects
+//!   created per frame or per request.
+//! * `checked`: keeps the `TypeId` check of [`DynObj`] in release builds,
+//!   turning an access with the wrong type into a panic instead of undefined
+//!   behavior, at the cost of storing the `TypeId` in every `DynObj`.
+//! * `diagnostics`: counts the objects created and dropped per object type,
+//!   reported by `stats()`, to track down leaked per-request objects.
+//! * `ffi`: adds [`ExtObjHandle`] and the `extern "C"` functions
+//!   [`extobj_get`], [`extobj_set`] and [`extobj_destroy`], so C and C++
+//!   plugins can read and write the fields of objects owned by the host by
+//!   name. [`DynObj::into_raw_parts`] hands type-erased values to C.
+//! * `json`: enables `serde` and adds [`ExtObj::apply_patch`], which
+//!   deserializes a map of field names to `serde_json` values into the
+//!   matching fields, reporting the fields it could not apply, and
+//!   [`ExtObj::to_debug_json`], which dumps every field for logs and bug
+//!   reports.
+//! * `linkme`: enables `extobj!(impl ..., backend = linkme)`, which collects
+//!   the fields in a [`linkme`](https://docs.rs/linkme) distributed slice at
+//!   link time instead of registering them from constructors running before
+//!   `main`. The fields of an object are registered, in a deterministic order,
+//!   the first time the object type is used.
+//! * `metrics`: counts the reads and writes of every field through its `Var`,
+//!   reported by [`FieldInfo`] from [`registry`], to find the hot extension
+//!   fields before optimizing their layout. Every access then touches a shared
+//!   atomic counter, this is meant for profiling builds.
+//! * `python`: enables `serde` and adds [`PyExtObj`], a
+//!   [`pyo3`](https://docs.rs/pyo3) class exposing an `ExtObj` to Python,
+//!   where `obj.FIELD` reads and writes the serializable fields, for tooling
+//!   scripts inspecting extension state.
+//! * `rayon`: adds parallel iterators over the columns of an [`ExtObjVec`],
+//!   like [`ExtObjVec::par_for_each`], and [`ExtObj::new_parallel`], which
+//!   runs the initializers of the fields on the rayon thread pool.
+//! * `rhai`: enables `serde` and adds [`RhaiExtObj`], which registers the
+//!   fields of an object as properties in a [`rhai`](https://docs.rs/rhai)
+//!   engine, so game scripts read and write them by name without glue code.
+//! * `serde`: implements `Serialize` and `Deserialize` for `ExtObj`. The
+//!   object is represented as a map keyed by `crate::FIELD`; fields whose type
+//!   is not serializable are skipped, unknown keys are ignored and missing
+//!   fields keep their default value. [`Schema`] implements `Serialize` to
+//!   describe this representation.
+//! * `std` (default): the objects, their registry and the macros need the
+//!   standard library. Without it, the crate is `no_std` and only needs
+//!   `alloc`: [`DynObj`], [`CloneDynObj`], [`LocalDynObj`] and
+//!   [`FieldAllocator`] remain, for type-erased values in embedded code. The
+//!   other features, except `allocator_api` and `checked`, enable `std`.
+//! * `tracing`: emits [`tracing`](https://docs.rs/tracing) spans around the
+//!   construction and the drop of every `ExtObj`, and a `DEBUG` event for each
+//!   registered field with its object, name, type and module, to diagnose the
+//!   order constructors run in and the cost of creating objects.
+//! * `zeroize`: enables the `#[zeroize]` field attribute. The values of the
+//!   marked fields, whose type implements
+//!   [`Zeroize`](https://docs.rs/zeroize), are wiped before they are dropped
+//!   or reset, so secrets do not linger in freed memory. [`ExtObj::take`]
+//!   moves the value out without freeing it; values replaced with
+//!   [`ExtObj::set`] are not wiped, store a `Zeroizing<T>` when they must be.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 #![warn(missing_docs)]
 #![allow(non_camel_case_types)] // used by the macro-generated marker types
 
+extern crate alloc;
+
+#[cfg(feature = "std")]
+#[doc(hidden)]
+#[path = "private.rs"]
+pub mod __private;
+mod allocator;
+#[cfg(feature = "bumpalo")]
+mod arena;
+#[cfg(feature = "bevy")]
+mod bevy;
+#[cfg(feature = "binary")]
+mod binary;
+#[cfg(feature = "std")]
+mod cell;
+#[cfg(feature = "std")]
+mod cow;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+#[cfg(feature = "std")]
+mod dirty;
 mod dynobj;
+#[cfg(feature = "std")]
+mod error;
+#[cfg(feature = "std")]
+mod extended;
+#[cfg(feature = "std")]
+mod extmap;
+#[cfg(feature = "std")]
+mod extvec;
+#[cfg(feature = "ffi")]
+mod ffi;
+#[cfg(feature = "std")]
+mod frozen;
+#[cfg(feature = "std")]
+mod future;
+#[cfg(feature = "std")]
+mod inline;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "std")]
+mod live;
+#[cfg(feature = "std")]
+mod memory;
+#[cfg(feature = "std")]
+mod observe;
+#[cfg(feature = "std")]
+mod optional;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "std")]
+mod pool;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "std")]
+mod readonly;
+#[cfg(feature = "std")]
+mod registry;
+#[cfg(feature = "std")]
+mod schema;
+#[cfg(feature = "rhai")]
+mod scripting;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(feature = "std")]
+mod snapshot;
+#[cfg(feature = "std")]
+mod thread_local;
+#[cfg(feature = "std")]
+mod transaction;
+#[cfg(feature = "std")]
+mod validate;
+#[cfg(feature = "std")]
+mod visit;
 
+pub use allocator::{FieldAllocator, Global};
+#[cfg(feature = "bumpalo")]
+pub use arena::ArenaExtObj;
+#[cfg(feature = "bevy")]
+pub use bevy::DetectFieldChanges;
+#[cfg(feature = "bevy")]
+pub use bevy_ecs;
+#[cfg(feature = "binary")]
+pub use binary::register_migration;
+#[cfg(feature = "bumpalo")]
+pub use bumpalo;
+#[cfg(feature = "std")]
+pub use cell::ExtObjCell;
+#[cfg(feature = "std")]
+pub use cow::CowExtObj;
+#[cfg(feature = "std")]
 pub use ctor;
-pub use dynobj::DynObj;
-pub use extobj_macro::extobj;
+#[cfg(feature = "diagnostics")]
+pub use diagnostics::{Stats, stats};
+#[cfg(feature = "std")]
+pub use dirty::TrackedExtObj;
+pub use dynobj::{CloneDynObj, DynObj, LocalDynObj};
+#[cfg(feature = "binary")]
+pub use error::BinaryError;
+#[cfg(feature = "json")]
+pub use error::PatchError;
+#[cfg(feature = "std")]
+pub use error::{ExtObjInitError, ValidationError};
+#[cfg(feature = "std")]
+pub use extended::Extended;
+#[cfg(feature = "std")]
+pub use extmap::ExtMap;
+#[cfg(feature = "std")]
+pub use extobj_macro::{extensible, extobj};
+#[cfg(feature = "std")]
+pub use extvec::ExtObjVec;
+#[cfg(feature = "ffi")]
+pub use ffi::{ExtObjHandle, extobj_destroy, extobj_get, extobj_set};
+#[cfg(feature = "std")]
+pub use future::BoxFuture;
+#[cfg(feature = "std")]
+pub use inline::ExtObjInline;
+#[cfg(feature = "linkme")]
+pub use linkme;
+#[cfg(feature = "std")]
+pub use live::{LiveExtObj, instances};
+#[cfg(feature = "std")]
+pub use memory::{FieldMemory, HeapSize, MemoryUsage};
+#[cfg(feature = "std")]
+pub use observe::on_change;
+#[cfg(feature = "std")]
+pub use optional::Optional;
+#[cfg(feature = "std")]
 pub use parking_lot::RwLock;
+#[cfg(feature = "std")]
+pub use pool::ExtObjPool;
+#[cfg(feature = "python")]
+pub use pyo3;
+#[cfg(feature = "python")]
+pub use python::PyExtObj;
+#[cfg(feature = "std")]
+pub use readonly::{ReadOnlyVar, ReadVar};
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use registry::Defs;
+#[cfg(feature = "std")]
+use registry::{Field, FieldDef, PartialSlots, Slot, drop_slots, loaded_defs};
+#[cfg(feature = "std")]
+pub use registry::{
+    FieldInfo, RegistryHandle, attach, freeze, register_field, registry, var_by_name,
+};
+#[cfg(feature = "rhai")]
+pub use rhai;
+#[cfg(feature = "std")]
+pub use schema::{FieldSchema, Schema, schema};
+#[cfg(feature = "rhai")]
+pub use scripting::RhaiExtObj;
+#[cfg(feature = "std")]
+pub use snapshot::Snapshot;
+#[cfg(feature = "std")]
 use std::{
+    any::{Any, TypeId},
     fmt::{self, Debug, Formatter},
     hash::{Hash, Hasher},
     marker::PhantomData,
-    ops::{Index, IndexMut},
+    mem,
+    ops::{Deref, Index, IndexMut},
+    sync::OnceLock,
 };
-
-#[doc(hidden)]
-pub type Defs = RwLock<Vec<(unsafe fn() -> usize, unsafe fn(usize))>>;
+#[cfg(feature = "std")]
+pub use thread_local::thread_local;
+#[cfg(feature = "std")]
+pub use transaction::Transaction;
+#[cfg(feature = "std")]
+pub use visit::{FieldRef, FieldVisitor};
+#[cfg(feature = "zeroize")]
+pub use zeroize;
 
 /// # Note
 /// This trait is for used only in macros.
+#[cfg(feature = "std")]
 #[doc(hidden)]
 pub trait __ExtObjDef: 'static {
+    const NAME: &'static str;
+
+    /// `true` for the objects declared `sealed` or with a `key`, which do
+    /// not accept fields registered at runtime.
+    const SEALED: bool = false;
+
+    /// [`__private::Shared`], or [`__private::Local`] when the fields may be
+    /// `!Send`. Decides whether `ExtObj<Self>` is `Send + Sync`.
+    type Threading: 'static;
+
+    /// [`__private::Comparable`] for the objects declared `comparable`, whose
+    /// fields must be `Eq + Hash`, or [`__private::Incomparable`]. Decides
+    /// whether `ExtObj<Self>` is `Eq + Hash`.
+    type Comparability: 'static;
+
     fn defs() -> &'static Defs;
 }
 
 /// An extendable struct that be extented across crate.
-pub struct ExtObj<O: __ExtObjDef>(Vec<usize>, PhantomData<O>);
+///
+/// It is `Send + Sync` unless the object type is declared `local`, in which
+/// case its fields do not have to be `Send + Sync`.
+///
+/// The values whose type fits in a `usize` and has no drop glue, like
+/// integers, `bool` or small `Copy` enums, are stored in the object itself.
+/// The other fields are allocated by `A`, the global allocator by default, see
+/// [`ExtObj::new_in`].
+#[cfg(feature = "std")]
+pub struct ExtObj<O: __ExtObjDef, A: FieldAllocator = Global>(
+    Vec<Slot>,
+    PhantomData<(O, O::Threading)>,
+    A,
+);
+
+/// An [`ExtObj`] of an object type declared `local`, whose fields may be
+/// `!Send` like `Rc` or `RefCell`. It is neither `Send` nor `Sync`.
+///
+/// This alias documents the intent at use sites; objects which are not
+/// `local` keep a thread-safe `ExtObj`.
+///
+/// # Example
+///
+/// ```
+/// use std::{cell::RefCell, rc::Rc};
+/// use extobj::LocalExtObj;
+///
+/// extobj::extobj!(struct Widget, local);
+/// extobj::extobj!(impl Widget { pub CLICKS: Rc<RefCell<u32>> });
+///
+/// let widget = LocalExtObj::<Widget>::new();
+/// let clicks = Rc::clone(&widget[*CLICKS]);
+/// *clicks.borrow_mut() += 1;
+/// assert_eq!(*widget[*CLICKS].borrow(), 1);
+/// ```
+#[cfg(feature = "std")]
+pub type LocalExtObj<O> = ExtObj<O>;
 
+#[cfg(feature = "std")]
 impl<O: __ExtObjDef> ExtObj<O> {
     /// Creates a new, empty `ExtObj` with every registered field initialized to its
     /// default value.
+    ///
+    /// # Panics
+    /// Panics if a fallible initializer fails, see [`ExtObj::try_new`].
     pub fn new() -> Self {
-        Self(
-            O::defs()
-                .read()
-                .iter()
-                .map(|(f, _)| unsafe { f() })
-                .collect(),
-            PhantomData,
-        )
+        Self::new_with(&())
+    }
+
+    /// Creates a new `ExtObj`, passing `ctx` to the fields registered with
+    /// `= init_with(f)` where `f` is a `fn(&dyn Any) -> T`.
+    ///
+    /// This lets extension crates initialize their fields from the application
+    /// configuration. [`ExtObj::new`] passes `&()` as the context.
+    ///
+    /// # Panics
+    /// Panics if a fallible initializer fails, see [`ExtObj::try_new_with`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// struct AppConfig {
+    ///     workers: usize,
+    /// }
+    ///
+    /// extobj::extobj!(struct Server);
+    /// extobj::extobj!(impl Server {
+    ///     WORKERS: usize = init_with(|ctx| {
+    ///         ctx.downcast_ref::<AppConfig>().map_or(1, |c| c.workers)
+    ///     }),
+    /// });
+    ///
+    /// let server = extobj::ExtObj::<Server>::new_with(&AppConfig { workers: 8 });
+    /// assert_eq!(server[*WORKERS], 8);
+    ///
+    /// let server = extobj::ExtObj::<Server>::new();
+    /// assert_eq!(server[*WORKERS], 1);
+    /// ```
+    pub fn new_with(ctx: &dyn Any) -> Self {
+        Self::try_new_with(ctx).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Creates a new `ExtObj`, reporting the field whose initializer failed.
+    ///
+    /// Fields registered with `= try_init(f)` are created by a
+    /// `fn() -> Result<T, Box<dyn Error + Send + Sync>>`. The fields already
+    /// initialized are dropped when one fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extobj::extobj!(struct Config);
+    /// extobj::extobj!(impl Config {
+    ///     PORT: u16 = try_init(|| Ok("8080".parse()?)),
+    /// });
+    ///
+    /// let config = extobj::ExtObj::<Config>::try_new().unwrap();
+    /// assert_eq!(config[*PORT], 8080);
+    /// ```
+    pub fn try_new() -> Result<Self, ExtObjInitError> {
+        Self::try_new_with(&())
+    }
+
+    /// Creates a new `ExtObj` with the given context, reporting the field whose
+    /// initializer failed.
+    ///
+    /// See [`ExtObj::new_with`] and [`ExtObj::try_new`].
+    pub fn try_new_with(ctx: &dyn Any) -> Result<Self, ExtObjInitError> {
+        Self::try_new_with_in(ctx, Global)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<O: __ExtObjDef, A: FieldAllocator> ExtObj<O, A> {
+    /// Creates a new `ExtObj` whose fields are allocated by `alloc`.
+    ///
+    /// # Panics
+    /// Panics if a fallible initializer fails, see [`ExtObj::try_new_in`].
+    ///
+    /// # Example
+    ///
+    /// See [`FieldAllocator`].
+    pub fn new_in(alloc: A) -> Self {
+        Self::try_new_in(alloc).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Creates a new `ExtObj` whose fields are allocated by `alloc`, reporting
+    /// the field whose initializer failed.
+    pub fn try_new_in(alloc: A) -> Result<Self, ExtObjInitError> {
+        Self::try_new_with_in(&(), alloc)
+    }
+
+    /// Creates a new `ExtObj` with the given context whose fields are
+    /// allocated by `alloc`, reporting the field whose initializer failed.
+    ///
+    /// See [`ExtObj::new_with`] and [`ExtObj::try_new`].
+    pub fn try_new_with_in(ctx: &dyn Any, alloc: A) -> Result<Self, ExtObjInitError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("ExtObj::new", object = O::NAME).entered();
+
+        let slots = loaded_defs::<O>().with(|defs| {
+            // drops the fields already created if an initializer fails or panics
+            let mut slots = PartialSlots::new(defs, &alloc);
+
+            for def in defs {
+                match unsafe { def.create(ctx, &alloc) } {
+                    Ok(slot) => slots.push(slot),
+                    Err(e) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!(field = def.name, error = %e, "field initializer failed");
+
+                        return Err(ExtObjInitError::new(O::NAME, def.name, e));
+                    }
+                }
+            }
+
+            Ok(slots.finish())
+        })?;
+
+        Ok(Self::from_slots(slots, alloc))
+    }
+
+    /// Wraps slots created by the definitions at the same index with `alloc`.
+    #[inline]
+    pub(crate) fn from_slots(slots: Vec<Slot>, alloc: A) -> Self {
+        #[cfg(feature = "diagnostics")]
+        O::defs().count_created();
+
+        Self(slots, PhantomData, alloc)
+    }
+
+    /// The allocator of the fields.
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        &self.2
+    }
+
+    /// Initializes every field registered after this object was created with
+    /// its default value.
+    ///
+    /// Long-lived objects can call this to adopt fields of crates loaded at
+    /// runtime. Does nothing when the object is up to date. Context
+    /// initializers receive `&()`.
+    ///
+    /// # Panics
+    /// Panics if a fallible initializer fails.
+    pub fn upgrade(&mut self) {
+        loaded_defs::<O>().with(|defs| {
+            // the registry is grow-only, so it is never shorter than the object
+            let missing = &defs[self.0.len()..];
+            self.0.reserve(missing.len());
+
+            for def in missing {
+                match unsafe { def.create(&(), &self.2) } {
+                    Ok(slot) => self.0.push(slot),
+                    Err(e) => panic!("{}", ExtObjInitError::new(O::NAME, def.name, e)),
+                }
+            }
+        });
+    }
+
+    /// Resets every field to its default value, reusing the allocations of the
+    /// fields, and initializes the fields registered since the object was
+    /// created.
+    ///
+    /// Context initializers receive `&()`.
+    ///
+    /// # Panics
+    /// Panics if a fallible initializer fails. The fields reset before the
+    /// failing one keep their default value, the others their current value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extobj::extobj!(struct Request);
+    /// extobj::extobj!(impl Request { HEADERS: Vec<String> });
+    ///
+    /// let mut request = extobj::ExtObj::<Request>::new();
+    /// request[*HEADERS].push("accept: */*".into());
+    ///
+    /// request.reset();
+    /// assert!(request[*HEADERS].is_empty());
+    /// ```
+    pub fn reset(&mut self) {
+        loaded_defs::<O>().with(|defs| {
+            for (def, slot) in defs.iter().zip(&mut self.0) {
+                if let Err(e) = unsafe { def.reset(slot, &()) } {
+                    panic!("{}", ExtObjInitError::new(O::NAME, def.name, e));
+                }
+            }
+        });
+
+        self.upgrade();
     }
 
     /// Immutably borrows the value of the given variable.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
     #[inline]
+    #[track_caller]
     pub fn get<T>(&self, var: Var<O, T>) -> &T {
-        unsafe { &*(*self.0.get_unchecked(var.0) as *const T) }
+        match self.try_get(var) {
+            Some(v) => v,
+            None => missing_field(var.0),
+        }
     }
 
     /// Mutably borrows the value of the given variable.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
     #[inline]
+    #[track_caller]
     pub fn get_mut<T>(&mut self, var: Var<O, T>) -> &mut T {
-        unsafe { &mut *(*self.0.get_unchecked(var.0) as *mut T) }
+        match self.try_get_mut(var) {
+            Some(v) => v,
+            None => missing_field(var.0),
+        }
+    }
+
+    /// Immutably borrows the value of the given variable, or returns `None` if
+    /// the variable was registered after this object was created.
+    #[inline]
+    pub fn try_get<T>(&self, var: Var<O, T>) -> Option<&T> {
+        #[cfg(feature = "metrics")]
+        O::defs().count_access(var.0, false);
+
+        self.0.get(var.0).map(|slot| unsafe { slot.get() })
+    }
+
+    /// Mutably borrows the value of the given variable, or returns `None` if
+    /// the variable was registered after this object was created.
+    #[inline]
+    pub fn try_get_mut<T>(&mut self, var: Var<O, T>) -> Option<&mut T> {
+        #[cfg(feature = "metrics")]
+        O::defs().count_access(var.0, true);
+
+        self.0.get_mut(var.0).map(|slot| unsafe { slot.get_mut() })
+    }
+
+    /// Sets the value of the given variable, dropping the previous one, then
+    /// calls the observers registered with [`on_change`].
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
+    #[inline]
+    #[track_caller]
+    pub fn set<T>(&mut self, var: Var<O, T>, value: T) {
+        *self.get_mut(var) = value;
+        self.notify(var.0);
+    }
+
+    /// Replaces the value of the given variable, returning the previous one.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
+    #[inline]
+    #[track_caller]
+    pub fn replace<T>(&mut self, var: Var<O, T>, value: T) -> T {
+        mem::replace(self.get_mut(var), value)
+    }
+
+    /// Takes the value of the given variable, leaving `T::default()` in its place.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
+    #[inline]
+    #[track_caller]
+    pub fn take<T: Default>(&mut self, var: Var<O, T>) -> T {
+        mem::take(self.get_mut(var))
+    }
+
+    /// Immutably borrows the value of a variable whose type is only known at
+    /// runtime.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use extobj::UntypedVar;
+    ///
+    /// extobj::extobj!(struct Ctx);
+    /// extobj::extobj!(impl Ctx { pub RETRIES: u32 = 3, pub NAME: String });
+    ///
+    /// let vars: Vec<UntypedVar<Ctx>> = vec![RETRIES.untyped(), NAME.untyped()];
+    /// let ctx = extobj::ExtObj::<Ctx>::new();
+    ///
+    /// assert_eq!(ctx.get_dyn(vars[0]).downcast_ref::<u32>(), Some(&3));
+    /// assert_eq!(vars[1].downcast::<String>(), Some(*NAME));
+    /// ```
+    #[track_caller]
+    pub fn get_dyn(&self, var: UntypedVar<O>) -> &dyn Any {
+        let slot = self.slot(var.index);
+        let def = O::defs().with(|defs| defs[var.index]);
+        unsafe { &*(def.as_any)(def.value(slot)) }
+    }
+
+    /// Mutably borrows the value of a variable whose type is only known at
+    /// runtime.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created,
+    /// or if it is a `#[readonly]` field found by name.
+    #[track_caller]
+    pub fn get_dyn_mut(&mut self, var: UntypedVar<O>) -> &mut dyn Any {
+        if !var.writable {
+            readonly_field::<O>(var.index);
+        }
+
+        let def = O::defs().with(|defs| defs[var.index]);
+        let slot = match self.0.get_mut(var.index) {
+            Some(slot) => slot,
+            None => missing_field(var.index),
+        };
+
+        unsafe { &mut *(def.as_any)(def.value_mut(slot)) }
+    }
+
+    /// Iterates over the fields of this object as `(name, value)` pairs, in
+    /// registration order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extobj::extobj!(struct Ctx);
+    /// extobj::extobj!(impl Ctx { pub RETRIES: u32 = 3, pub NAME: String });
+    ///
+    /// let ctx = extobj::ExtObj::<Ctx>::new();
+    ///
+    /// for (name, value) in ctx.iter() {
+    ///     if let Some(n) = value.downcast_ref::<u32>() {
+    ///         println!("{name} = {n}");
+    ///     }
+    /// }
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &dyn Any)> {
+        // collected so that the registry lock is not held while iterating
+        let fields: Vec<_> = O::defs().with(|defs| {
+            defs.iter()
+                .zip(&self.0)
+                .map(|(def, slot)| (def.name, unsafe { &*(def.as_any)(def.value(slot)) }))
+                .collect()
+        });
+
+        fields.into_iter()
+    }
+
+    /// Iterates mutably over the fields of this object as `(name, value)`
+    /// pairs, in registration order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&'static str, &mut dyn Any)> {
+        let fields: Vec<_> = O::defs().with(|defs| {
+            defs.iter()
+                .zip(&mut self.0)
+                // SAFETY: every value has its own slot or allocation
+                .map(|(def, slot)| (def.name, unsafe { &mut *(def.as_any)(def.value_mut(slot)) }))
+                .collect()
+        });
+
+        fields.into_iter()
+    }
+
+    /// Calls `visitor` with every field of this object, in registration order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extobj::extobj!(struct Ctx);
+    /// extobj::extobj!(impl Ctx { pub RETRIES: u32 = 3, pub NAME: String });
+    ///
+    /// let ctx = extobj::ExtObj::<Ctx>::new();
+    /// let mut total = 0;
+    ///
+    /// ctx.visit(&mut |field: extobj::FieldRef<'_>| {
+    ///     if let Some(n) = field.downcast_ref::<u32>() {
+    ///         total += n;
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(total, 3);
+    /// ```
+    pub fn visit(&self, visitor: &mut impl FieldVisitor) {
+        O::defs().with(|defs| {
+            for (def, slot) in defs.iter().zip(&self.0) {
+                // SAFETY: the slot was created by `def` and is borrowed by `self`
+                visitor.visit(unsafe { FieldRef::new(*def, def.value(slot)) });
+            }
+        });
+    }
+
+    #[inline]
+    #[track_caller]
+    fn slot(&self, index: usize) -> &Slot {
+        match self.0.get(index) {
+            Some(slot) => slot,
+            None => missing_field(index),
+        }
+    }
+
+    /// Immutably borrows the value of the given variable without bounds checking.
+    ///
+    /// # Safety
+    /// The variable must have been registered before this object was created.
+    #[inline]
+    pub unsafe fn get_unchecked<T>(&self, var: Var<O, T>) -> &T {
+        unsafe { self.0.get_unchecked(var.0).get() }
+    }
+
+    /// Mutably borrows the value of the given variable without bounds checking.
+    ///
+    /// # Safety
+    /// The variable must have been registered before this object was created.
+    #[inline]
+    pub unsafe fn get_unchecked_mut<T>(&mut self, var: Var<O, T>) -> &mut T {
+        unsafe { self.0.get_unchecked_mut(var.0).get_mut() }
     }
 }
 
-impl<O: __ExtObjDef> Default for ExtObj<O> {
+#[cfg(feature = "std")]
+impl<O: __ExtObjDef, A: FieldAllocator + Default> Default for ExtObj<O, A> {
     #[inline]
     fn default() -> Self {
-        Self::new()
+        Self::new_in(A::default())
     }
 }
 
-impl<O: __ExtObjDef> Drop for ExtObj<O> {
-    fn drop(&mut self) {
-        // single, shared read-lock
-        let defs = O::defs().read();
-        let defs = defs.as_slice();
-
-        // SAFETY: both slices have identical length
-        unsafe {
-            for i in 0..self.0.len() {
-                let ptr = *self.0.get_unchecked(i);
-                let (_, drop_fn) = *defs.get_unchecked(i);
-                drop_fn(ptr);
+#[cfg(feature = "std")]
+impl<O: __ExtObjDef, A: FieldAllocator> Debug for ExtObj<O, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        O::defs().with(|defs| {
+            let mut s = f.debug_struct(O::NAME);
+            let mut non_exhaustive = false;
+
+            for (def, slot) in defs.iter().zip(&self.0) {
+                match def.debug {
+                    Some(debug) => {
+                        s.field(def.name, &DebugField(unsafe { def.value(slot) }, debug));
+                    }
+                    None => non_exhaustive = true,
+                }
             }
-        }
+
+            if non_exhaustive {
+                s.finish_non_exhaustive()
+            } else {
+                s.finish()
+            }
+        })
     }
 }
 
-impl<O: __ExtObjDef, T> Index<Var<O, T>> for ExtObj<O> {
+#[cfg(feature = "std")]
+struct DebugField(usize, __private::DebugFn);
+
+#[cfg(feature = "std")]
+impl Debug for DebugField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // SAFETY: the formatter was registered with the field type
+        unsafe { (self.1)(self.0, f) }
+    }
+}
+
+/// Two objects are equal when every field is equal.
+///
+/// Only the objects declared with `extobj!(struct Name, comparable)` can be
+/// compared, their fields being checked to implement `Eq` and `Hash` at
+/// compile time. Objects which do not hold the same number of fields (see
+/// [`ExtObj::upgrade`]) are never equal.
+#[cfg(feature = "std")]
+impl<O, A> PartialEq for ExtObj<O, A>
+where
+    O: __ExtObjDef<Comparability = __private::Comparable>,
+    A: FieldAllocator,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && O::defs().with(|defs| {
+                defs.iter()
+                    .zip(self.0.iter().zip(&other.0))
+                    .all(|(def, (a, b))| {
+                        let eq = def.eq.unwrap_or_else(|| unsupported::<O>(def.name, "Eq"));
+                        // SAFETY: the comparator was registered with the field type
+                        unsafe { eq(def.value(a), def.value(b)) }
+                    })
+            })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<O, A> Eq for ExtObj<O, A>
+where
+    O: __ExtObjDef<Comparability = __private::Comparable>,
+    A: FieldAllocator,
+{
+}
+
+#[cfg(feature = "std")]
+impl<O, A> Hash for ExtObj<O, A>
+where
+    O: __ExtObjDef<Comparability = __private::Comparable>,
+    A: FieldAllocator,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.len().hash(state);
+
+        O::defs().with(|defs| {
+            for (def, slot) in defs.iter().zip(&self.0) {
+                let hash = def
+                    .hash
+                    .unwrap_or_else(|| unsupported::<O>(def.name, "Hash"));
+                // SAFETY: the hasher was registered with the field type
+                unsafe { hash(def.value(slot), state) };
+            }
+        });
+    }
+}
+
+#[cfg(feature = "std")]
+impl<O: __ExtObjDef, A: FieldAllocator> Drop for ExtObj<O, A> {
+    fn drop(&mut self) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("ExtObj::drop", object = O::NAME).entered();
+
+        // single, shared read-lock (none once frozen)
+        // SAFETY: the registry is grow-only, so every slot has a definition
+        O::defs().with(|defs| unsafe { drop_slots(defs, &self.0, &self.2) });
+
+        #[cfg(feature = "diagnostics")]
+        O::defs().count_dropped();
+    }
+}
+
+#[cfg(feature = "std")]
+impl<O: __ExtObjDef, A: FieldAllocator, T> Index<Var<O, T>> for ExtObj<O, A> {
     type Output = T;
 
     #[inline]
+    #[track_caller]
     fn index(&self, index: Var<O, T>) -> &Self::Output {
         self.get(index)
     }
 }
 
-impl<O: __ExtObjDef, T> IndexMut<Var<O, T>> for ExtObj<O> {
+#[cfg(feature = "std")]
+impl<O: __ExtObjDef, A: FieldAllocator, T> IndexMut<Var<O, T>> for ExtObj<O, A> {
     #[inline]
+    #[track_caller]
     fn index_mut(&mut self, index: Var<O, T>) -> &mut Self::Output {
         self.get_mut(index)
     }
@@ -155,9 +1357,11 @@ impl<O: __ExtObjDef, T> IndexMut<Var<O, T>> for ExtObj<O> {
 ///
 /// Values of this type are produced by the `extobj!` macro and are normally
 /// used as `static` items.
+#[cfg(feature = "std")]
 #[repr(transparent)]
-pub struct Var<O, T>(usize, PhantomData<(O, T)>);
+pub struct Var<O, T>(usize, PhantomData<fn() -> (O, T)>);
 
+#[cfg(feature = "std")]
 impl<O, T> Var<O, T> {
     /// Erase the type of the variable and extract the information representing this
     /// variable in the extobj type.
@@ -167,6 +1371,7 @@ impl<O, T> Var<O, T> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<O, T> Clone for Var<O, T> {
     #[inline]
     fn clone(&self) -> Self {
@@ -174,16 +1379,20 @@ impl<O, T> Clone for Var<O, T> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<O, T> Copy for Var<O, T> {}
 
+#[cfg(feature = "std")]
 impl<O, T> Debug for Var<O, T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_tuple("Var").field(&self.0).finish()
     }
 }
 
+#[cfg(feature = "std")]
 impl<O, T> Eq for Var<O, T> {}
 
+#[cfg(feature = "std")]
 impl<O, T> Hash for Var<O, T> {
     #[inline]
     fn hash<H: Hasher>(&self, state: &mut H) {
@@ -191,6 +1400,7 @@ impl<O, T> Hash for Var<O, T> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<O, T> PartialEq for Var<O, T> {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
@@ -198,31 +1408,216 @@ impl<O, T> PartialEq for Var<O, T> {
     }
 }
 
-impl<O: __ExtObjDef, T: Default + 'static> Var<O, T> {
+#[cfg(feature = "std")]
+impl<O: __ExtObjDef, T: 'static> Var<O, T> {
     #[doc(hidden)]
-    pub fn __new() -> Self {
-        let mut defs = O::defs().write();
-        let index = defs.len();
+    pub fn __new(field: Field<T>) -> Self
+    where
+        O::Threading: __private::Accepts<T>,
+        O::Comparability: __private::Compares<T>,
+    {
+        Self::register(field)
+    }
 
-        defs.push((init_default::<T>, dropper::<T>));
+    /// Registers the field, which the caller checked the object accepts.
+    pub(crate) fn register(field: Field<T>) -> Self {
+        Self(O::defs().register(O::NAME, field.def), PhantomData)
+    }
 
-        Self(index, PhantomData)
+    /// The name of the field, as declared in the `extobj!` macro.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extobj::extobj!(struct Ctx);
+    /// extobj::extobj!(impl Ctx { pub RETRIES: u32 });
+    ///
+    /// assert_eq!(RETRIES.name(), "RETRIES");
+    /// assert_eq!(RETRIES.type_name(), "u32");
+    /// println!("declared in {}", RETRIES.origin());
+    /// ```
+    pub fn name(self) -> &'static str {
+        self.def().name
+    }
+
+    /// The type name of the field, from [`std::any::type_name`].
+    pub fn type_name(self) -> &'static str {
+        self.def().type_name
+    }
+
+    /// The module path where the field is declared, from [`module_path!`].
+    /// Its first segment is the declaring crate.
+    pub fn origin(self) -> &'static str {
+        self.def().origin
+    }
+
+    fn def(self) -> FieldDef {
+        loaded_defs::<O>().with(|defs| defs[self.0])
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn __index(self) -> usize {
+        self.0
+    }
+
+    /// Erases the type of the variable, keeping its `TypeId` to downcast it
+    /// back.
+    #[inline]
+    pub fn untyped(self) -> UntypedVar<O> {
+        UntypedVar {
+            index: self.0,
+            type_id: TypeId::of::<T>(),
+            writable: true,
+            _marker: PhantomData,
+        }
     }
 }
 
-unsafe fn init_default<T: Default>() -> usize {
-    Box::into_raw(Box::<T>::default()) as usize
+#[cfg(feature = "std")]
+impl<O: __ExtObjDef, T: 'static> From<Var<O, T>> for UntypedVar<O> {
+    #[inline]
+    fn from(var: Var<O, T>) -> Self {
+        var.untyped()
+    }
+}
+
+/// A variable registered by an explicit call instead of a constructor
+/// running before `main`.
+///
+/// Produced by `extobj!(impl ..., register = fn_name)` for targets where
+/// life-before-main is not available (wasm, some embedded targets). It
+/// dereferences to its [`Var`] once `fn_name()` has been called.
+#[cfg(feature = "std")]
+pub struct LazyVar<O, T> {
+    var: OnceLock<Var<O, T>>,
+    name: &'static str,
+    register_fn: &'static str,
 }
 
-unsafe fn dropper<T>(ptr: usize) {
-    unsafe {
-        drop(Box::from_raw(ptr as *mut T));
+// SAFETY: a `Var` is only an index, it never holds a `T` or an `O`
+#[cfg(feature = "std")]
+unsafe impl<O, T> Sync for LazyVar<O, T> {}
+
+#[cfg(feature = "std")]
+impl<O, T> LazyVar<O, T> {
+    #[doc(hidden)]
+    pub const fn __new(name: &'static str, register_fn: &'static str) -> Self {
+        Self {
+            var: OnceLock::new(),
+            name,
+            register_fn,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn __set(&self, var: Var<O, T>) {
+        let _ = self.var.set(var);
     }
+
+    /// Returns the variable, or `None` if it has not been registered yet.
+    #[inline]
+    pub fn get(&self) -> Option<Var<O, T>> {
+        self.var.get().copied()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<O, T> Deref for LazyVar<O, T> {
+    type Target = Var<O, T>;
+
+    /// # Panics
+    /// Panics if the registration function has not been called.
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &Var<O, T> {
+        match self.var.get() {
+            Some(var) => var,
+            None => not_registered(self.name, self.register_fn),
+        }
+    }
+}
+
+/// A variable registered at link time, produced by
+/// `extobj!(impl ..., backend = linkme)`.
+///
+/// The fields of an object are collected in a `linkme` distributed slice and
+/// registered, in slice order, the first time the object type is used. No
+/// constructor runs before `main`.
+#[cfg(feature = "linkme")]
+pub struct LinkedVar<O, T> {
+    entry: &'static __private::LinkedField,
+    var: OnceLock<Var<O, T>>,
+}
+
+// SAFETY: a `Var` is only an index, it never holds a `T` or an `O`
+#[cfg(feature = "linkme")]
+unsafe impl<O, T> Sync for LinkedVar<O, T> {}
+
+#[cfg(feature = "linkme")]
+impl<O, T> LinkedVar<O, T> {
+    #[doc(hidden)]
+    pub const fn __new(entry: &'static __private::LinkedField) -> Self {
+        Self {
+            entry,
+            var: OnceLock::new(),
+        }
+    }
+}
+
+#[cfg(feature = "linkme")]
+impl<O: __ExtObjDef, T> Deref for LinkedVar<O, T> {
+    type Target = Var<O, T>;
+
+    #[inline]
+    fn deref(&self) -> &Var<O, T> {
+        self.var.get_or_init(|| {
+            loaded_defs::<O>();
+            Var(self.entry.index(), PhantomData)
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn not_registered(name: &str, register_fn: &str) -> ! {
+    panic!("field `{name}` is not registered, call `{register_fn}()` first")
+}
+
+#[cfg(feature = "std")]
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn missing_field(index: usize) -> ! {
+    panic!("field #{index} was registered after this object was created")
+}
+
+#[cfg(feature = "std")]
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn readonly_field<O: __ExtObjDef>(index: usize) -> ! {
+    let name = O::defs().with(|defs| defs[index].name);
+    panic!("field `{name}` of `{}` is read-only", O::NAME)
+}
+
+#[cfg(feature = "std")]
+#[cold]
+#[inline(never)]
+fn unsupported<O: __ExtObjDef>(field: &str, capability: &str) -> ! {
+    panic!(
+        "field `{field}` of `{}` does not implement `{capability}`",
+        O::NAME
+    )
 }
 
 /// The variable identifier inside the extobj. This can be hashed.
+#[cfg(feature = "std")]
 pub struct VarId<O>(usize, PhantomData<O>);
 
+#[cfg(feature = "std")]
 impl<O> Clone for VarId<O> {
     #[inline]
     fn clone(&self) -> Self {
@@ -230,16 +1625,20 @@ impl<O> Clone for VarId<O> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<O> Copy for VarId<O> {}
 
+#[cfg(feature = "std")]
 impl<O> Debug for VarId<O> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_tuple("VarId").field(&self.0).finish()
     }
 }
 
+#[cfg(feature = "std")]
 impl<O> Eq for VarId<O> {}
 
+#[cfg(feature = "std")]
 impl<O> Hash for VarId<O> {
     #[inline]
     fn hash<H: Hasher>(&self, state: &mut H) {
@@ -247,6 +1646,7 @@ impl<O> Hash for VarId<O> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<O> PartialEq for VarId<O> {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
@@ -254,6 +1654,7 @@ impl<O> PartialEq for VarId<O> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<O, T> PartialEq<Var<O, T>> for VarId<O> {
     #[inline]
     fn eq(&self, other: &Var<O, T>) -> bool {
@@ -261,9 +1662,98 @@ impl<O, T> PartialEq<Var<O, T>> for VarId<O> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<O, T> PartialEq<VarId<O>> for Var<O, T> {
     #[inline]
     fn eq(&self, other: &VarId<O>) -> bool {
         self.0 == other.0
     }
 }
+
+/// A variable whose type is only known at runtime, created by
+/// [`Var::untyped`].
+///
+/// It can be stored in collections next to variables of other types, read
+/// with [`ExtObj::get_dyn`] and converted back with [`UntypedVar::downcast`].
+#[cfg(feature = "std")]
+pub struct UntypedVar<O> {
+    index: usize,
+    type_id: TypeId,
+    /// `false` for the `#[readonly]` fields found by name.
+    writable: bool,
+    _marker: PhantomData<O>,
+}
+
+#[cfg(feature = "std")]
+impl<O> UntypedVar<O> {
+    /// The `TypeId` of the field type.
+    #[inline]
+    pub fn type_id(self) -> TypeId {
+        self.type_id
+    }
+
+    /// Returns `true` if the field is of type `T`.
+    #[inline]
+    pub fn is<T: 'static>(self) -> bool {
+        self.type_id == TypeId::of::<T>()
+    }
+
+    /// Restores the typed variable, or returns `None` if the field is not of
+    /// type `T` or if it is a `#[readonly]` field found by name, see
+    /// [`UntypedVar::downcast_read`].
+    #[inline]
+    pub fn downcast<T: 'static>(self) -> Option<Var<O, T>> {
+        (self.writable && self.is::<T>()).then_some(Var(self.index, PhantomData))
+    }
+
+    /// Restores the variable as a read-only one, or returns `None` if the
+    /// field is not of type `T`.
+    #[inline]
+    pub fn downcast_read<T: 'static>(self) -> Option<ReadVar<O, T>> {
+        self.is::<T>()
+            .then_some(ReadVar::from(Var(self.index, PhantomData)))
+    }
+
+    /// Erases the field type, see [`Var::var_id`].
+    #[inline]
+    pub fn var_id(self) -> VarId<O> {
+        VarId(self.index, PhantomData)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<O> Clone for UntypedVar<O> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+#[cfg(feature = "std")]
+impl<O> Copy for UntypedVar<O> {}
+
+#[cfg(feature = "std")]
+impl<O> Debug for UntypedVar<O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("UntypedVar").field(&self.index).finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<O> Eq for UntypedVar<O> {}
+
+#[cfg(feature = "std")]
+impl<O> Hash for UntypedVar<O> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<O> PartialEq for UntypedVar<O> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
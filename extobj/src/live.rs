@@ -0,0 +1,93 @@
+use crate::{__ExtObjDef, ExtObj};
+use parking_lot::{Mutex, RwLock};
+use std::{
+    any::{Any, TypeId},
+    collections::BTreeMap,
+    sync::{Arc, Weak},
+};
+
+/// An object shared behind a lock and listed by [`instances`].
+pub type LiveExtObj<O> = Arc<RwLock<ExtObj<O>>>;
+
+type Instances = Vec<Weak<dyn Any + Send + Sync>>;
+
+/// The weak handles of the live objects, by object type.
+static LIVE: Mutex<BTreeMap<TypeId, Instances>> = Mutex::new(BTreeMap::new());
+
+impl<O: __ExtObjDef> ExtObj<O>
+where
+    Self: Send + Sync,
+{
+    /// Creates a new object and lists it in [`instances`] until it is dropped.
+    ///
+    /// # Panics
+    /// Panics if a fallible initializer fails.
+    pub fn new_live() -> LiveExtObj<O> {
+        Self::new().into_live()
+    }
+
+    /// Shares this object and lists it in [`instances`] until it is dropped.
+    pub fn into_live(self) -> LiveExtObj<O> {
+        let obj = Arc::new(RwLock::new(self));
+        let weak = Arc::downgrade(&obj) as Weak<dyn Any + Send + Sync>;
+        let mut live = LIVE.lock();
+        let instances = live.entry(TypeId::of::<O>()).or_default();
+
+        // pruned before growing, so dropped objects cost amortized O(1)
+        if instances.len() == instances.capacity() {
+            instances.retain(|weak| weak.strong_count() > 0);
+        }
+
+        instances.push(weak);
+        obj
+    }
+}
+
+/// The live objects of type `O` created with [`ExtObj::new_live`] or
+/// [`ExtObj::into_live`], in creation order.
+///
+/// The objects are collected first, so the iteration holds no lock and may
+/// create other live objects.
+///
+/// # Example
+///
+/// ```
+/// extobj::extobj!(pub struct Plugin);
+/// extobj::extobj!(impl Plugin { pub ENABLED: bool });
+///
+/// let first = extobj::ExtObj::<Plugin>::new_live();
+/// let second = extobj::ExtObj::<Plugin>::new_live();
+/// drop(second);
+///
+/// // broadcast an update to every live object
+/// for obj in extobj::instances::<Plugin>() {
+///     obj.write()[*ENABLED] = true;
+/// }
+///
+/// assert!(first.read()[*ENABLED]);
+/// assert_eq!(extobj::instances::<Plugin>().count(), 1);
+/// ```
+pub fn instances<O: __ExtObjDef>() -> impl Iterator<Item = LiveExtObj<O>>
+where
+    ExtObj<O>: Send + Sync,
+{
+    let mut live = LIVE.lock();
+    let mut objects = Vec::new();
+
+    if let Some(instances) = live.get_mut(&TypeId::of::<O>()) {
+        instances.retain(|weak| match weak.upgrade() {
+            Some(obj) => {
+                objects.push(obj);
+                true
+            }
+            None => false,
+        });
+    }
+
+    drop(live);
+
+    objects.into_iter().map(|obj| match obj.downcast() {
+        Ok(obj) => obj,
+        Err(_) => unreachable!("instances are stored by type"),
+    })
+}
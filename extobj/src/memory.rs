@@ -0,0 +1,169 @@
+use crate::{__ExtObjDef, ExtObj, FieldAllocator};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    mem,
+};
+
+/// The bytes a value owns on the heap, reported by [`ExtObj::memory_usage`].
+///
+/// Implement it for the field types whose heap allocations should be counted.
+///
+/// # Example
+///
+/// ```
+/// use extobj::HeapSize;
+///
+/// #[derive(Default)]
+/// struct Cache {
+///     entries: Vec<String>,
+/// }
+///
+/// impl HeapSize for Cache {
+///     fn heap_size(&self) -> usize {
+///         self.entries.heap_size()
+///     }
+/// }
+///
+/// extobj::extobj!(struct Service);
+/// extobj::extobj!(impl Service { pub CACHE: Cache });
+///
+/// let mut service = extobj::ExtObj::<Service>::new();
+/// service[*CACHE].entries = Vec::with_capacity(4);
+///
+/// let usage = service.memory_usage();
+/// assert_eq!(usage.fields[0].heap, Some(4 * size_of::<String>()));
+/// ```
+pub trait HeapSize {
+    /// The bytes owned on the heap, excluding `size_of_val(self)`.
+    fn heap_size(&self) -> usize;
+}
+
+macro_rules! no_heap {
+    ($($ty:ty),*) => {
+        $(impl HeapSize for $ty {
+            #[inline]
+            fn heap_size(&self) -> usize {
+                0
+            }
+        })*
+    };
+}
+
+no_heap!((), bool, char, str, f32, f64);
+no_heap!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize
+);
+
+impl HeapSize for String {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T: HeapSize + ?Sized> HeapSize for Box<T> {
+    fn heap_size(&self) -> usize {
+        mem::size_of_val::<T>(self) + (**self).heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, T::heap_size)
+    }
+}
+
+impl<T: HeapSize> HeapSize for [T] {
+    fn heap_size(&self) -> usize {
+        self.iter().map(T::heap_size).sum()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * mem::size_of::<T>() + self.as_slice().heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for VecDeque<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * mem::size_of::<T>() + self.iter().map(T::heap_size).sum::<usize>()
+    }
+}
+
+/// Counts the entries only, the overhead of the table or the tree is not
+/// included.
+impl<K: HeapSize, V: HeapSize, S> HeapSize for HashMap<K, V, S> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * mem::size_of::<(K, V)>()
+            + self
+                .iter()
+                .map(|(k, v)| k.heap_size() + v.heap_size())
+                .sum::<usize>()
+    }
+}
+
+/// Counts the entries only, the overhead of the table or the tree is not
+/// included.
+impl<K: HeapSize, V: HeapSize> HeapSize for BTreeMap<K, V> {
+    fn heap_size(&self) -> usize {
+        self.iter()
+            .map(|(k, v)| mem::size_of::<(K, V)>() + k.heap_size() + v.heap_size())
+            .sum()
+    }
+}
+
+/// The memory used by the fields of an object, see [`ExtObj::memory_usage`].
+#[derive(Clone, Debug)]
+pub struct MemoryUsage {
+    /// One entry per field, in registration order.
+    pub fields: Vec<FieldMemory>,
+}
+
+/// The memory used by one field.
+#[derive(Clone, Copy, Debug)]
+pub struct FieldMemory {
+    /// The name of the field.
+    pub name: &'static str,
+    /// The type of the field, as given by `std::any::type_name`.
+    pub type_name: &'static str,
+    /// The size of the value, `size_of::<T>()`.
+    pub size: usize,
+    /// The bytes owned on the heap, or `None` when the type does not
+    /// implement [`HeapSize`].
+    pub heap: Option<usize>,
+}
+
+impl MemoryUsage {
+    /// The bytes used by every field, counting the heap of the fields which
+    /// report it.
+    pub fn total(&self) -> usize {
+        self.fields
+            .iter()
+            .map(|field| field.size + field.heap.unwrap_or(0))
+            .sum()
+    }
+}
+
+impl<O: __ExtObjDef, A: FieldAllocator> ExtObj<O, A> {
+    /// Reports the bytes used by each field: the size of its value, plus its
+    /// heap allocations when its type implements [`HeapSize`].
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let fields = O::defs().with(|defs| {
+            defs.iter()
+                .zip(&self.0)
+                .map(|(def, slot)| FieldMemory {
+                    name: def.name,
+                    type_name: def.type_name,
+                    size: def.size,
+                    // SAFETY: the function was registered with the field type
+                    heap: def
+                        .heap_size
+                        .map(|heap_size| unsafe { heap_size(def.value(slot)) }),
+                })
+                .collect()
+        });
+
+        MemoryUsage { fields }
+    }
+}
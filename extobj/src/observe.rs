@@ -0,0 +1,78 @@
+use crate::{
+    __ExtObjDef, ExtObj, FieldAllocator, Var,
+    registry::{Slot, loaded_defs},
+};
+
+/// A type-erased `fn(&T)` called when a field is written.
+#[derive(Clone, Copy)]
+pub(crate) struct Observer {
+    /// Casts `f` back to `fn(&T)` and calls it with the value of a slot.
+    call: unsafe fn(fn(), &Slot),
+    f: fn(),
+}
+
+/// Registers `f` to be called with the new value of `var` each time it is
+/// written with [`ExtObj::set`] or [`ExtObj::update`], on any object.
+///
+/// Observers are called in registration order, after the write. Mutations
+/// through `get_mut` or `IndexMut` are not observed.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::atomic::{AtomicU32, Ordering};
+///
+/// extobj::extobj!(struct Widget);
+/// extobj::extobj!(impl Widget { pub WIDTH: u32 });
+///
+/// static LAYOUTS: AtomicU32 = AtomicU32::new(0);
+///
+/// extobj::on_change(*WIDTH, |_: &u32| {
+///     LAYOUTS.fetch_add(1, Ordering::Relaxed);
+/// });
+///
+/// let mut widget = extobj::ExtObj::<Widget>::new();
+/// widget.set(*WIDTH, 120);
+/// widget.update(*WIDTH, |width| *width *= 2);
+///
+/// assert_eq!(widget[*WIDTH], 240);
+/// assert_eq!(LAYOUTS.load(Ordering::Relaxed), 2);
+/// ```
+pub fn on_change<O: __ExtObjDef, T: 'static>(var: Var<O, T>, f: fn(&T)) {
+    unsafe fn call<T>(f: fn(), slot: &Slot) {
+        let f: fn(&T) = unsafe { std::mem::transmute(f) };
+        f(unsafe { slot.get() })
+    }
+
+    let observer = Observer {
+        call: call::<T>,
+        // SAFETY: transmuted back to `fn(&T)` by `call::<T>`
+        f: unsafe { std::mem::transmute::<fn(&T), fn()>(f) },
+    };
+
+    loaded_defs::<O>().observe(var.0, observer);
+}
+
+impl<O: __ExtObjDef, A: FieldAllocator> ExtObj<O, A> {
+    /// Mutates the value of the given variable with `f`, then calls the
+    /// observers registered with [`on_change`].
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
+    #[track_caller]
+    pub fn update<T, R>(&mut self, var: Var<O, T>, f: impl FnOnce(&mut T) -> R) -> R {
+        let result = f(self.get_mut(var));
+        self.notify(var.0);
+        result
+    }
+
+    /// Calls the observers of the field at `index`.
+    #[inline]
+    pub(crate) fn notify(&self, index: usize) {
+        let slot = &self.0[index];
+
+        O::defs().each_observer(index, |observer| unsafe {
+            (observer.call)(observer.f, slot)
+        });
+    }
+}
@@ -0,0 +1,127 @@
+use crate::HeapSize;
+use std::fmt::{self, Debug, Formatter};
+
+/// The value of an `#[optional]` field: absent until it is set.
+///
+/// An empty field only holds a null pointer, whatever the size of `T`. The
+/// value is boxed once set, so rarely used extensions of large types only
+/// cost the objects using them. It does not require `T: Default`.
+///
+/// # Example
+///
+/// ```
+/// extobj::extobj!(struct Account);
+/// extobj::extobj!(impl Account {
+///     #[optional]
+///     pub AVATAR: Vec<u8>,
+/// });
+///
+/// let mut account = extobj::ExtObj::<Account>::new();
+/// assert_eq!(account[*AVATAR].get(), None);
+///
+/// account[*AVATAR].set(vec![1, 2, 3]);
+/// assert_eq!(account[*AVATAR].get(), Some(&vec![1, 2, 3]));
+///
+/// assert_eq!(account[*AVATAR].take(), Some(vec![1, 2, 3]));
+/// assert!(!account[*AVATAR].is_set());
+/// ```
+#[derive(Clone, Eq, Hash, PartialEq)]
+pub struct Optional<T>(Option<Box<T>>);
+
+impl<T> Optional<T> {
+    /// An empty value.
+    #[inline]
+    pub const fn new() -> Self {
+        Self(None)
+    }
+
+    /// Returns `true` when a value is set.
+    #[inline]
+    pub fn is_set(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Borrows the value, if set.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        self.0.as_deref()
+    }
+
+    /// Mutably borrows the value, if set.
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.0.as_deref_mut()
+    }
+
+    /// Sets the value, dropping the previous one.
+    ///
+    /// The allocation of the previous value is reused.
+    pub fn set(&mut self, value: T) {
+        match &mut self.0 {
+            Some(b) => **b = value,
+            None => self.0 = Some(Box::new(value)),
+        }
+    }
+
+    /// Sets the value, returning the previous one.
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        match &mut self.0 {
+            Some(b) => Some(std::mem::replace(&mut **b, value)),
+            None => {
+                self.0 = Some(Box::new(value));
+                None
+            }
+        }
+    }
+
+    /// Removes the value, leaving the field empty.
+    #[inline]
+    pub fn take(&mut self) -> Option<T> {
+        self.0.take().map(|b| *b)
+    }
+
+    /// Mutably borrows the value, setting it with `f` first when empty.
+    pub fn get_or_insert_with(&mut self, f: impl FnOnce() -> T) -> &mut T {
+        self.0.get_or_insert_with(|| Box::new(f()))
+    }
+
+    /// Returns the value, if set.
+    #[inline]
+    pub fn into_inner(self) -> Option<T> {
+        self.0.map(|b| *b)
+    }
+}
+
+impl<T> Default for Optional<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Debug> Debug for Optional<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+impl<T> From<Option<T>> for Optional<T> {
+    #[inline]
+    fn from(value: Option<T>) -> Self {
+        Self(value.map(Box::new))
+    }
+}
+
+impl<T> From<Optional<T>> for Option<T> {
+    #[inline]
+    fn from(value: Optional<T>) -> Self {
+        value.into_inner()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Optional<T> {
+    #[inline]
+    fn heap_size(&self) -> usize {
+        self.0.heap_size()
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{
+    __ExtObjDef, ExtObj, ExtObjInitError, Global,
+    registry::{FieldDef, PartialSlots, Slot, loaded_defs},
+};
+use rayon::prelude::*;
+use std::mem;
+
+impl<O: __ExtObjDef> ExtObj<O>
+where
+    Self: Send,
+{
+    /// Creates a new `ExtObj`, running the initializers of its fields in
+    /// parallel on the rayon thread pool.
+    ///
+    /// Worth it for objects with many fields whose initial values are
+    /// expensive to create; the initializers must not depend on the order in
+    /// which they run. Context initializers receive `&()`.
+    ///
+    /// # Panics
+    /// Panics if a fallible initializer fails, see
+    /// [`ExtObj::try_new_parallel`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extobj::extobj!(struct Analytics);
+    /// extobj::extobj!(impl Analytics {
+    ///     pub PRIMES: Vec<u64> = (2..1000).filter(|n| (2..*n).all(|d| n % d != 0)).collect(),
+    ///     pub SQUARES: Vec<u64> = (0..1000).map(|n| n * n).collect(),
+    /// });
+    ///
+    /// let analytics = extobj::ExtObj::<Analytics>::new_parallel();
+    /// assert_eq!(analytics[*PRIMES][..4], [2, 3, 5, 7]);
+    /// assert_eq!(analytics[*SQUARES][3], 9);
+    /// ```
+    pub fn new_parallel() -> Self {
+        Self::try_new_parallel().unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Creates a new `ExtObj` in parallel, reporting the first field, in
+    /// registration order, whose initializer failed.
+    ///
+    /// Every initializer runs even when one fails; the fields created are
+    /// then dropped. See [`ExtObj::new_parallel`] and [`ExtObj::try_new`].
+    pub fn try_new_parallel() -> Result<Self, ExtObjInitError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("ExtObj::new_parallel", object = O::NAME).entered();
+
+        let slots = loaded_defs::<O>().with(|defs| {
+            let created: Vec<_> = defs
+                .par_iter()
+                .map(|def| unsafe { def.create(&(), &Global) }.map(|slot| Created(def, slot)))
+                .collect();
+
+            // drops the fields already created if an initializer failed
+            let mut slots = PartialSlots::new(defs, &Global);
+
+            for (def, created) in defs.iter().zip(created) {
+                match created {
+                    Ok(created) => slots.push(created.into_slot()),
+                    Err(e) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!(field = def.name, error = %e, "field initializer failed");
+
+                        return Err(ExtObjInitError::new(O::NAME, def.name, e));
+                    }
+                }
+            }
+
+            Ok(slots.finish())
+        })?;
+
+        Ok(Self::from_slots(slots, Global))
+    }
+}
+
+/// A slot created on a worker thread, dropped unless moved into the object,
+/// so the fields are not leaked when another initializer fails or panics.
+struct Created<'a>(&'a FieldDef, Slot);
+
+impl Created<'_> {
+    fn into_slot(self) -> Slot {
+        let slot = self.1;
+        mem::forget(self);
+        slot
+    }
+}
+
+impl Drop for Created<'_> {
+    fn drop(&mut self) {
+        // SAFETY: the slot was created by the definition with `Global`
+        unsafe { self.0.destroy(self.1, &Global) }
+    }
+}
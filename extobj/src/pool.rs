@@ -0,0 +1,113 @@
+use crate::{__ExtObjDef, ExtObj};
+use parking_lot::Mutex;
+use std::fmt::{self, Debug, Formatter};
+
+/// A pool of reusable objects for high-churn workloads such as per-request
+/// contexts.
+///
+/// Creating an `ExtObj` boxes every field. A released object is reset to the
+/// default values with [`ExtObj::reset`], which writes into the existing
+/// boxes, and is handed out again by the next [`ExtObjPool::acquire`].
+///
+/// # Example
+///
+/// ```
+/// use extobj::ExtObjPool;
+///
+/// extobj::extobj!(struct Request);
+/// extobj::extobj!(impl Request { BODY: String });
+///
+/// static POOL: ExtObjPool<Request> = ExtObjPool::new();
+///
+/// let mut request = POOL.acquire();
+/// request[*BODY].push_str("hello");
+/// POOL.release(request);
+///
+/// let request = POOL.acquire();
+/// assert!(request[*BODY].is_empty());
+/// assert_eq!(POOL.idle(), 0);
+/// ```
+pub struct ExtObjPool<O: __ExtObjDef> {
+    idle: Mutex<Vec<ExtObj<O>>>,
+    max_idle: usize,
+}
+
+impl<O: __ExtObjDef> ExtObjPool<O> {
+    /// Creates an empty pool keeping every released object.
+    pub const fn new() -> Self {
+        Self::with_max_idle(usize::MAX)
+    }
+
+    /// Creates an empty pool keeping at most `max_idle` released objects, the
+    /// others are dropped.
+    pub const fn with_max_idle(max_idle: usize) -> Self {
+        Self {
+            idle: Mutex::new(Vec::new()),
+            max_idle,
+        }
+    }
+
+    /// Takes an idle object from the pool, or creates a new one when the pool
+    /// is empty.
+    ///
+    /// The object has every field registered so far, set to its default value.
+    ///
+    /// # Panics
+    /// Panics if a fallible initializer fails.
+    pub fn acquire(&self) -> ExtObj<O> {
+        // popped before matching, so the lock is not held during `new`
+        let obj = self.idle.lock().pop();
+
+        match obj {
+            Some(mut obj) => {
+                obj.upgrade();
+                obj
+            }
+            None => ExtObj::new(),
+        }
+    }
+
+    /// Resets `obj` and returns it to the pool.
+    ///
+    /// # Panics
+    /// Panics if a fallible initializer fails, the object is then dropped.
+    pub fn release(&self, mut obj: ExtObj<O>) {
+        if self.idle.lock().len() >= self.max_idle {
+            return;
+        }
+
+        obj.reset();
+
+        let mut idle = self.idle.lock();
+
+        if idle.len() < self.max_idle {
+            idle.push(obj);
+        }
+    }
+
+    /// The number of idle objects in the pool.
+    pub fn idle(&self) -> usize {
+        self.idle.lock().len()
+    }
+
+    /// Drops every idle object.
+    pub fn clear(&self) {
+        // dropped outside of the lock
+        drop(std::mem::take(&mut *self.idle.lock()));
+    }
+}
+
+impl<O: __ExtObjDef> Default for ExtObjPool<O> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<O: __ExtObjDef> Debug for ExtObjPool<O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtObjPool")
+            .field("object", &O::NAME)
+            .field("idle", &self.idle())
+            .finish()
+    }
+}
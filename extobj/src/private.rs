@@ -0,0 +1,481 @@
+//! Support items for the code generated by the `extobj!` macro.
+//!
+//! Capabilities of a field type (`Debug`, `Eq`, `Serialize`, ...) are detected
+//! with autoref specialization: `(&&Probe::<T>::new()).debug_fn()` resolves to the
+//! specialized trait when `T` implements the capability and to the fallback
+//! trait otherwise. This only works because the macro expands with a concrete
+//! field type.
+
+use crate::{
+    __ExtObjDef, ExtObj, FieldAllocator, HeapSize, UntypedVar, Var, missing_field, registry::Slot,
+};
+pub use crate::{error::BoxError, registry::Field};
+use std::{
+    fmt::{self, Debug, Formatter},
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    mem,
+};
+
+pub type DebugFn = unsafe fn(usize, &mut Formatter<'_>) -> fmt::Result;
+
+pub type EqFn = unsafe fn(usize, usize) -> bool;
+
+pub type HashFn = unsafe fn(usize, &mut dyn Hasher);
+
+/// Returns the bytes owned on the heap by the value of a slot.
+pub type HeapSizeFn = unsafe fn(usize) -> usize;
+
+/// Securely wipes the value of a slot before it is dropped, see the `zeroize`
+/// feature.
+pub type ZeroizeFn = unsafe fn(usize);
+
+/// Writes a clone of the value of the first slot in the second slot, which is
+/// uninitialized, or live when the flag is set and then uses `clone_from`.
+pub type CloneFn = unsafe fn(usize, usize, bool);
+
+#[cfg(feature = "serde")]
+pub type SerializeFn = unsafe fn(usize) -> *const dyn erased_serde::Serialize;
+
+#[cfg(feature = "serde")]
+pub type DeserializeFn =
+    unsafe fn(usize, &mut dyn erased_serde::Deserializer<'_>) -> Result<(), erased_serde::Error>;
+
+/// Stands for the serde v-table entries when the `serde` feature is disabled.
+#[cfg(not(feature = "serde"))]
+#[derive(Clone, Copy)]
+pub enum SerializeFn {}
+
+#[cfg(not(feature = "serde"))]
+pub type DeserializeFn = SerializeFn;
+
+/// Hands out disjoint mutable borrows of the fields of an object, used by the
+/// view structs generated with `view = Name`.
+pub struct Borrows<'a, O: __ExtObjDef> {
+    /// The slots of the object, which may hold the values inline.
+    slots: *mut Slot,
+    len: usize,
+    taken: Vec<usize>,
+    _marker: PhantomData<&'a mut ExtObj<O>>,
+}
+
+impl<'a, O: __ExtObjDef> Borrows<'a, O> {
+    pub fn new<A: FieldAllocator>(obj: &'a mut ExtObj<O, A>) -> Self {
+        Self {
+            slots: obj.0.as_mut_ptr(),
+            len: obj.0.len(),
+            taken: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// # Panics
+    /// Panics if the field was registered after the object was created or was
+    /// already borrowed.
+    #[track_caller]
+    pub fn take<T>(&mut self, var: Var<O, T>) -> &'a mut T {
+        if var.0 >= self.len {
+            missing_field(var.0)
+        }
+
+        assert!(
+            !self.taken.contains(&var.0),
+            "field #{} is borrowed twice",
+            var.0
+        );
+        self.taken.push(var.0);
+
+        // SAFETY: the object is mutably borrowed for `'a` and every slot is
+        // handed out at most once.
+        unsafe { &mut *Slot::raw::<T>(self.slots.add(var.0)) }
+    }
+}
+
+/// A field collected at link time in [`LINKED_FIELDS`].
+#[cfg(feature = "linkme")]
+pub struct LinkedField {
+    /// The `TypeId` of the object type.
+    pub(crate) object: fn() -> std::any::TypeId,
+    /// Registers the field and returns its index.
+    register: fn() -> usize,
+    index: std::sync::OnceLock<usize>,
+}
+
+#[cfg(feature = "linkme")]
+impl LinkedField {
+    pub const fn new(object: fn() -> std::any::TypeId, register: fn() -> usize) -> Self {
+        Self {
+            object,
+            register,
+            index: std::sync::OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn register(&self) {
+        self.index.get_or_init(self.register);
+    }
+
+    pub(crate) fn index(&self) -> usize {
+        *self
+            .index
+            .get()
+            .expect("the linked field is not registered yet")
+    }
+}
+
+/// The fields declared with `extobj!(impl ..., backend = linkme)`, of every
+/// object type.
+#[cfg(feature = "linkme")]
+#[linkme::distributed_slice]
+pub static LINKED_FIELDS: [LinkedField];
+
+/// The threading of an object whose fields are `Send + Sync`, making the
+/// `ExtObj` `Send + Sync`.
+pub struct Shared;
+
+/// The threading of an object declared with `extobj!(struct Name, local)`,
+/// whose fields may be `!Send` and whose `ExtObj` is `!Send + !Sync`.
+pub struct Local(PhantomData<*const ()>);
+
+/// Implemented by the threading of an object when `T` can be one of its
+/// fields.
+#[diagnostic::on_unimplemented(
+    message = "`{T}` cannot be a field of a thread-safe extobj",
+    label = "`{T}` is not `Send + Sync`",
+    note = "declare the object with `extobj!(struct Name, local)` to allow `!Send` fields"
+)]
+pub trait Accepts<T> {}
+
+impl<T: Send + Sync> Accepts<T> for Shared {}
+
+impl<T> Accepts<T> for Local {}
+
+/// Fails to compile when the object `O` does not accept fields of type `T`.
+pub const fn assert_field<O: __ExtObjDef, T>()
+where
+    O::Threading: Accepts<T>,
+    O::Comparability: Compares<T>,
+{
+}
+
+/// The comparability of an object declared with
+/// `extobj!(struct Name, comparable)`, whose `ExtObj` implements `Eq` and
+/// `Hash`.
+pub struct Comparable;
+
+/// The comparability of the other objects, whose fields may be of any type.
+pub struct Incomparable;
+
+/// Implemented by the comparability of an object when `T` can be one of its
+/// fields.
+#[diagnostic::on_unimplemented(
+    message = "`{T}` cannot be a field of a comparable extobj",
+    label = "`{T}` is not `Eq + Hash`",
+    note = "the fields of an object declared with `extobj!(struct Name, comparable)` must implement `Eq` and `Hash`"
+)]
+pub trait Compares<T> {}
+
+impl<T: Eq + Hash> Compares<T> for Comparable {}
+
+impl<T> Compares<T> for Incomparable {}
+
+/// Implemented by `T` for itself only: `K: Is<Key>` requires `K` to be `Key`.
+pub trait Is<T: ?Sized> {}
+
+impl<T: ?Sized> Is<T> for T {}
+
+pub struct Probe<T>(PhantomData<T>);
+
+impl<T> Probe<T> {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+pub trait DebugProbe {
+    fn debug_fn(&self) -> Option<DebugFn>;
+}
+
+impl<T: Debug> DebugProbe for &Probe<T> {
+    fn debug_fn(&self) -> Option<DebugFn> {
+        Some(debug_thunk::<T>)
+    }
+}
+
+pub trait DebugFallback {
+    fn debug_fn(&self) -> Option<DebugFn> {
+        None
+    }
+}
+
+impl<T> DebugFallback for Probe<T> {}
+
+unsafe fn debug_thunk<T: Debug>(ptr: usize, f: &mut Formatter<'_>) -> fmt::Result {
+    unsafe { Debug::fmt(&*(ptr as *const T), f) }
+}
+
+pub trait EqProbe {
+    fn eq_fn(&self) -> Option<EqFn>;
+}
+
+impl<T: Eq> EqProbe for &Probe<T> {
+    fn eq_fn(&self) -> Option<EqFn> {
+        Some(eq_thunk::<T>)
+    }
+}
+
+pub trait EqFallback {
+    fn eq_fn(&self) -> Option<EqFn> {
+        None
+    }
+}
+
+impl<T> EqFallback for Probe<T> {}
+
+unsafe fn eq_thunk<T: Eq>(a: usize, b: usize) -> bool {
+    unsafe { *(a as *const T) == *(b as *const T) }
+}
+
+pub trait HashProbe {
+    fn hash_fn(&self) -> Option<HashFn>;
+}
+
+impl<T: Hash> HashProbe for &Probe<T> {
+    fn hash_fn(&self) -> Option<HashFn> {
+        Some(hash_thunk::<T>)
+    }
+}
+
+pub trait HashFallback {
+    fn hash_fn(&self) -> Option<HashFn> {
+        None
+    }
+}
+
+impl<T> HashFallback for Probe<T> {}
+
+unsafe fn hash_thunk<T: Hash>(ptr: usize, mut state: &mut dyn Hasher) {
+    unsafe { (*(ptr as *const T)).hash(&mut state) }
+}
+
+pub trait CloneProbe {
+    fn clone_fn(&self) -> Option<CloneFn>;
+}
+
+impl<T: Clone> CloneProbe for &Probe<T> {
+    fn clone_fn(&self) -> Option<CloneFn> {
+        Some(clone_thunk::<T>)
+    }
+}
+
+pub trait CloneFallback {
+    fn clone_fn(&self) -> Option<CloneFn> {
+        None
+    }
+}
+
+impl<T> CloneFallback for Probe<T> {}
+
+pub trait HeapSizeProbe {
+    fn heap_size_fn(&self) -> Option<HeapSizeFn>;
+}
+
+impl<T: HeapSize> HeapSizeProbe for &Probe<T> {
+    fn heap_size_fn(&self) -> Option<HeapSizeFn> {
+        Some(heap_size_thunk::<T>)
+    }
+}
+
+pub trait HeapSizeFallback {
+    fn heap_size_fn(&self) -> Option<HeapSizeFn> {
+        None
+    }
+}
+
+impl<T> HeapSizeFallback for Probe<T> {}
+
+unsafe fn heap_size_thunk<T: HeapSize>(ptr: usize) -> usize {
+    unsafe { (*(ptr as *const T)).heap_size() }
+}
+
+unsafe fn clone_thunk<T: Clone>(src: usize, dst: usize, live: bool) {
+    let src = unsafe { &*(src as *const T) };
+
+    if live {
+        unsafe { (*(dst as *mut T)).clone_from(src) }
+    } else {
+        unsafe { std::ptr::write(dst as *mut T, src.clone()) }
+    }
+}
+
+pub trait SerializeProbe {
+    fn serialize_fn(&self) -> Option<SerializeFn>;
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize + 'static> SerializeProbe for &Probe<T> {
+    fn serialize_fn(&self) -> Option<SerializeFn> {
+        Some(serialize_thunk::<T>)
+    }
+}
+
+pub trait SerializeFallback {
+    fn serialize_fn(&self) -> Option<SerializeFn> {
+        None
+    }
+}
+
+impl<T> SerializeFallback for Probe<T> {}
+
+#[cfg(feature = "serde")]
+unsafe fn serialize_thunk<T: serde::Serialize + 'static>(
+    ptr: usize,
+) -> *const dyn erased_serde::Serialize {
+    ptr as *const T
+}
+
+pub trait DeserializeProbe {
+    fn deserialize_fn(&self) -> Option<DeserializeFn>;
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::de::DeserializeOwned> DeserializeProbe for &Probe<T> {
+    fn deserialize_fn(&self) -> Option<DeserializeFn> {
+        Some(deserialize_thunk::<T>)
+    }
+}
+
+pub trait DeserializeFallback {
+    fn deserialize_fn(&self) -> Option<DeserializeFn> {
+        None
+    }
+}
+
+impl<T> DeserializeFallback for Probe<T> {}
+
+#[cfg(feature = "serde")]
+unsafe fn deserialize_thunk<T: serde::de::DeserializeOwned>(
+    ptr: usize,
+    deserializer: &mut dyn erased_serde::Deserializer<'_>,
+) -> Result<(), erased_serde::Error> {
+    let value = erased_serde::deserialize::<T>(deserializer)?;
+    unsafe { *(ptr as *mut T) = value };
+    Ok(())
+}
+
+/// Checks the frozen struct `frozen` mirrors every field of `O` exactly once,
+/// with the registered type, see [`frozen_struct!`](crate::frozen_struct).
+#[track_caller]
+pub fn check_frozen<O: __ExtObjDef>(frozen: &str, vars: &[UntypedVar<O>]) {
+    let missing = crate::registry::loaded_defs::<O>().with(|defs| {
+        let mut listed = vec![false; defs.len()];
+
+        for var in vars {
+            let index = var.index;
+            let def = &defs[index];
+
+            if def.type_id != var.type_id {
+                panic!(
+                    "`{frozen}` lists the field `{}` of `{}` with another type than `{}`",
+                    def.name,
+                    O::NAME,
+                    def.type_name
+                );
+            }
+
+            if mem::replace(&mut listed[index], true) {
+                panic!(
+                    "`{frozen}` lists the field `{}` of `{}` twice",
+                    def.name,
+                    O::NAME
+                );
+            }
+        }
+
+        defs.iter()
+            .zip(listed)
+            .filter(|(_, listed)| !listed)
+            .map(|(def, _)| format!("`{}`", def.name))
+            .collect::<Vec<_>>()
+    });
+
+    if !missing.is_empty() {
+        panic!(
+            "`{frozen}` does not list the fields {} of `{}`: their values would be lost",
+            missing.join(", "),
+            O::NAME
+        );
+    }
+}
+
+/// Builds the `Field<T>` description of a field, probing the capabilities of
+/// its type. The field is initialized with `$init: fn() -> T`, with
+/// `try_init = $init: fn() -> Result<T, BoxError>`, with
+/// `init_with = $init: fn(&dyn Any) -> T`, with
+/// `try_init_with = $init: fn(&dyn Any) -> Result<T, BoxError>` or with
+/// `T::default()` when omitted.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __field {
+    (@probe $ty:ty, $field:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::__private::{
+            CloneFallback as _, CloneProbe as _, DebugFallback as _, DebugProbe as _,
+            DeserializeFallback as _, DeserializeProbe as _, EqFallback as _, EqProbe as _,
+            HashFallback as _, HashProbe as _, HeapSizeFallback as _, HeapSizeProbe as _,
+            SerializeFallback as _, SerializeProbe as _,
+        };
+
+        $field
+            .clone_fn((&&$crate::__private::Probe::<$ty>::new()).clone_fn())
+            .debug((&&$crate::__private::Probe::<$ty>::new()).debug_fn())
+            .heap_size((&&$crate::__private::Probe::<$ty>::new()).heap_size_fn())
+            .eq_hash(
+                (&&$crate::__private::Probe::<$ty>::new()).eq_fn(),
+                (&&$crate::__private::Probe::<$ty>::new()).hash_fn(),
+            )
+            .serde(
+                (&&$crate::__private::Probe::<$ty>::new()).serialize_fn(),
+                (&&$crate::__private::Probe::<$ty>::new()).deserialize_fn(),
+            )
+    }};
+    ($ty:ty, $name:ident) => {
+        $crate::__field!($ty, $name, <$ty as ::std::default::Default>::default)
+    };
+    ($ty:ty, $name:ident, init_with = $init:expr) => {
+        $crate::__field!(@probe $ty, $crate::__private::Field::<$ty>::with_ctx(
+            ::std::stringify!($name),
+            ::std::module_path!(),
+            $init,
+        ))
+    };
+    ($ty:ty, $name:ident, try_init_with = $init:expr) => {
+        $crate::__field!(@probe $ty, $crate::__private::Field::<$ty>::try_with_ctx(
+            ::std::stringify!($name),
+            ::std::module_path!(),
+            $init,
+        ))
+    };
+    ($ty:ty, $name:ident, init_async = $init:expr) => {
+        $crate::__field!(@probe $ty, $crate::__private::Field::<$ty>::new_async(
+            ::std::stringify!($name),
+            ::std::module_path!(),
+            $init,
+        ))
+    };
+    ($ty:ty, $name:ident, try_init = $init:expr) => {
+        $crate::__field!(@probe $ty, $crate::__private::Field::<$ty>::try_new(
+            ::std::stringify!($name),
+            ::std::module_path!(),
+            $init,
+        ))
+    };
+    ($ty:ty, $name:ident, $init:expr) => {
+        $crate::__field!(@probe $ty, $crate::__private::Field::<$ty>::new(
+            ::std::stringify!($name),
+            ::std::module_path!(),
+            $init,
+        ))
+    };
+}
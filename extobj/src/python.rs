@@ -0,0 +1,167 @@
+use crate::{
+    __ExtObjDef, ExtObj,
+    registry::{FieldDef, find_field},
+};
+use pyo3::{
+    exceptions::{PyAttributeError, PyTypeError, PyValueError},
+    prelude::*,
+};
+use pythonize::{Depythonizer, pythonize};
+use std::any::Any;
+
+/// An `ExtObj` exposed to Python as an attribute bag.
+///
+/// Reading or writing `obj.FIELD` converts the value of the field with serde,
+/// through [`pythonize`](https://docs.rs/pythonize). Fields are found like
+/// [`var_by_name`](crate::var_by_name), and `dir(obj)` lists them. Writing a
+/// field calls its observers; the values are not validated until
+/// [`ExtObj::validate_all`] is called.
+///
+/// # Example
+///
+/// ```no_run
+/// use extobj::{ExtObj, PyExtObj, pyo3::prelude::*};
+///
+/// extobj::extobj!(struct Session);
+/// extobj::extobj!(impl Session { pub USER: String });
+///
+/// Python::with_gil(|py| {
+///     let session = Py::new(py, PyExtObj::new(ExtObj::<Session>::new())).unwrap();
+///     // hand `session` to a tooling script, which sets `session.USER = "ada"`
+/// });
+/// ```
+#[pyclass(name = "ExtObj", module = "extobj", unsendable)]
+pub struct PyExtObj(Box<dyn Fields>);
+
+impl PyExtObj {
+    /// Wraps `obj` for Python.
+    pub fn new<O: __ExtObjDef>(obj: ExtObj<O>) -> Self {
+        Self(Box::new(obj))
+    }
+
+    /// Borrows the object, if it is an `ExtObj<O>`.
+    pub fn downcast_ref<O: __ExtObjDef>(&self) -> Option<&ExtObj<O>> {
+        self.0.as_any().downcast_ref()
+    }
+
+    /// Mutably borrows the object, if it is an `ExtObj<O>`.
+    pub fn downcast_mut<O: __ExtObjDef>(&mut self) -> Option<&mut ExtObj<O>> {
+        self.0.as_any_mut().downcast_mut()
+    }
+}
+
+// the receivers expanded by pyo3 0.22, next to the impl, call an unsafe
+// function outside of an `unsafe` block, which edition 2024 reports
+#[allow(unsafe_op_in_unsafe_fn)]
+mod methods {
+    use super::*;
+
+    #[pymethods]
+    impl PyExtObj {
+        fn __getattr__(&self, py: Python<'_>, name: &str) -> PyResult<PyObject> {
+            self.0.get(py, name)
+        }
+
+        fn __setattr__(&mut self, name: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+            self.0.set(name, value)
+        }
+
+        fn __dir__(&self) -> Vec<&'static str> {
+            self.0.names()
+        }
+
+        fn __repr__(&self) -> String {
+            format!("<extobj.ExtObj of {}>", self.0.object())
+        }
+    }
+}
+
+/// The fields of an `ExtObj`, whose object type is erased.
+trait Fields: Any {
+    fn object(&self) -> &'static str;
+
+    fn names(&self) -> Vec<&'static str>;
+
+    fn get(&self, py: Python<'_>, name: &str) -> PyResult<PyObject>;
+
+    fn set(&mut self, name: &str, value: &Bound<'_, PyAny>) -> PyResult<()>;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<O: __ExtObjDef> Fields for ExtObj<O> {
+    fn object(&self) -> &'static str {
+        O::NAME
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        O::defs().with(|defs| defs[..self.0.len()].iter().map(|def| def.name).collect())
+    }
+
+    fn get(&self, py: Python<'_>, name: &str) -> PyResult<PyObject> {
+        let (index, def) = field::<O>(name, self.0.len())?;
+
+        let Some(serialize) = def.serialize else {
+            return Err(PyTypeError::new_err(format!(
+                "the field `{name}` of `{}` is not serializable",
+                O::NAME
+            )));
+        };
+
+        // SAFETY: the serializer was registered with the field type
+        let value = unsafe { &*serialize(def.value(&self.0[index])) };
+        Ok(pythonize(py, value)?.unbind())
+    }
+
+    fn set(&mut self, name: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        let (index, def) = field::<O>(name, self.0.len())?;
+
+        if def.readonly {
+            return Err(PyAttributeError::new_err(format!(
+                "the field `{name}` of `{}` is read-only",
+                O::NAME
+            )));
+        }
+
+        let Some(deserialize) = def.deserialize else {
+            return Err(PyTypeError::new_err(format!(
+                "the field `{name}` of `{}` is not deserializable",
+                O::NAME
+            )));
+        };
+
+        let mut de = Depythonizer::from_object(value);
+        let mut erased = <dyn erased_serde::Deserializer>::erase(&mut de);
+
+        // SAFETY: the deserializer was registered with the field type
+        unsafe { deserialize(def.value_mut(&mut self.0[index]), &mut erased) }
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+        self.notify(index);
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Finds the field `name` among the first `len` fields of `O`.
+///
+/// The definition is copied, so the lock is released before converting the
+/// value, which may construct other objects.
+fn field<O: __ExtObjDef>(name: &str, len: usize) -> PyResult<(usize, FieldDef)> {
+    O::defs()
+        .with(|defs| {
+            find_field(defs, name)
+                .filter(|&index| index < len)
+                .map(|index| (index, defs[index]))
+        })
+        .ok_or_else(|| PyAttributeError::new_err(format!("`{}` has no field `{name}`", O::NAME)))
+}
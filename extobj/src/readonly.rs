@@ -0,0 +1,155 @@
+use crate::{__ExtObjDef, CowExtObj, ExtObj, FieldAllocator, TrackedExtObj, Var, VarId};
+use std::{
+    fmt::{self, Debug, Formatter},
+    hash::{Hash, Hasher},
+    ops::{Deref, Index},
+    sync::OnceLock,
+};
+
+/// An immutable handle to a field, which reads it with `Index` but cannot
+/// mutate it.
+///
+/// `#[readonly]` fields are published as a [`ReadOnlyVar`] dereferencing to
+/// a `ReadVar`, and their declaring crate keeps the mutable [`Var`] in a
+/// `pub(crate)` static named after the field with a `_MUT` suffix.
+///
+/// # Example
+///
+/// ```
+/// extobj::extobj!(struct Node);
+/// extobj::extobj!(impl Node {
+///     #[readonly]
+///     pub DEPTH: u32,
+/// });
+///
+/// let mut node = extobj::ExtObj::<Node>::new();
+/// node[*DEPTH_MUT] = 3;
+///
+/// // other crates only see `DEPTH`, which cannot be used with `IndexMut`
+/// assert_eq!(node[*DEPTH], 3);
+/// ```
+#[repr(transparent)]
+pub struct ReadVar<O, T>(Var<O, T>);
+
+impl<O, T> ReadVar<O, T> {
+    /// Erases the type of the variable, see [`Var::var_id`].
+    #[inline]
+    pub fn var_id(self) -> VarId<O> {
+        self.0.var_id()
+    }
+}
+
+impl<O: __ExtObjDef, T: 'static> ReadVar<O, T> {
+    /// The name of the field, as declared in the `extobj!` macro.
+    pub fn name(self) -> &'static str {
+        self.0.name()
+    }
+
+    /// The type name of the field, from [`std::any::type_name`].
+    pub fn type_name(self) -> &'static str {
+        self.0.type_name()
+    }
+
+    /// The module path where the field is declared, from [`module_path!`].
+    pub fn origin(self) -> &'static str {
+        self.0.origin()
+    }
+}
+
+/// Every variable can be used as a read-only one.
+impl<O, T> From<Var<O, T>> for ReadVar<O, T> {
+    #[inline]
+    fn from(var: Var<O, T>) -> Self {
+        Self(var)
+    }
+}
+
+impl<O, T> Clone for ReadVar<O, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<O, T> Copy for ReadVar<O, T> {}
+
+impl<O, T> Debug for ReadVar<O, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ReadVar").field(&self.0).finish()
+    }
+}
+
+impl<O, T> Eq for ReadVar<O, T> {}
+
+impl<O, T> Hash for ReadVar<O, T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl<O, T> PartialEq for ReadVar<O, T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<O: __ExtObjDef, A: FieldAllocator, T> Index<ReadVar<O, T>> for ExtObj<O, A> {
+    type Output = T;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, index: ReadVar<O, T>) -> &Self::Output {
+        self.get(index.0)
+    }
+}
+
+impl<O: __ExtObjDef, T> Index<ReadVar<O, T>> for CowExtObj<O> {
+    type Output = T;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, index: ReadVar<O, T>) -> &Self::Output {
+        self.get(index.0)
+    }
+}
+
+impl<O: __ExtObjDef, A: FieldAllocator, T> Index<ReadVar<O, T>> for TrackedExtObj<O, A> {
+    type Output = T;
+
+    #[inline]
+    #[track_caller]
+    fn index(&self, index: ReadVar<O, T>) -> &Self::Output {
+        self.get(index.0)
+    }
+}
+
+/// The static published for a `#[readonly]` field, dereferencing to its
+/// [`ReadVar`].
+pub struct ReadOnlyVar<O, T> {
+    var: OnceLock<ReadVar<O, T>>,
+    writable: fn() -> Var<O, T>,
+}
+
+// SAFETY: a `Var` is only an index, it never holds a `T` or an `O`
+unsafe impl<O, T> Sync for ReadOnlyVar<O, T> {}
+
+impl<O, T> ReadOnlyVar<O, T> {
+    #[doc(hidden)]
+    pub const fn __new(writable: fn() -> Var<O, T>) -> Self {
+        Self {
+            var: OnceLock::new(),
+            writable,
+        }
+    }
+}
+
+impl<O, T> Deref for ReadOnlyVar<O, T> {
+    type Target = ReadVar<O, T>;
+
+    #[inline]
+    fn deref(&self) -> &ReadVar<O, T> {
+        self.var.get_or_init(|| ReadVar((self.writable)()))
+    }
+}
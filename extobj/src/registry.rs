@@ -0,0 +1,1322 @@
+use crate::{
+    __ExtObjDef,
+    __private::{
+        CloneFn, DebugFn, DeserializeFn, EqFn, HashFn, HeapSizeFn, SerializeFn, ZeroizeFn,
+    },
+    FieldAllocator, UntypedVar, Var,
+    error::BoxError,
+    future::{BoxFuture, Resolved},
+    observe::Observer,
+    validate::Validator,
+};
+use parking_lot::RwLock;
+#[cfg(feature = "metrics")]
+use std::sync::atomic::AtomicU64;
+use std::{
+    alloc::Layout,
+    any::{Any, TypeId, type_name},
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    ptr::{self, NonNull},
+    sync::{
+        OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+/// The type-erased v-table of one registered field.
+#[derive(Clone, Copy)]
+pub(crate) struct FieldDef {
+    /// Writes a value created by `init_fn` in a slot.
+    init: InitFn,
+    /// The type-erased `fn() -> T` (or one of the other initializer
+    /// signatures) creating the initial value.
+    init_fn: fn(),
+    /// Starts the future of the fields registered with `= init_async(f)`,
+    /// whose value is then stored by `init`.
+    init_async: Option<AsyncInitFn>,
+    /// Drops the value of a slot without freeing its memory.
+    drop: unsafe fn(usize),
+    /// Manipulates a boxed `Vec<T>`, a column of an `ExtObjVec`.
+    column: ColumnFns,
+    /// Fields are dropped by increasing priority, then in reverse
+    /// registration order.
+    drop_priority: i32,
+    /// Casts a slot to `*mut dyn Any`.
+    pub(crate) as_any: unsafe fn(usize) -> *mut dyn Any,
+    pub(crate) name: &'static str,
+    pub(crate) type_id: TypeId,
+    pub(crate) type_name: &'static str,
+    pub(crate) size: usize,
+    align: usize,
+    /// Values are stored in their slot, see [`is_inline`].
+    inline: bool,
+    /// The offset of the value in the buffer of an
+    /// [`ExtObjInline`](crate::ExtObjInline), after the fields registered
+    /// before it.
+    pub(crate) offset: usize,
+    /// The module where the field is declared.
+    pub(crate) origin: &'static str,
+    pub(crate) clone: Option<CloneFn>,
+    pub(crate) debug: Option<DebugFn>,
+    pub(crate) heap_size: Option<HeapSizeFn>,
+    pub(crate) eq: Option<EqFn>,
+    pub(crate) hash: Option<HashFn>,
+    pub(crate) serialize: Option<SerializeFn>,
+    pub(crate) deserialize: Option<DeserializeFn>,
+    pub(crate) validator: Option<Validator>,
+    /// The version of the field type and semantics, written in binary
+    /// snapshots.
+    pub(crate) version: u32,
+    /// Declared `#[readonly]`: the fields found by name cannot be written.
+    pub(crate) readonly: bool,
+    /// Wipes the value before it is dropped or reset.
+    zeroize: Option<ZeroizeFn>,
+    #[cfg(feature = "metrics")]
+    access: &'static AccessCounts,
+}
+
+impl FieldDef {
+    /// Creates the initial value of the field, in its slot or in memory
+    /// allocated by `alloc`.
+    ///
+    /// # Safety
+    /// The returned slot must be released with `destroy` and the same
+    /// allocator.
+    pub(crate) unsafe fn create(
+        &self,
+        ctx: &dyn Any,
+        alloc: &impl FieldAllocator,
+    ) -> Result<Slot, BoxError> {
+        if self.inline {
+            let mut slot = Slot::uninit();
+
+            // SAFETY: the slot can hold a value of the field type
+            unsafe { (self.init)(self.init_fn, ctx, Dest::Uninit(self.value_mut(&mut slot))) }?;
+            return Ok(slot);
+        }
+
+        let ptr = alloc.alloc(self.layout());
+
+        // SAFETY: the memory has the layout of the field type
+        unsafe { (self.init)(self.init_fn, ctx, Dest::Uninit(ptr.as_ptr() as usize)) }
+            .map(|()| Slot::boxed(ptr))
+            .inspect_err(|_| unsafe { alloc.dealloc(ptr, self.layout()) })
+    }
+
+    /// Starts the asynchronous initializer of the field, if it has one. The
+    /// resolved value is passed to `create` in a [`Resolved`] context.
+    pub(crate) fn start(&self) -> Option<BoxFuture<'static, Box<dyn Any + Send>>> {
+        // SAFETY: `init_async` was registered with the signature of `init_fn`
+        self.init_async.map(|start| unsafe { start(self.init_fn) })
+    }
+
+    /// Writes the initial value of the field at the address `value`.
+    ///
+    /// # Safety
+    /// `value` must be valid for writes of the layout of the field type.
+    #[inline]
+    pub(crate) unsafe fn init_at(&self, ctx: &dyn Any, value: usize) -> Result<(), BoxError> {
+        unsafe { (self.init)(self.init_fn, ctx, Dest::Uninit(value)) }
+    }
+
+    /// Drops the value at the address `value`, wiping it first when the field
+    /// is `#[zeroize]`.
+    ///
+    /// # Safety
+    /// `value` must hold a value of the field type, not used afterwards.
+    #[inline]
+    pub(crate) unsafe fn drop_at(&self, value: usize) {
+        unsafe {
+            if let Some(zeroize) = self.zeroize {
+                zeroize(value);
+            }
+
+            (self.drop)(value);
+        }
+    }
+
+    /// Drops the value of `slot` and releases its memory.
+    ///
+    /// # Safety
+    /// `slot` must have been created by this definition with `alloc`, and not
+    /// be used afterwards.
+    #[inline]
+    pub(crate) unsafe fn destroy(&self, mut slot: Slot, alloc: &impl FieldAllocator) {
+        unsafe {
+            self.drop_at(self.value_mut(&mut slot));
+
+            if !self.inline {
+                alloc.dealloc(slot.ptr(), self.layout());
+            }
+        }
+    }
+
+    /// The address of the value of `slot`, for reading.
+    ///
+    /// # Safety
+    /// `slot` must have been created by this definition.
+    #[inline]
+    pub(crate) unsafe fn value(&self, slot: &Slot) -> usize {
+        if self.inline {
+            slot as *const Slot as usize
+        } else {
+            unsafe { slot.ptr().as_ptr() as usize }
+        }
+    }
+
+    /// The address of the value of `slot`, for writing.
+    ///
+    /// # Safety
+    /// `slot` must have been created by this definition, or be uninitialized
+    /// when the value is inline.
+    #[inline]
+    pub(crate) unsafe fn value_mut(&self, slot: &mut Slot) -> usize {
+        if self.inline {
+            slot as *mut Slot as usize
+        } else {
+            unsafe { slot.ptr().as_ptr() as usize }
+        }
+    }
+
+    /// Clones the value of `src`, in the new slot or in memory allocated by
+    /// `alloc`, or returns `None` when the field type does not implement
+    /// `Clone`.
+    ///
+    /// # Safety
+    /// `src` must have been created by this definition. The returned slot
+    /// must be released with `destroy` and the same allocator.
+    pub(crate) unsafe fn clone_slot(
+        &self,
+        src: &Slot,
+        alloc: &impl FieldAllocator,
+    ) -> Option<Slot> {
+        let clone = self.clone?;
+
+        let mut dst = match self.inline {
+            true => Slot::uninit(),
+            false => Slot::boxed(alloc.alloc(self.layout())),
+        };
+
+        // SAFETY: the cloner was registered with the field type
+        unsafe { clone(self.value(src), self.value_mut(&mut dst), false) };
+        Some(dst)
+    }
+
+    /// Replaces the value of `dst` with a clone of the value of `src`, or
+    /// returns `false` when the field type does not implement `Clone`.
+    ///
+    /// # Safety
+    /// Both slots must have been created by this definition.
+    pub(crate) unsafe fn clone_over(&self, src: &Slot, dst: &mut Slot) -> bool {
+        match self.clone {
+            Some(clone) => {
+                // SAFETY: the cloner was registered with the field type
+                unsafe { clone(self.value(src), self.value_mut(dst), true) };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Swaps the values of two slots, which may use different allocators.
+    ///
+    /// # Safety
+    /// Both slots must have been created by this definition.
+    #[inline]
+    pub(crate) unsafe fn swap(&self, a: &mut Slot, b: &mut Slot) {
+        unsafe {
+            ptr::swap_nonoverlapping(
+                self.value_mut(a) as *mut u8,
+                self.value_mut(b) as *mut u8,
+                self.size,
+            )
+        }
+    }
+
+    /// Replaces the value of `slot` with a new initial value, keeping its
+    /// allocation. The old value is kept if the initializer fails.
+    ///
+    /// # Safety
+    /// `slot` must have been created by this definition.
+    #[inline]
+    pub(crate) unsafe fn reset(&self, slot: &mut Slot, ctx: &dyn Any) -> Result<(), BoxError> {
+        let dest = Dest::Reset(unsafe { self.value_mut(slot) }, self.zeroize);
+        unsafe { (self.init)(self.init_fn, ctx, dest) }
+    }
+
+    /// Creates an empty column.
+    #[inline]
+    pub(crate) fn new_column(&self) -> usize {
+        (self.column.new)()
+    }
+
+    /// Pushes the initial value of the field at the end of `column`.
+    ///
+    /// # Safety
+    /// `column` must have been created by this definition.
+    #[inline]
+    pub(crate) unsafe fn push_column(&self, column: usize, ctx: &dyn Any) -> Result<(), BoxError> {
+        unsafe { (self.init)(self.init_fn, ctx, Dest::Push(column)) }
+    }
+
+    /// # Safety
+    /// `column` must have been created by this definition.
+    #[inline]
+    pub(crate) unsafe fn drop_column(&self, column: usize) {
+        unsafe { (self.column.drop)(column) }
+    }
+
+    /// # Safety
+    /// `column` must have been created by this definition.
+    #[inline]
+    pub(crate) unsafe fn truncate_column(&self, column: usize, len: usize) {
+        unsafe { (self.column.truncate)(column, len) }
+    }
+
+    /// # Safety
+    /// `column` must have been created by this definition and `index` be in
+    /// bounds.
+    #[inline]
+    pub(crate) unsafe fn swap_remove_column(&self, column: usize, index: usize) {
+        unsafe { (self.column.swap_remove)(column, index) }
+    }
+
+    /// The memory layout of the field type.
+    pub(crate) fn layout(&self) -> Layout {
+        // SAFETY: taken from the layout of the field type
+        unsafe { Layout::from_size_align_unchecked(self.size, self.align) }
+    }
+
+    /// The crate declaring the field.
+    pub(crate) fn krate(&self) -> &'static str {
+        self.origin.split("::").next().unwrap_or(self.origin)
+    }
+
+    /// The stable key of the field, `crate::NAME`, which survives moving the
+    /// field to another module.
+    pub(crate) fn key(&self) -> FieldKey {
+        FieldKey(self.krate(), self.name)
+    }
+}
+
+/// The reads and writes of a field through its `Var`, see the `metrics`
+/// feature.
+#[cfg(feature = "metrics")]
+#[derive(Default)]
+struct AccessCounts {
+    reads: AtomicU64,
+    writes: AtomicU64,
+}
+
+/// The stable key of a field, displayed as `crate::NAME`.
+#[derive(Clone, Copy)]
+pub(crate) struct FieldKey(&'static str, &'static str);
+
+impl FieldKey {
+    pub(crate) fn matches(self, key: &str) -> bool {
+        key.split_once("::") == Some((self.0, self.1))
+    }
+}
+
+impl Display for FieldKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}::{}", self.0, self.1)
+    }
+}
+
+/// The description of a field of type `T`, ready to be registered.
+///
+/// # Note
+/// This type is for used only in macros.
+#[doc(hidden)]
+pub struct Field<T> {
+    pub(crate) def: FieldDef,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static> Field<T> {
+    pub fn new(name: &'static str, origin: &'static str, init: fn() -> T) -> Self {
+        // SAFETY: transmuted back by `init_boxed::<T>`
+        let init_fn = unsafe { mem::transmute::<fn() -> T, fn()>(init) };
+        Self::with_init(name, origin, init_boxed::<T>, init_fn)
+    }
+
+    pub fn try_new(
+        name: &'static str,
+        origin: &'static str,
+        init: fn() -> Result<T, BoxError>,
+    ) -> Self {
+        // SAFETY: transmuted back by `try_init_boxed::<T>`
+        let init_fn = unsafe { mem::transmute::<fn() -> Result<T, BoxError>, fn()>(init) };
+        Self::with_init(name, origin, try_init_boxed::<T>, init_fn)
+    }
+
+    pub fn with_ctx(name: &'static str, origin: &'static str, init: fn(&dyn Any) -> T) -> Self {
+        // SAFETY: transmuted back by `init_with_ctx_boxed::<T>`
+        let init_fn = unsafe { mem::transmute::<fn(&dyn Any) -> T, fn()>(init) };
+        Self::with_init(name, origin, init_with_ctx_boxed::<T>, init_fn)
+    }
+
+    pub fn try_with_ctx(
+        name: &'static str,
+        origin: &'static str,
+        init: fn(&dyn Any) -> Result<T, BoxError>,
+    ) -> Self {
+        // SAFETY: transmuted back by `try_init_with_ctx_boxed::<T>`
+        let init_fn = unsafe { mem::transmute::<fn(&dyn Any) -> Result<T, BoxError>, fn()>(init) };
+        Self::with_init(name, origin, try_init_with_ctx_boxed::<T>, init_fn)
+    }
+
+    pub fn new_async(
+        name: &'static str,
+        origin: &'static str,
+        init: fn() -> BoxFuture<'static, T>,
+    ) -> Self
+    where
+        T: Send,
+    {
+        // SAFETY: transmuted back by `start_async::<T>`
+        let init_fn = unsafe { mem::transmute::<fn() -> BoxFuture<'static, T>, fn()>(init) };
+        let mut field = Self::with_init(name, origin, init_async_boxed::<T>, init_fn);
+        field.def.init_async = Some(start_async::<T>);
+        field
+    }
+
+    fn with_init(name: &'static str, origin: &'static str, init: InitFn, init_fn: fn()) -> Self {
+        Self {
+            def: FieldDef {
+                init,
+                init_fn,
+                init_async: None,
+                drop: dropper::<T>,
+                column: ColumnFns::of::<T>(),
+                drop_priority: 0,
+                as_any: as_any::<T>,
+                name,
+                type_id: TypeId::of::<T>(),
+                type_name: type_name::<T>(),
+                size: mem::size_of::<T>(),
+                align: mem::align_of::<T>(),
+                inline: is_inline::<T>(),
+                offset: 0,
+                origin,
+                clone: None,
+                debug: None,
+                heap_size: None,
+                validator: None,
+                version: 0,
+                readonly: false,
+                zeroize: None,
+                #[cfg(feature = "metrics")]
+                access: Box::leak(Box::default()),
+                eq: None,
+                hash: None,
+                serialize: None,
+                deserialize: None,
+            },
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Field<T> {
+    pub fn clone_fn(mut self, clone: Option<CloneFn>) -> Self {
+        self.def.clone = clone;
+        self
+    }
+
+    pub fn debug(mut self, debug: Option<DebugFn>) -> Self {
+        self.def.debug = debug;
+        self
+    }
+
+    pub fn heap_size(mut self, heap_size: Option<HeapSizeFn>) -> Self {
+        self.def.heap_size = heap_size;
+        self
+    }
+
+    pub fn eq_hash(mut self, eq: Option<EqFn>, hash: Option<HashFn>) -> Self {
+        self.def.eq = eq;
+        self.def.hash = hash;
+        self
+    }
+
+    pub fn validate<E: Into<BoxError>>(mut self, f: fn(&T) -> Result<(), E>) -> Self {
+        self.def.validator = Some(Validator::new(f));
+        self
+    }
+
+    #[cfg(feature = "zeroize")]
+    pub fn zeroize(mut self) -> Self
+    where
+        T: zeroize::Zeroize,
+    {
+        unsafe fn zeroize<T: zeroize::Zeroize>(ptr: usize) {
+            unsafe { (*(ptr as *mut T)).zeroize() }
+        }
+
+        self.def.zeroize = Some(zeroize::<T>);
+        self
+    }
+
+    pub fn drop_priority(mut self, priority: i32) -> Self {
+        self.def.drop_priority = priority;
+        self
+    }
+
+    pub fn version(mut self, version: u32) -> Self {
+        self.def.version = version;
+        self
+    }
+
+    pub fn readonly(mut self) -> Self {
+        self.def.readonly = true;
+        self
+    }
+
+    pub fn serde(
+        mut self,
+        serialize: Option<SerializeFn>,
+        deserialize: Option<DeserializeFn>,
+    ) -> Self {
+        self.def.serialize = serialize;
+        self.def.deserialize = deserialize;
+        self
+    }
+}
+
+/// Where an initializer stores the value it creates.
+#[derive(Clone, Copy)]
+enum Dest {
+    /// In uninitialized memory with the layout of the field.
+    Uninit(usize),
+    /// Over the live value of a slot, reusing its allocation. The old value is
+    /// wiped first when the field is zeroized.
+    Reset(usize, Option<ZeroizeFn>),
+    /// At the end of a column.
+    Push(usize),
+}
+
+/// Creates a value and stores it in `dest`.
+type InitFn = unsafe fn(fn(), &dyn Any, Dest) -> Result<(), BoxError>;
+
+/// Starts an asynchronous initializer, whose future resolves to the boxed
+/// value.
+type AsyncInitFn = unsafe fn(fn()) -> BoxFuture<'static, Box<dyn Any + Send>>;
+
+/// # Safety
+/// `dest` must point to a live `T`, to uninitialized memory for a `T` or to
+/// a `Vec<T>`.
+unsafe fn store<T>(dest: Dest, value: T) {
+    match dest {
+        Dest::Uninit(ptr) => unsafe { ptr::write(ptr as *mut T, value) },
+        Dest::Reset(ptr, zeroize) => unsafe {
+            if let Some(zeroize) = zeroize {
+                zeroize(ptr);
+            }
+
+            *(ptr as *mut T) = value
+        },
+        Dest::Push(column) => unsafe { (*(column as *mut Vec<T>)).push(value) },
+    }
+}
+
+unsafe fn init_boxed<T>(init: fn(), _: &dyn Any, dest: Dest) -> Result<(), BoxError> {
+    let init = unsafe { mem::transmute::<fn(), fn() -> T>(init) };
+    unsafe { store(dest, init()) };
+    Ok(())
+}
+
+unsafe fn try_init_boxed<T>(init: fn(), _: &dyn Any, dest: Dest) -> Result<(), BoxError> {
+    let init = unsafe { mem::transmute::<fn(), fn() -> Result<T, BoxError>>(init) };
+    unsafe { store(dest, init()?) };
+    Ok(())
+}
+
+unsafe fn init_with_ctx_boxed<T>(init: fn(), ctx: &dyn Any, dest: Dest) -> Result<(), BoxError> {
+    let init = unsafe { mem::transmute::<fn(), fn(&dyn Any) -> T>(init) };
+    unsafe { store(dest, init(ctx)) };
+    Ok(())
+}
+
+unsafe fn try_init_with_ctx_boxed<T>(
+    init: fn(),
+    ctx: &dyn Any,
+    dest: Dest,
+) -> Result<(), BoxError> {
+    let init = unsafe { mem::transmute::<fn(), fn(&dyn Any) -> Result<T, BoxError>>(init) };
+    unsafe { store(dest, init(ctx)?) };
+    Ok(())
+}
+
+unsafe fn start_async<T: Send + 'static>(init: fn()) -> BoxFuture<'static, Box<dyn Any + Send>> {
+    let init = unsafe { mem::transmute::<fn(), fn() -> BoxFuture<'static, T>>(init) };
+    let future = init();
+    Box::pin(async move { Box::new(future.await) as Box<dyn Any + Send> })
+}
+
+/// Stores the value resolved by `start_async::<T>`, which only
+/// [`ExtObj::new_async`](crate::ExtObj::new_async) passes as the context.
+unsafe fn init_async_boxed<T: 'static>(_: fn(), ctx: &dyn Any, dest: Dest) -> Result<(), BoxError> {
+    let Some(value) = ctx.downcast_ref::<Resolved>().and_then(Resolved::take) else {
+        return Err("the initializer is async, create the object with `ExtObj::new_async`".into());
+    };
+
+    let value = value
+        .downcast::<T>()
+        .expect("the future resolves to the field type");
+    unsafe { store(dest, *value) };
+    Ok(())
+}
+
+/// The type-erased operations on a column, a boxed `Vec<T>`.
+#[derive(Clone, Copy)]
+struct ColumnFns {
+    new: fn() -> usize,
+    drop: unsafe fn(usize),
+    truncate: unsafe fn(usize, usize),
+    swap_remove: unsafe fn(usize, usize),
+}
+
+impl ColumnFns {
+    fn of<T>() -> Self {
+        fn new<T>() -> usize {
+            Box::into_raw(Box::new(Vec::<T>::new())) as usize
+        }
+
+        unsafe fn drop<T>(column: usize) {
+            mem::drop(unsafe { Box::from_raw(column as *mut Vec<T>) });
+        }
+
+        unsafe fn truncate<T>(column: usize, len: usize) {
+            unsafe { (*(column as *mut Vec<T>)).truncate(len) }
+        }
+
+        unsafe fn swap_remove<T>(column: usize, index: usize) {
+            unsafe { (*(column as *mut Vec<T>)).swap_remove(index) };
+        }
+
+        Self {
+            new: new::<T>,
+            drop: drop::<T>,
+            truncate: truncate::<T>,
+            swap_remove: swap_remove::<T>,
+        }
+    }
+}
+
+/// The storage of a field in an object: the value itself when its type fits
+/// in a word, see [`is_inline`], or a pointer to its allocation.
+#[derive(Clone, Copy)]
+#[repr(transparent)]
+pub(crate) struct Slot(MaybeUninit<usize>);
+
+/// Returns `true` when the values of `T` are stored in their slot, saving an
+/// allocation and an indirection.
+///
+/// Only plain values without drop glue are inlined, the way `Copy` types are:
+/// owning pointers like `Box`, `Rc` or `Arc` keep their own allocation.
+#[inline(always)]
+pub(crate) const fn is_inline<T>() -> bool {
+    mem::size_of::<T>() <= mem::size_of::<Slot>()
+        && mem::align_of::<T>() <= mem::align_of::<Slot>()
+        && !mem::needs_drop::<T>()
+}
+
+impl Slot {
+    #[inline]
+    pub(crate) const fn uninit() -> Self {
+        Self(MaybeUninit::uninit())
+    }
+
+    #[inline]
+    fn boxed(ptr: NonNull<u8>) -> Self {
+        Self(MaybeUninit::new(ptr.as_ptr() as usize))
+    }
+
+    /// The allocation of a value which is not inline.
+    ///
+    /// # Safety
+    /// The slot must hold a pointer.
+    #[inline]
+    unsafe fn ptr(&self) -> NonNull<u8> {
+        unsafe { NonNull::new_unchecked(self.0.assume_init() as *mut u8) }
+    }
+
+    /// The value of a slot holding a `T`.
+    ///
+    /// # Safety
+    /// The slot must have been created for a field of type `T`, and the value
+    /// must not be mutated through `slot` while the pointer is used.
+    #[inline(always)]
+    pub(crate) unsafe fn raw<T>(slot: *mut Slot) -> *mut T {
+        if is_inline::<T>() {
+            slot.cast()
+        } else {
+            unsafe { (*slot).0.assume_init() as *mut T }
+        }
+    }
+
+    /// # Safety
+    /// The slot must have been created for a field of type `T`.
+    #[inline(always)]
+    pub(crate) unsafe fn get<T>(&self) -> &T {
+        unsafe { &*Self::raw::<T>(self as *const Slot as *mut Slot) }
+    }
+
+    /// # Safety
+    /// The slot must have been created for a field of type `T`.
+    #[inline(always)]
+    pub(crate) unsafe fn get_mut<T>(&mut self) -> &mut T {
+        unsafe { &mut *Self::raw::<T>(self) }
+    }
+}
+
+/// Calls `f` with the index of the first `len` definitions, by increasing
+/// drop priority and then in reverse registration order.
+pub(crate) fn drop_order(defs: &[FieldDef], len: usize, mut f: impl FnMut(usize)) {
+    let defs = &defs[..len];
+
+    if defs.iter().all(|def| def.drop_priority == 0) {
+        (0..len).rev().for_each(f);
+        return;
+    }
+
+    let mut order: Vec<usize> = (0..len).rev().collect();
+    // stable, so equal priorities stay in reverse registration order
+    order.sort_by_key(|&i| defs[i].drop_priority);
+
+    for i in order {
+        f(i);
+    }
+}
+
+/// Drops the values of `slots`, see [`drop_order`].
+///
+/// # Safety
+/// Every slot must have been created by the definition at the same index,
+/// with `alloc`.
+pub(crate) unsafe fn drop_slots(defs: &[FieldDef], slots: &[Slot], alloc: &impl FieldAllocator) {
+    drop_order(defs, slots.len(), |i| unsafe {
+        defs[i].destroy(slots[i], alloc)
+    });
+}
+
+/// The slots of an object under construction, dropped unless finished.
+pub(crate) struct PartialSlots<'a, A: FieldAllocator> {
+    defs: &'a [FieldDef],
+    slots: Vec<Slot>,
+    alloc: &'a A,
+}
+
+impl<'a, A: FieldAllocator> PartialSlots<'a, A> {
+    pub(crate) fn new(defs: &'a [FieldDef], alloc: &'a A) -> Self {
+        Self {
+            defs,
+            slots: Vec::with_capacity(defs.len()),
+            alloc,
+        }
+    }
+
+    /// Adds the slot created by the next definition.
+    #[inline]
+    pub(crate) fn push(&mut self, slot: Slot) {
+        self.slots.push(slot);
+    }
+
+    pub(crate) fn finish(mut self) -> Vec<Slot> {
+        mem::take(&mut self.slots)
+    }
+}
+
+impl<A: FieldAllocator> Drop for PartialSlots<'_, A> {
+    fn drop(&mut self) {
+        // SAFETY: the slots were created by the definitions at the same index
+        unsafe { drop_slots(self.defs, &self.slots, self.alloc) };
+    }
+}
+
+unsafe fn as_any<T: 'static>(ptr: usize) -> *mut dyn Any {
+    ptr as *mut T
+}
+
+unsafe fn dropper<T>(ptr: usize) {
+    unsafe { ptr::drop_in_place(ptr as *mut T) }
+}
+
+/// The field registry of an extobj type.
+///
+/// # Note
+/// This type is for used only in macros.
+#[doc(hidden)]
+pub struct Defs {
+    fields: RwLock<Vec<FieldDef>>,
+    frozen: OnceLock<Box<[FieldDef]>>,
+    /// The registry of the host binary, once attached, see [`attach`].
+    host: OnceLock<&'static Defs>,
+    /// The change observers, by field index, see [`on_change`](crate::on_change).
+    observers: RwLock<Vec<(usize, Observer)>>,
+    /// Set once an observer is registered, so writes skip the lock until then.
+    observed: AtomicBool,
+    #[cfg(feature = "diagnostics")]
+    created: std::sync::atomic::AtomicUsize,
+    #[cfg(feature = "diagnostics")]
+    dropped: std::sync::atomic::AtomicUsize,
+    /// Set once the fields of the `linkme` distributed slice are registered.
+    #[cfg(feature = "linkme")]
+    linked: std::sync::Once,
+}
+
+impl Defs {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            fields: RwLock::new(Vec::new()),
+            frozen: OnceLock::new(),
+            host: OnceLock::new(),
+            observers: RwLock::new(Vec::new()),
+            observed: AtomicBool::new(false),
+            #[cfg(feature = "diagnostics")]
+            created: std::sync::atomic::AtomicUsize::new(0),
+            #[cfg(feature = "diagnostics")]
+            dropped: std::sync::atomic::AtomicUsize::new(0),
+            #[cfg(feature = "linkme")]
+            linked: std::sync::Once::new(),
+        }
+    }
+
+    /// The registry holding the fields, which is the host one once attached.
+    #[inline]
+    fn target(&self) -> &Defs {
+        match self.host.get() {
+            Some(host) => host,
+            None => self,
+        }
+    }
+
+    fn attach(&'static self, host: &'static Defs) {
+        let host = host.target();
+
+        if ptr::eq(self, host) {
+            return;
+        }
+
+        // holding the write lock ensures no local registration races the attach
+        let fields = self.fields.write();
+
+        if !fields.is_empty() {
+            panic!("cannot attach a registry after fields were registered in it");
+        }
+
+        if !ptr::eq(*self.host.get_or_init(|| host), host) {
+            panic!("the registry is already attached to another host");
+        }
+    }
+
+    /// Returns the registry of the instantiation `O` of a generic extobj type.
+    ///
+    /// A `static` inside a generic function is shared by every instantiation,
+    /// so the registries are leaked and looked up by `TypeId` instead.
+    pub fn generic<O: 'static>() -> &'static Defs {
+        static GENERIC: RwLock<BTreeMap<TypeId, &'static Defs>> = RwLock::new(BTreeMap::new());
+
+        let id = TypeId::of::<O>();
+
+        if let Some(defs) = GENERIC.read().get(&id) {
+            return defs;
+        }
+
+        GENERIC
+            .write()
+            .entry(id)
+            .or_insert_with(|| Box::leak(Box::new(Defs::new())))
+    }
+
+    /// Appends a field of `object` and returns its index.
+    ///
+    /// # Panics
+    /// Panics if the registry has been frozen, naming the field and the crate
+    /// registering it.
+    pub(crate) fn register(&self, object: &'static str, mut def: FieldDef) -> usize {
+        let mut fields = self.fields.write();
+
+        if let Some(host) = self.host.get() {
+            drop(fields);
+            return host.register(object, def);
+        }
+
+        if self.frozen.get().is_some() {
+            drop(fields);
+            frozen_registration(object, &def);
+        }
+
+        def.offset = fields
+            .last()
+            .map_or(0, |last| last.offset + last.size)
+            .next_multiple_of(def.align);
+
+        fields.push(def);
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            object,
+            field = def.name,
+            type_name = def.type_name,
+            origin = def.origin,
+            index = fields.len() - 1,
+            "registered field"
+        );
+
+        fields.len() - 1
+    }
+
+    /// Calls `f` with the registered fields.
+    ///
+    /// Once frozen, this does not take the lock anymore.
+    #[inline]
+    pub(crate) fn with<R>(&self, f: impl FnOnce(&[FieldDef]) -> R) -> R {
+        let this = self.target();
+
+        match this.frozen.get() {
+            Some(fields) => f(fields),
+            None => f(&this.fields.read()),
+        }
+    }
+
+    /// Registers an observer of the field at `index`.
+    pub(crate) fn observe(&self, index: usize, observer: Observer) {
+        let this = self.target();
+        this.observers.write().push((index, observer));
+        this.observed.store(true, Ordering::Release);
+    }
+
+    /// Calls `f` with each observer of the field at `index`, in registration
+    /// order.
+    ///
+    /// The observers are copied first, so they may register other observers.
+    #[inline]
+    pub(crate) fn each_observer(&self, index: usize, f: impl FnMut(Observer)) {
+        let this = self.target();
+
+        if !this.observed.load(Ordering::Acquire) {
+            return;
+        }
+
+        let observers: Vec<_> = this
+            .observers
+            .read()
+            .iter()
+            .filter(|(i, _)| *i == index)
+            .map(|&(_, observer)| observer)
+            .collect();
+
+        observers.into_iter().for_each(f);
+    }
+
+    #[cfg(feature = "diagnostics")]
+    #[inline]
+    pub(crate) fn count_created(&self) {
+        self.target().created.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "diagnostics")]
+    #[inline]
+    pub(crate) fn count_dropped(&self) {
+        self.target().dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a read or a write of the field at `index` through its `Var`.
+    #[cfg(feature = "metrics")]
+    #[inline]
+    pub(crate) fn count_access(&self, index: usize, write: bool) {
+        self.with(|defs| {
+            if let Some(def) = defs.get(index) {
+                let counter = if write {
+                    &def.access.writes
+                } else {
+                    &def.access.reads
+                };
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+    }
+
+    /// The number of objects created and dropped.
+    #[cfg(feature = "diagnostics")]
+    pub(crate) fn counts(&self) -> (usize, usize) {
+        let this = self.target();
+        let dropped = this.dropped.load(Ordering::Relaxed);
+        (this.created.load(Ordering::Relaxed), dropped)
+    }
+
+    fn freeze(&self) {
+        let this = self.target();
+
+        // holding the write lock ensures no registration can race the snapshot
+        let fields = this.fields.write();
+        this.frozen.get_or_init(|| fields.as_slice().into());
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn frozen_registration(object: &str, def: &FieldDef) -> ! {
+    panic!(
+        "cannot register field `{}` of `{object}` from crate `{}` (declared in `{}`): \
+         the registry has been frozen, the crate was probably loaded after objects were created",
+        def.name,
+        def.krate(),
+        def.origin,
+    )
+}
+
+#[cold]
+#[inline(never)]
+fn sealed_registration(object: &str, field: &str) -> ! {
+    panic!("cannot register field `{field}` of `{object}`: the object is sealed")
+}
+
+#[cold]
+#[inline(never)]
+fn incomparable_registration(object: &str, field: &str) -> ! {
+    panic!(
+        "cannot register field `{field}` of `{object}` at runtime: \
+         the object is comparable, its fields must be declared with `extobj!`"
+    )
+}
+
+/// Returns the registry of `O`, after registering the fields collected at
+/// link time when the `linkme` feature is enabled.
+#[inline]
+pub(crate) fn loaded_defs<O: __ExtObjDef>() -> &'static Defs {
+    let defs = O::defs();
+
+    #[cfg(feature = "linkme")]
+    defs.linked.call_once(|| {
+        let object = TypeId::of::<O>();
+
+        for entry in crate::__private::LINKED_FIELDS {
+            if (entry.object)() == object {
+                entry.register();
+            }
+        }
+    });
+
+    defs
+}
+
+/// The description of a registered field, see [`registry`].
+#[derive(Clone, Copy)]
+pub struct FieldInfo {
+    index: usize,
+    def: FieldDef,
+}
+
+impl FieldInfo {
+    /// The position of the field in the registry.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The name of the field, as declared in the `extobj!` macro.
+    pub fn name(&self) -> &'static str {
+        self.def.name
+    }
+
+    /// The type name of the field, from [`std::any::type_name`].
+    pub fn type_name(&self) -> &'static str {
+        self.def.type_name
+    }
+
+    /// The size of the field type in bytes.
+    pub fn size(&self) -> usize {
+        self.def.size
+    }
+
+    /// The alignment of the field type in bytes.
+    pub fn align(&self) -> usize {
+        self.def.align
+    }
+
+    /// Returns `true` when the values are stored in the object itself rather
+    /// than in a separate allocation, which is the case of the types fitting
+    /// in a `usize` and without drop glue.
+    pub fn is_inline(&self) -> bool {
+        self.def.inline
+    }
+
+    /// Returns `true` when the field is declared `#[readonly]`, so it cannot
+    /// be written through [`var_by_name`] nor by the other by-name writers.
+    pub fn is_readonly(&self) -> bool {
+        self.def.readonly
+    }
+
+    /// The number of times the field was borrowed through its `Var`, with
+    /// `get`, `try_get` or `Index`, on any object.
+    #[cfg(feature = "metrics")]
+    pub fn reads(&self) -> u64 {
+        self.def.access.reads.load(Ordering::Relaxed)
+    }
+
+    /// The number of times the field was mutably borrowed through its `Var`,
+    /// with `get_mut`, `try_get_mut`, `set` or `IndexMut`, on any object.
+    #[cfg(feature = "metrics")]
+    pub fn writes(&self) -> u64 {
+        self.def.access.writes.load(Ordering::Relaxed)
+    }
+
+    /// Resets the read and write counters of the field.
+    #[cfg(feature = "metrics")]
+    pub fn reset_access_counts(&self) {
+        self.def.access.reads.store(0, Ordering::Relaxed);
+        self.def.access.writes.store(0, Ordering::Relaxed);
+    }
+
+    /// The module path where the field is declared.
+    pub fn origin(&self) -> &'static str {
+        self.def.origin
+    }
+
+    /// The crate which registered the field.
+    pub fn crate_name(&self) -> &'static str {
+        self.def.krate()
+    }
+
+    /// The stable key of the field, `crate::NAME`, used by serialization.
+    pub fn key(&self) -> String {
+        self.def.key().to_string()
+    }
+}
+
+impl fmt::Debug for FieldInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FieldInfo")
+            .field("index", &self.index)
+            .field("name", &self.name())
+            .field("type_name", &self.type_name())
+            .field("size", &self.size())
+            .field("align", &self.align())
+            .field("origin", &self.origin())
+            .finish()
+    }
+}
+
+/// Returns the fields registered for `O`, in registration order.
+///
+/// Useful for diagnostics, or to check which crates extended an object.
+///
+/// # Example
+///
+/// ```
+/// extobj::extobj!(struct Ctx);
+/// extobj::extobj!(impl Ctx { pub RETRIES: u32 });
+///
+/// let field = extobj::registry::<Ctx>().next().unwrap();
+/// assert_eq!(field.name(), "RETRIES");
+/// assert_eq!(field.type_name(), "u32");
+/// assert_eq!(field.size(), 4);
+/// ```
+pub fn registry<O: __ExtObjDef>() -> impl Iterator<Item = FieldInfo> {
+    let fields: Vec<_> = loaded_defs::<O>().with(|defs| {
+        defs.iter()
+            .enumerate()
+            .map(|(index, &def)| FieldInfo { index, def })
+            .collect()
+    });
+
+    fields.into_iter()
+}
+
+/// Finds a field of `O` by name, for scripting layers and configuration
+/// loaders resolving fields from strings.
+///
+/// `name` is either the name of the field, as declared in the `extobj!`
+/// macro, or its stable key `crate::NAME` when several crates declare a field
+/// with that name. Returns `None` when no field, or more than one, matches.
+///
+/// # Example
+///
+/// ```
+/// extobj::extobj!(struct Ctx);
+/// extobj::extobj!(impl Ctx { pub COUNTER: u32 = 7 });
+///
+/// let var = extobj::var_by_name::<Ctx>("COUNTER").unwrap();
+/// let obj = extobj::ExtObj::<Ctx>::new();
+/// assert_eq!(obj[var.downcast::<u32>().unwrap()], 7);
+///
+/// assert!(extobj::var_by_name::<Ctx>("MISSING").is_none());
+/// ```
+///
+/// The variable of a `#[readonly]` field only downcasts to a
+/// [`ReadVar`](crate::ReadVar), with [`UntypedVar::downcast_read`].
+pub fn var_by_name<O: __ExtObjDef>(name: &str) -> Option<UntypedVar<O>> {
+    loaded_defs::<O>().with(|defs| {
+        let index = find_field(defs, name)?;
+
+        Some(UntypedVar {
+            index,
+            type_id: defs[index].type_id,
+            writable: !defs[index].readonly,
+            _marker: PhantomData,
+        })
+    })
+}
+
+/// The index of the only field named `name`, or whose stable key is `name`.
+pub(crate) fn find_field(defs: &[FieldDef], name: &str) -> Option<usize> {
+    let mut found = defs
+        .iter()
+        .enumerate()
+        .filter(|(_, def)| def.name == name || def.key().matches(name));
+
+    match (found.next(), found.next()) {
+        (Some((index, _)), None) => Some(index),
+        _ => None,
+    }
+}
+
+/// Registers a new field of `O` at runtime and returns its variable.
+///
+/// Plugin hosts can create fields from their configuration without declaring
+/// them in an `extobj!` block. The name is leaked, and the field is declared
+/// in the `runtime` origin, so its stable key is `runtime::NAME`. Such fields
+/// are neither `Debug`, comparable nor serialized.
+///
+/// Objects created before the call do not hold the field until
+/// [`ExtObj::upgrade`](crate::ExtObj::upgrade).
+///
+/// # Panics
+/// Panics if the registry of `O` is frozen, if `O` is sealed or has a key, or
+/// if `O` is comparable.
+///
+/// # Example
+///
+/// ```
+/// extobj::extobj!(struct Ctx);
+///
+/// let score = extobj::register_field::<Ctx, u32>("score", || 10);
+///
+/// let ctx = extobj::ExtObj::<Ctx>::new();
+/// assert_eq!(ctx[score], 10);
+/// assert_eq!(score.name(), "score");
+/// ```
+pub fn register_field<O: __ExtObjDef, T: 'static>(name: &str, init: fn() -> T) -> Var<O, T>
+where
+    O::Threading: crate::__private::Accepts<T>,
+{
+    if O::SEALED {
+        sealed_registration(O::NAME, name);
+    }
+
+    // the comparators of the field type are not known here
+    if TypeId::of::<O::Comparability>() == TypeId::of::<crate::__private::Comparable>() {
+        incomparable_registration(O::NAME, name);
+    }
+
+    let name = Box::leak(name.to_owned().into_boxed_str());
+    Var::register(Field::new(name, RUNTIME_ORIGIN, init))
+}
+
+/// The origin of the fields registered with [`register_field`].
+const RUNTIME_ORIGIN: &str = "runtime";
+
+/// A handle to the field registry of `O` in the current binary, see
+/// [`attach`].
+pub struct RegistryHandle<O> {
+    defs: &'static Defs,
+    _marker: PhantomData<fn() -> O>,
+}
+
+impl<O: __ExtObjDef> RegistryHandle<O> {
+    /// The registry of `O` in the current binary.
+    pub fn current() -> Self {
+        Self {
+            defs: O::defs().target(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Converts the handle to a raw pointer, to hand it to a dynamically
+    /// loaded library.
+    pub fn into_raw(self) -> *const () {
+        ptr::from_ref(self.defs).cast()
+    }
+
+    /// Restores a handle from [`RegistryHandle::into_raw`].
+    ///
+    /// # Safety
+    /// `ptr` must come from `RegistryHandle::<O>::into_raw` of a binary built
+    /// by the same compiler with the same version of this crate.
+    pub unsafe fn from_raw(ptr: *const ()) -> Self {
+        Self {
+            defs: unsafe { &*ptr.cast::<Defs>() },
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<O> Clone for RegistryHandle<O> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<O> Copy for RegistryHandle<O> {}
+
+/// Makes the registry of `O` in the current binary forward to the `host`
+/// registry.
+///
+/// When a plugin is compiled as a separate `cdylib`, it gets its own copy of
+/// the registry of `O`, so its fields would not be part of the objects built
+/// by the host. Attaching merges the fields of every plugin into the host
+/// registry, giving one coherent layout. The host hands its
+/// [`RegistryHandle::current`] to the plugin, which attaches before any of its
+/// fields are registered, typically from the `init = ...` code of its
+/// `extobj!(impl ...)` blocks.
+///
+/// Attaching to the registry of the current binary does nothing.
+///
+/// # Panics
+/// Panics if fields were already registered in the registry of the current
+/// binary, or if it is attached to another host.
+///
+/// # Example
+///
+/// ```ignore
+/// // in the plugin, `host_registry` returns the pointer given by the host.
+/// extobj::extobj!(impl Player { pub MANA: u32 }, init = attach_host());
+///
+/// fn attach_host() {
+///     let host = unsafe { extobj::RegistryHandle::<Player>::from_raw(host_registry()) };
+///     extobj::attach(host);
+/// }
+/// ```
+pub fn attach<O: __ExtObjDef>(host: RegistryHandle<O>) {
+    O::defs().attach(host.defs);
+}
+
+/// Freezes the field registry of `O`.
+///
+/// After this call, constructing or dropping an `ExtObj<O>` no longer takes
+/// any lock, and registering a new field of `O` panics. Call it once every
+/// crate extending `O` has been loaded, typically at the start of `main`.
+///
+/// Freezing is idempotent.
+///
+/// # Example
+///
+/// ```
+/// extobj::extobj!(struct Ctx);
+/// extobj::extobj!(impl Ctx { COUNT: u32 });
+///
+/// extobj::freeze::<Ctx>();
+///
+/// let ctx = extobj::ExtObj::<Ctx>::new();
+/// assert_eq!(ctx[*COUNT], 0);
+/// ```
+pub fn freeze<O: __ExtObjDef>() {
+    loaded_defs::<O>().freeze();
+}
@@ -0,0 +1,61 @@
+use crate::{__ExtObjDef, registry::loaded_defs};
+
+/// A machine-readable description of the fields of an object type, see
+/// [`schema`].
+///
+/// With the `serde` feature, it implements `Serialize` so it can be shipped
+/// to other services validating the payloads of a serialized `ExtObj`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Schema {
+    /// The name of the object type.
+    pub object: &'static str,
+    /// The registered fields, in registration order.
+    pub fields: Vec<FieldSchema>,
+}
+
+/// The description of one field of a [`Schema`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FieldSchema {
+    /// The stable key of the field, `crate::NAME`, used by the serde
+    /// representation of the object.
+    pub key: String,
+    /// The name of the field, as declared in the `extobj!` macro.
+    pub name: &'static str,
+    /// The type name of the field, from [`std::any::type_name`].
+    pub type_name: &'static str,
+    /// Whether the field is part of the serde representation of the object.
+    pub serializable: bool,
+}
+
+/// Describes the fields registered for `O`.
+///
+/// # Example
+///
+/// ```
+/// extobj::extobj!(struct Ctx);
+/// extobj::extobj!(impl Ctx { pub RETRIES: u32 });
+///
+/// let schema = extobj::schema::<Ctx>();
+/// assert_eq!(schema.object, "Ctx");
+/// assert_eq!(schema.fields[0].name, "RETRIES");
+/// assert!(schema.fields[0].key.ends_with("::RETRIES"));
+/// ```
+pub fn schema<O: __ExtObjDef>() -> Schema {
+    let fields = loaded_defs::<O>().with(|defs| {
+        defs.iter()
+            .map(|def| FieldSchema {
+                key: def.key().to_string(),
+                name: def.name,
+                type_name: def.type_name,
+                serializable: def.serialize.is_some() && def.deserialize.is_some(),
+            })
+            .collect()
+    });
+
+    Schema {
+        object: O::NAME,
+        fields,
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{
+    __ExtObjDef, ExtObj,
+    registry::{find_field, loaded_defs},
+};
+use rhai::{Dynamic, Engine, EvalAltResult};
+use std::{
+    cell::{Ref, RefCell, RefMut},
+    rc::Rc,
+};
+
+/// A shared `ExtObj` given to [`rhai`](https://docs.rs/rhai) scripts, where
+/// `obj.FIELD` reads and writes the serializable fields.
+///
+/// [`RhaiExtObj::register`] adds a getter and a setter to the engine for each
+/// field registered so far, named after the field, without a setter for the
+/// `#[readonly]` fields. The values are converted
+/// with serde, and are not validated until [`ExtObj::validate_all`] is
+/// called. Setting a field calls its observers.
+///
+/// # Example
+///
+/// ```
+/// use extobj::{ExtObj, RhaiExtObj, rhai::{Engine, Scope}};
+///
+/// extobj::extobj!(struct Npc);
+/// extobj::extobj!(impl Npc { pub HP: i64 = 10, pub MOOD: String });
+///
+/// let mut engine = Engine::new();
+/// RhaiExtObj::<Npc>::register(&mut engine);
+///
+/// let npc = RhaiExtObj::new(ExtObj::<Npc>::new());
+/// let mut scope = Scope::new();
+/// scope.push("npc", npc.clone());
+///
+/// engine
+///     .run_with_scope(&mut scope, r#"npc.HP -= 3; npc.MOOD = "angry";"#)
+///     .unwrap();
+///
+/// assert_eq!(npc.borrow()[*HP], 7);
+/// assert_eq!(npc.borrow()[*MOOD], "angry");
+/// ```
+pub struct RhaiExtObj<O: __ExtObjDef>(Rc<RefCell<ExtObj<O>>>);
+
+impl<O: __ExtObjDef> RhaiExtObj<O> {
+    /// Shares `obj` with scripts.
+    pub fn new(obj: ExtObj<O>) -> Self {
+        Self(Rc::new(RefCell::new(obj)))
+    }
+
+    /// Borrows the object.
+    ///
+    /// # Panics
+    /// Panics if a script is writing one of its fields.
+    pub fn borrow(&self) -> Ref<'_, ExtObj<O>> {
+        self.0.borrow()
+    }
+
+    /// Mutably borrows the object.
+    ///
+    /// # Panics
+    /// Panics if the object is borrowed, by a script or by the host.
+    pub fn borrow_mut(&self) -> RefMut<'_, ExtObj<O>> {
+        self.0.borrow_mut()
+    }
+
+    /// Registers the type, named after the object, and the accessors of the
+    /// fields of `O` in `engine`.
+    ///
+    /// Fields registered afterwards are not accessible. When several crates
+    /// declare a field with the same name, none of them is.
+    pub fn register(engine: &mut Engine) {
+        engine.register_type_with_name::<Self>(O::NAME);
+
+        let defs = loaded_defs::<O>().with(|defs| {
+            defs.iter()
+                .enumerate()
+                .filter(|(index, def)| find_field(defs, def.name) == Some(*index))
+                .map(|(index, &def)| (index, def))
+                .collect::<Vec<_>>()
+        });
+
+        for (index, def) in defs {
+            if let Some(serialize) = def.serialize {
+                engine.register_get(
+                    def.name,
+                    move |obj: &mut Self| -> Result<_, Box<EvalAltResult>> {
+                        let obj = obj.0.borrow();
+                        let slot = obj.0.get(index).ok_or_else(|| missing::<O>(def.name))?;
+
+                        // SAFETY: the serializer was registered with the field type
+                        let value = unsafe { &*serialize(def.value(slot)) };
+                        rhai::serde::to_dynamic(value)
+                    },
+                );
+            }
+
+            if let Some(deserialize) = def.deserialize.filter(|_| !def.readonly) {
+                engine.register_set(
+                    def.name,
+                    move |obj: &mut Self, value: Dynamic| -> Result<_, Box<EvalAltResult>> {
+                        let value: serde_json::Value = rhai::serde::from_dynamic(&value)?;
+                        let mut obj = obj.0.borrow_mut();
+                        let slot = obj.0.get_mut(index).ok_or_else(|| missing::<O>(def.name))?;
+                        let mut erased = <dyn erased_serde::Deserializer>::erase(&value);
+
+                        // SAFETY: the deserializer was registered with the field type
+                        unsafe { deserialize(def.value_mut(slot), &mut erased) }
+                            .map_err(|e| format!("cannot set `{}`: {e}", def.name))?;
+
+                        obj.notify(index);
+                        Ok(())
+                    },
+                );
+            }
+        }
+    }
+}
+
+impl<O: __ExtObjDef> Clone for RhaiExtObj<O> {
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+/// The error of a field registered after the object was created.
+fn missing<O: __ExtObjDef>(field: &str) -> Box<EvalAltResult> {
+    format!("`{}` was created before the field `{field}`", O::NAME).into()
+}
@@ -0,0 +1,189 @@
+use crate::{
+    __ExtObjDef, __private::DeserializeFn, ExtObj, Extended, FieldAllocator, FieldSchema, Optional,
+    Schema,
+};
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    ser::{SerializeMap, SerializeStruct},
+};
+use std::{
+    fmt::{self, Formatter},
+    marker::PhantomData,
+};
+
+/// Serializes the object as a map of `crate::NAME` keys to values.
+///
+/// Fields whose type does not implement `Serialize` are skipped.
+impl<O: __ExtObjDef, A: FieldAllocator> Serialize for ExtObj<O, A> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        O::defs().with(|defs| {
+            let fields = || {
+                defs.iter().zip(&self.0).filter_map(|(def, slot)| {
+                    Some((def.key(), def.serialize?, unsafe { def.value(slot) }))
+                })
+            };
+
+            let mut map = serializer.serialize_map(Some(fields().count()))?;
+
+            for (key, serialize, ptr) in fields() {
+                // SAFETY: the serializer was registered with the field type
+                let value = unsafe { &*serialize(ptr) };
+                map.serialize_entry(&format_args!("{key}"), value)?;
+            }
+
+            map.end()
+        })
+    }
+}
+
+/// Deserializes the object from a map of `crate::NAME` keys to values.
+///
+/// Unknown keys are ignored and missing fields keep their default value.
+impl<'de, O: __ExtObjDef> Deserialize<'de> for ExtObj<O> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(ExtObjVisitor(PhantomData))
+    }
+}
+
+struct ExtObjVisitor<O>(PhantomData<O>);
+
+impl<'de, O: __ExtObjDef> Visitor<'de> for ExtObjVisitor<O> {
+    type Value = ExtObj<O>;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "a map of `{}` fields", O::NAME)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut obj = ExtObj::<O>::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            // the lock is released before deserializing the value, which may
+            // construct other objects.
+            let seed = O::defs().with(|defs| {
+                defs.iter()
+                    .zip(&mut obj.0)
+                    .find(|(def, _)| def.key().matches(&key))
+                    .and_then(|(def, slot)| {
+                        Some(FieldSeed(unsafe { def.value_mut(slot) }, def.deserialize?))
+                    })
+            });
+
+            match seed {
+                Some(seed) => map.next_value_seed(seed)?,
+                None => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        Ok(obj)
+    }
+}
+
+struct FieldSeed(usize, DeserializeFn);
+
+impl<'de> DeserializeSeed<'de> for FieldSeed {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        let mut erased = <dyn erased_serde::Deserializer>::erase(deserializer);
+
+        // SAFETY: the deserializer was registered with the field type
+        unsafe { (self.1)(self.0, &mut erased) }.map_err(de::Error::custom)
+    }
+}
+
+/// Serializes the value and its extensions as a struct with the fields
+/// `value` and `ext`.
+impl<T: Serialize, O: __ExtObjDef> Serialize for Extended<T, O> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Extended", 2)?;
+        s.serialize_field("value", &**self)?;
+        s.serialize_field("ext", self.ext())?;
+        s.end()
+    }
+}
+
+/// Deserializes the struct written by `Serialize`. When `ext` is missing, the
+/// extension fields keep their default value.
+impl<'de, T: Deserialize<'de>, O: __ExtObjDef> Deserialize<'de> for Extended<T, O> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("Extended", &["value", "ext"], ExtendedVisitor(PhantomData))
+    }
+}
+
+struct ExtendedVisitor<T, O>(PhantomData<(T, O)>);
+
+impl<'de, T: Deserialize<'de>, O: __ExtObjDef> Visitor<'de> for ExtendedVisitor<T, O> {
+    type Value = Extended<T, O>;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "an extended value with `{}` extensions", O::NAME)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let value = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+
+        let ext = match seq.next_element()? {
+            Some(ext) => ext,
+            None => ExtObj::new(),
+        };
+
+        Ok(Extended::from_parts(value, ext))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut value = None;
+        let mut ext = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "value" => value = Some(map.next_value()?),
+                "ext" => ext = Some(map.next_value()?),
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        let value = value.ok_or_else(|| de::Error::missing_field("value"))?;
+        Ok(Extended::from_parts(value, ext.unwrap_or_else(ExtObj::new)))
+    }
+}
+
+impl Serialize for Schema {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Schema", 2)?;
+        s.serialize_field("object", self.object)?;
+        s.serialize_field("fields", &self.fields)?;
+        s.end()
+    }
+}
+
+impl Serialize for FieldSchema {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("FieldSchema", 4)?;
+        s.serialize_field("key", &self.key)?;
+        s.serialize_field("name", self.name)?;
+        s.serialize_field("type_name", self.type_name)?;
+        s.serialize_field("serializable", &self.serializable)?;
+        s.end()
+    }
+}
+
+/// Serializes the value as an `Option<T>`.
+impl<T: Serialize> Serialize for Optional<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get().serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Optional<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Option::deserialize(deserializer).map(Self::from)
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{
+    __ExtObjDef, ExtObj, FieldAllocator, Global,
+    registry::{Slot, drop_order, loaded_defs},
+};
+use std::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+};
+
+/// A copy of the fields of an object, taken with [`ExtObj::snapshot`] and
+/// written back with [`ExtObj::restore`].
+///
+/// Only the fields whose type implements `Clone` are captured.
+pub struct Snapshot<O: __ExtObjDef> {
+    /// The cloned slots, `None` for the fields which are not `Clone`.
+    slots: Vec<Option<Slot>>,
+    _marker: PhantomData<(O, O::Threading)>,
+}
+
+impl<O: __ExtObjDef, A: FieldAllocator> ExtObj<O, A> {
+    /// Clones the fields of this object into a save point.
+    ///
+    /// Fields whose type does not implement `Clone` are not captured, they
+    /// keep their current value when the snapshot is restored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extobj::extobj!(struct Level);
+    /// extobj::extobj!(impl Level {
+    ///     pub SCORE: u32,
+    ///     pub ITEMS: Vec<String>,
+    /// });
+    ///
+    /// let mut level = extobj::ExtObj::<Level>::new();
+    /// level[*SCORE] = 10;
+    ///
+    /// let save = level.snapshot();
+    /// level[*SCORE] = 0;
+    /// level[*ITEMS].push("sword".into());
+    ///
+    /// level.restore(&save);
+    /// assert_eq!(level[*SCORE], 10);
+    /// assert!(level[*ITEMS].is_empty());
+    /// ```
+    pub fn snapshot(&self) -> Snapshot<O> {
+        let slots = loaded_defs::<O>().with(|defs| {
+            defs.iter()
+                .zip(&self.0)
+                .map(|(def, slot)| unsafe { def.clone_slot(slot, &Global) })
+                .collect()
+        });
+
+        Snapshot {
+            slots,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Writes the values of `snapshot` back into this object, with
+    /// `Clone::clone_from` so the allocations of the fields are reused.
+    ///
+    /// The fields which were not captured, or registered after the snapshot
+    /// was taken, keep their current value.
+    pub fn restore(&mut self, snapshot: &Snapshot<O>) {
+        O::defs().with(|defs| {
+            for ((def, dst), src) in defs.iter().zip(&mut self.0).zip(&snapshot.slots) {
+                if let Some(src) = src {
+                    // SAFETY: both slots were created by the same definition
+                    unsafe { def.clone_over(src, dst) };
+                }
+            }
+        });
+    }
+}
+
+impl<O: __ExtObjDef> Snapshot<O> {
+    /// The number of captured fields.
+    pub fn len(&self) -> usize {
+        self.slots.iter().flatten().count()
+    }
+
+    /// Returns `true` when no field was captured.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<O: __ExtObjDef> Debug for Snapshot<O> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("object", &O::NAME)
+            .field("fields", &self.len())
+            .finish()
+    }
+}
+
+impl<O: __ExtObjDef> Drop for Snapshot<O> {
+    fn drop(&mut self) {
+        O::defs().with(|defs| {
+            drop_order(defs, self.slots.len(), |i| {
+                if let Some(slot) = self.slots[i] {
+                    // SAFETY: the slot was cloned by the definition with `Global`
+                    unsafe { defs[i].destroy(slot, &Global) };
+                }
+            });
+        });
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{__ExtObjDef, ExtObj};
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::HashMap,
+    ops::Deref,
+    rc::Rc,
+};
+
+thread_local! {
+    /// The instances of the current thread, by object type.
+    static INSTANCES: RefCell<HashMap<TypeId, Rc<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+/// Returns the instance of `O` owned by the current thread, created on first
+/// use and dropped when the thread exits.
+///
+/// The instance is shared by every call on the thread, so mutable state needs
+/// interior mutability; declaring the object `local` allows `Cell` and
+/// `RefCell` fields. The returned handle cannot be sent to another thread.
+///
+/// # Panics
+/// Panics if a fallible initializer fails.
+///
+/// # Example
+///
+/// ```
+/// use std::cell::Cell;
+///
+/// extobj::extobj!(struct Worker, local);
+/// extobj::extobj!(impl Worker { pub JOBS: Cell<u32> });
+///
+/// let jobs = &extobj::thread_local::<Worker>()[*JOBS];
+/// jobs.set(jobs.get() + 1);
+///
+/// assert_eq!(extobj::thread_local::<Worker>()[*JOBS].get(), 1);
+///
+/// std::thread::spawn(|| assert_eq!(extobj::thread_local::<Worker>()[*JOBS].get(), 0))
+///     .join()
+///     .unwrap();
+/// ```
+pub fn thread_local<O: __ExtObjDef>() -> impl Deref<Target = ExtObj<O>> {
+    let id = TypeId::of::<O>();
+
+    if let Some(obj) = INSTANCES.with_borrow(|instances| instances.get(&id).cloned()) {
+        return downcast(obj);
+    }
+
+    // created without borrowing the map, the initializers may use other
+    // thread-local instances
+    let obj: Rc<dyn Any> = Rc::new(ExtObj::<O>::new());
+
+    downcast(INSTANCES.with_borrow_mut(|instances| instances.entry(id).or_insert(obj).clone()))
+}
+
+#[inline]
+fn downcast<O: __ExtObjDef>(obj: Rc<dyn Any>) -> Rc<ExtObj<O>> {
+    match obj.downcast() {
+        Ok(obj) => obj,
+        Err(_) => unreachable!("instances are stored by type"),
+    }
+}
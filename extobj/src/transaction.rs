@@ -0,0 +1,184 @@
+use crate::{
+    __ExtObjDef, ExtObj, FieldAllocator, Global, Var, missing_field,
+    registry::{FieldDef, Slot, loaded_defs},
+    unsupported,
+};
+use std::{
+    fmt::{self, Debug, Formatter},
+    mem,
+};
+
+/// The staged changes of an [`ExtObj::transaction`].
+///
+/// The first mutable access to a field saves a clone of its value, which is
+/// written back if the transaction is rolled back. Mutating a field whose type
+/// does not implement `Clone` panics.
+pub struct Transaction<'a, O: __ExtObjDef, A: FieldAllocator = Global> {
+    obj: &'a mut ExtObj<O, A>,
+    /// The fields mutated so far, with the slot holding their saved value.
+    saved: Vec<(usize, Slot, FieldDef)>,
+    /// The fields written with `set` or `update`, observed on commit.
+    written: Vec<usize>,
+}
+
+impl<O: __ExtObjDef, A: FieldAllocator> ExtObj<O, A> {
+    /// Runs `f` with a transaction over this object: the changes are kept if
+    /// `f` returns `Ok`, and rolled back if it returns `Err` or panics.
+    ///
+    /// The observers registered with [`on_change`](crate::on_change) are
+    /// called on commit, for the fields written with [`Transaction::set`] or
+    /// [`Transaction::update`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extobj::extobj!(struct Account);
+    /// extobj::extobj!(impl Account {
+    ///     pub BALANCE: i64 = 100,
+    ///     pub HISTORY: Vec<i64>,
+    /// });
+    ///
+    /// let mut account = extobj::ExtObj::<Account>::new();
+    ///
+    /// let result = account.transaction(|tx| {
+    ///     tx.update(*HISTORY, |history| history.push(-150));
+    ///     tx.update(*BALANCE, |balance| *balance -= 150);
+    ///
+    ///     match *tx.get(*BALANCE) {
+    ///         balance if balance < 0 => Err("insufficient funds"),
+    ///         balance => Ok(balance),
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(result, Err("insufficient funds"));
+    /// assert_eq!(account[*BALANCE], 100);
+    /// assert!(account[*HISTORY].is_empty());
+    /// ```
+    pub fn transaction<R, E>(
+        &mut self,
+        f: impl FnOnce(&mut Transaction<'_, O, A>) -> Result<R, E>,
+    ) -> Result<R, E> {
+        let mut tx = Transaction {
+            obj: self,
+            saved: Vec::new(),
+            written: Vec::new(),
+        };
+
+        let result = f(&mut tx);
+
+        if result.is_ok() {
+            tx.commit();
+        }
+
+        result
+    }
+}
+
+impl<O: __ExtObjDef, A: FieldAllocator> Transaction<'_, O, A> {
+    /// Immutably borrows the value of the given variable, with the changes
+    /// staged so far.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after the object was created.
+    #[inline]
+    #[track_caller]
+    pub fn get<T>(&self, var: Var<O, T>) -> &T {
+        self.obj.get(var)
+    }
+
+    /// Mutably borrows the value of the given variable, saving its value
+    /// first.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after the object was created, or
+    /// if its type does not implement `Clone`.
+    #[track_caller]
+    pub fn get_mut<T>(&mut self, var: Var<O, T>) -> &mut T {
+        let Some(slot) = self.obj.0.get(var.0) else {
+            missing_field(var.0)
+        };
+
+        if !self.saved.iter().any(|&(index, ..)| index == var.0) {
+            let def = loaded_defs::<O>().with(|defs| defs[var.0]);
+
+            // SAFETY: the slot was created by the definition
+            let Some(saved) = (unsafe { def.clone_slot(slot, &Global) }) else {
+                unsupported::<O>(def.name, "Clone")
+            };
+
+            self.saved.push((var.0, saved, def));
+        }
+
+        self.obj.get_mut(var)
+    }
+
+    /// Stages a new value for the given variable.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after the object was created, or
+    /// if its type does not implement `Clone`.
+    #[track_caller]
+    pub fn set<T>(&mut self, var: Var<O, T>, value: T) {
+        self.update(var, |v| *v = value);
+    }
+
+    /// Stages a new value for the given variable, returning the previous one.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after the object was created, or
+    /// if its type does not implement `Clone`.
+    #[track_caller]
+    pub fn replace<T>(&mut self, var: Var<O, T>, value: T) -> T {
+        mem::replace(self.get_mut(var), value)
+    }
+
+    /// Stages a mutation of the value of the given variable.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after the object was created, or
+    /// if its type does not implement `Clone`.
+    #[track_caller]
+    pub fn update<T, R>(&mut self, var: Var<O, T>, f: impl FnOnce(&mut T) -> R) -> R {
+        let result = f(self.get_mut(var));
+
+        if !self.written.contains(&var.0) {
+            self.written.push(var.0);
+        }
+
+        result
+    }
+
+    fn commit(&mut self) {
+        for (_, saved, def) in self.saved.drain(..) {
+            // SAFETY: the slot was cloned by the definition with `Global`
+            unsafe { def.destroy(saved, &Global) };
+        }
+
+        for index in mem::take(&mut self.written) {
+            self.obj.notify(index);
+        }
+    }
+}
+
+/// Rolls back the changes which were not committed.
+impl<O: __ExtObjDef, A: FieldAllocator> Drop for Transaction<'_, O, A> {
+    fn drop(&mut self) {
+        for (index, mut saved, def) in self.saved.drain(..).rev() {
+            // SAFETY: both slots were created by the definition, the staged
+            // value is dropped with the saved slot
+            unsafe {
+                def.swap(&mut self.obj.0[index], &mut saved);
+                def.destroy(saved, &Global);
+            }
+        }
+    }
+}
+
+impl<O: __ExtObjDef, A: FieldAllocator> Debug for Transaction<'_, O, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transaction")
+            .field("object", &O::NAME)
+            .field("staged", &self.saved.len())
+            .finish()
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{
+    __ExtObjDef, ExtObj, FieldAllocator, ValidationError, Var, error::BoxError, missing_field,
+    registry::FieldDef,
+};
+
+/// A type-erased `fn(&T) -> Result<(), E>` checking a value.
+#[derive(Clone, Copy)]
+pub(crate) struct Validator {
+    /// Casts `f` back to its typed signature and calls it with a value.
+    call: unsafe fn(fn(), usize) -> Result<(), BoxError>,
+    f: fn(),
+}
+
+impl Validator {
+    pub(crate) fn new<T, E: Into<BoxError>>(f: fn(&T) -> Result<(), E>) -> Self {
+        unsafe fn call<T, E: Into<BoxError>>(f: fn(), ptr: usize) -> Result<(), BoxError> {
+            let f: fn(&T) -> Result<(), E> = unsafe { std::mem::transmute(f) };
+            f(unsafe { &*(ptr as *const T) }).map_err(Into::into)
+        }
+
+        Self {
+            call: call::<T, E>,
+            // SAFETY: transmuted back by `call::<T, E>`
+            f: unsafe { std::mem::transmute::<fn(&T) -> Result<(), E>, fn()>(f) },
+        }
+    }
+
+    /// # Safety
+    /// `ptr` must point to a value of the type the validator was created for.
+    unsafe fn check(&self, ptr: usize) -> Result<(), BoxError> {
+        unsafe { (self.call)(self.f, ptr) }
+    }
+}
+
+impl<O: __ExtObjDef, A: FieldAllocator> ExtObj<O, A> {
+    /// Sets the value of the given variable if the validator of its field
+    /// accepts it, declared with `#[validate(f)]`. Fields without a validator
+    /// accept every value.
+    ///
+    /// # Panics
+    /// Panics if the variable was registered after this object was created.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// fn port(port: &u16) -> Result<(), &'static str> {
+    ///     if *port == 0 { Err("port 0 is reserved") } else { Ok(()) }
+    /// }
+    ///
+    /// extobj::extobj!(struct Server);
+    /// extobj::extobj!(impl Server {
+    ///     #[validate(port)]
+    ///     pub PORT: u16 = 80,
+    /// });
+    ///
+    /// let mut server = extobj::ExtObj::<Server>::new();
+    /// assert!(server.set_checked(*PORT, 8080).is_ok());
+    ///
+    /// let err = server.set_checked(*PORT, 0).unwrap_err();
+    /// assert_eq!(err.field(), "PORT");
+    /// assert_eq!(server[*PORT], 8080);
+    /// ```
+    #[track_caller]
+    pub fn set_checked<T>(&mut self, var: Var<O, T>, value: T) -> Result<(), ValidationError> {
+        if var.0 >= self.0.len() {
+            missing_field(var.0)
+        }
+
+        let def = O::defs().with(|defs| defs[var.0]);
+
+        if let Some(validator) = def.validator {
+            // SAFETY: the validator was registered with the field type
+            unsafe { validator.check(&value as *const T as usize) }
+                .map_err(|e| ValidationError::new(O::NAME, def.name, e))?;
+        }
+
+        self.set(var, value);
+        Ok(())
+    }
+
+    /// Checks the value of every field with a validator, reporting the first
+    /// one rejected in registration order.
+    ///
+    /// Values written without [`ExtObj::set_checked`], or deserialized, are
+    /// not validated until this is called.
+    pub fn validate_all(&self) -> Result<(), ValidationError> {
+        // the lock is released before calling the validators, which may
+        // construct other objects.
+        let defs: Vec<FieldDef> = O::defs().with(|defs| defs.to_vec());
+
+        for (def, slot) in defs.iter().zip(&self.0) {
+            if let Some(validator) = def.validator {
+                // SAFETY: the validator was registered with the field type
+                unsafe { validator.check(def.value(slot)) }
+                    .map_err(|e| ValidationError::new(O::NAME, def.name, e))?;
+            }
+        }
+
+        Ok(())
+    }
+}
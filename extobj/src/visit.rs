@@ -0,0 +1,89 @@
+use crate::{DebugField, registry::FieldDef};
+use std::{
+    any::Any,
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+};
+
+/// Visits the fields of an object, see [`ExtObj::visit`](crate::ExtObj::visit).
+///
+/// The field types are erased in the registry, so a visitor receives a
+/// [`FieldRef`] and recovers the value with [`FieldRef::downcast_ref`] or
+/// formats it with [`FieldRef::debug`].
+///
+/// Closures taking a `FieldRef` are visitors.
+pub trait FieldVisitor {
+    /// Called once per field, in registration order.
+    fn visit(&mut self, field: FieldRef<'_>);
+}
+
+impl<F: FnMut(FieldRef<'_>)> FieldVisitor for F {
+    #[inline]
+    fn visit(&mut self, field: FieldRef<'_>) {
+        self(field)
+    }
+}
+
+/// A borrowed field of an object, handed to a [`FieldVisitor`].
+#[derive(Clone, Copy)]
+pub struct FieldRef<'a> {
+    def: FieldDef,
+    ptr: usize,
+    _marker: PhantomData<&'a ()>,
+}
+
+impl<'a> FieldRef<'a> {
+    /// # Safety
+    /// `ptr` must be a slot created by `def` and outlive `'a`.
+    pub(crate) unsafe fn new(def: FieldDef, ptr: usize) -> Self {
+        Self {
+            def,
+            ptr,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The name of the field, as declared in the `extobj!` macro.
+    pub fn name(&self) -> &'static str {
+        self.def.name
+    }
+
+    /// The type name of the field, from [`std::any::type_name`].
+    pub fn type_name(&self) -> &'static str {
+        self.def.type_name
+    }
+
+    /// The module path where the field is declared.
+    pub fn origin(&self) -> &'static str {
+        self.def.origin
+    }
+
+    /// The value of the field.
+    pub fn as_any(&self) -> &'a dyn Any {
+        // SAFETY: the slot was created by this definition
+        unsafe { &*(self.def.as_any)(self.ptr) }
+    }
+
+    /// The value of the field, or `None` if it is not of type `T`.
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&'a T> {
+        self.as_any().downcast_ref()
+    }
+
+    /// The value of the field as `Debug`, or `None` if its type does not
+    /// implement `Debug`.
+    pub fn debug(&self) -> Option<impl Debug + 'a> {
+        self.def.debug.map(|debug| DebugField(self.ptr, debug))
+    }
+}
+
+impl Debug for FieldRef<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("FieldRef");
+        s.field("name", &self.name());
+
+        match self.debug() {
+            Some(value) => s.field("value", &value).finish(),
+            None => s.finish_non_exhaustive(),
+        }
+    }
+}
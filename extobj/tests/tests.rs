@@ -1,5 +1,8 @@
-use extobj::{ExtObj, RwLock, extobj};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use extobj::{ExtObj, FieldAllocator, RwLock, extobj};
+use std::{
+    mem,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 // Minimal sanity
 
@@ -96,6 +99,7 @@ fn ext_obj_is_default() {
 // Thread-safety (smoke test)
 
 #[test]
+#[allow(clippy::identity_op)]
 fn concurrent_access() {
     use std::thread;
 
@@ -122,3 +126,2124 @@ mod inside_another_mod {
 }
 
 extobj!(impl inside_another_mod::Insider { InsiderID: u32 });
+
+// Checked access
+
+extobj!(struct Late);
+extobj!(impl Late { EARLY: u8 });
+
+#[test]
+fn try_get_late_field() {
+    let mut o = ExtObj::<Late>::new();
+    let late = extobj::Var::<Late, u8>::__new(extobj::__field!(u8, LATE));
+
+    assert_eq!(o.try_get(*EARLY), Some(&0));
+    assert_eq!(o.try_get(late), None);
+    assert!(o.try_get_mut(late).is_none());
+    assert_eq!(ExtObj::<Late>::new().try_get(late), Some(&0));
+}
+
+#[test]
+#[should_panic(expected = "registered after this object was created")]
+fn get_late_field_panics() {
+    let o = ExtObj::<Late>::new();
+    let late = extobj::Var::<Late, u16>::__new(extobj::__field!(u16, LATE));
+    o.get(late);
+}
+
+// Frozen registry
+
+extobj!(struct Frozen);
+extobj!(impl Frozen { FROZEN_FIELD: u32 });
+
+#[test]
+fn frozen_registry() {
+    extobj::freeze::<Frozen>();
+    extobj::freeze::<Frozen>();
+
+    let mut o = ExtObj::<Frozen>::new();
+    o[*FROZEN_FIELD] = 3;
+    assert_eq!(o[*FROZEN_FIELD], 3);
+}
+
+#[test]
+#[should_panic(expected = "cannot register field `LATE` of `Frozen` from crate `tests`")]
+fn register_after_freeze_panics() {
+    extobj::freeze::<Frozen>();
+    extobj::Var::<Frozen, u8>::__new(extobj::__field!(u8, LATE));
+}
+
+// Frozen structs
+
+extobj!(struct Hero);
+extobj!(impl Hero { HEALTH: u32 = 10, NICK: String });
+
+extobj::frozen_struct!(
+    struct FrozenHero: Hero {
+        health: u32 = HEALTH,
+        nick: String = NICK,
+    }
+);
+
+extobj::frozen_struct!(struct PartialHero: Hero { health: u32 = HEALTH });
+
+extobj::frozen_struct!(
+    struct TwiceHero: Hero {
+        health: u32 = HEALTH,
+        again: u32 = HEALTH,
+        nick: String = NICK,
+    }
+);
+
+#[test]
+fn frozen_struct_round_trip() {
+    let mut frozen = FrozenHero::from(ExtObj::<Hero>::new());
+    assert_eq!(frozen.health, 10);
+    frozen.nick.push_str("ada");
+
+    let hero = ExtObj::<Hero>::from(frozen);
+    assert_eq!(hero[*NICK], "ada");
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "`PartialHero` does not list the fields `NICK` of `Hero`")]
+fn frozen_struct_missing_fields_panics() {
+    let _ = PartialHero::from(ExtObj::<Hero>::new());
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "`TwiceHero` lists the field `HEALTH` of `Hero` twice")]
+fn frozen_struct_duplicated_fields_panics() {
+    let _ = TwiceHero::from(ExtObj::<Hero>::new());
+}
+
+// Upgrade
+
+#[test]
+fn upgrade_late_field() {
+    let mut o = ExtObj::<Late>::new();
+    o[*EARLY] = 5;
+
+    let late = extobj::Var::<Late, u32>::__new(extobj::__field!(u32, LATE));
+    assert_eq!(o.try_get(late), None);
+
+    o.upgrade();
+    o[late] = 7;
+
+    assert_eq!(o[*EARLY], 5);
+    assert_eq!(o[late], 7);
+}
+
+// Debug
+
+#[derive(Default)]
+struct NotDebug;
+
+extobj!(struct Dbg);
+extobj!(impl Dbg {
+    NUM: i32,
+    OPAQUE: NotDebug,
+});
+
+#[test]
+fn debug_lists_fields() {
+    let mut o = ExtObj::<Dbg>::new();
+    o[*NUM] = 42;
+    assert_eq!(format!("{o:?}"), "Dbg { NUM: 42, .. }");
+}
+
+// Serde
+
+#[cfg(feature = "serde")]
+mod serde_support {
+    use super::NotDebug;
+    use extobj::{ExtObj, extobj};
+    use serde_json::json;
+
+    extobj!(struct Persisted);
+    extobj!(impl Persisted {
+        HP: i32,
+        NAME: String,
+        SKIPPED: NotDebug,
+    });
+
+    #[test]
+    fn serialize_with_stable_keys() {
+        let mut o = ExtObj::<Persisted>::new();
+        o[*HP] = 5;
+        o[*NAME] = "bob".into();
+
+        let value = serde_json::to_value(&o).unwrap();
+        assert_eq!(value, json!({ "tests::HP": 5, "tests::NAME": "bob" }));
+    }
+
+    #[test]
+    fn deserialize_tolerates_unknown_and_missing() {
+        let o: ExtObj<Persisted> =
+            serde_json::from_value(json!({ "tests::HP": 9, "other::FIELD": [1, 2] })).unwrap();
+
+        assert_eq!(o[*HP], 9);
+        assert_eq!(o[*NAME], "");
+    }
+
+    #[test]
+    fn schema_to_json() {
+        let value = serde_json::to_value(extobj::schema::<Persisted>()).unwrap();
+
+        assert_eq!(value["object"], "Persisted");
+        assert_eq!(value["fields"][0]["key"], "tests::HP");
+        assert_eq!(value["fields"][2]["serializable"], false);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn apply_patch_reports_failed_fields() {
+        use std::collections::HashMap;
+
+        let mut o = ExtObj::<Persisted>::new();
+        let patch = HashMap::from([
+            ("HP".to_owned(), json!(12)),
+            ("tests::NAME".to_owned(), json!(false)),
+            ("SKIPPED".to_owned(), json!(null)),
+            ("MISSING".to_owned(), json!(1)),
+        ]);
+
+        let err = o.apply_patch(&patch).unwrap_err();
+        let keys: Vec<_> = err.fields().map(|(key, _)| key).collect();
+
+        assert_eq!(o[*HP], 12);
+        assert_eq!(o[*NAME], "");
+        assert_eq!(keys, ["MISSING", "SKIPPED", "tests::NAME"]);
+    }
+
+    extobj!(struct Audited);
+    extobj!(impl Audited {
+        #[readonly]
+        AUTHOR: String = "ada".into(),
+    });
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn apply_patch_rejects_readonly_fields() {
+        use std::collections::HashMap;
+
+        let mut o = ExtObj::<Audited>::new();
+        let patch = HashMap::from([("AUTHOR".to_owned(), json!("eve"))]);
+
+        let err = o.apply_patch(&patch).unwrap_err();
+        assert_eq!(
+            err.fields().map(|(key, _)| key).collect::<Vec<_>>(),
+            ["AUTHOR"]
+        );
+        assert_eq!(o[*AUTHOR], "ada");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn debug_json_dump() {
+        let mut o = ExtObj::<Persisted>::new();
+        o[*NAME] = "bob".into();
+
+        assert_eq!(
+            o.to_debug_json(),
+            json!({
+                "tests::HP": "0",
+                "tests::NAME": "\"bob\"",
+                "tests::SKIPPED": "<tests::NotDebug>",
+            })
+        );
+    }
+}
+
+// C FFI
+
+#[cfg(feature = "ffi")]
+mod ffi {
+    use extobj::{ExtObj, ExtObjHandle, extobj, extobj_destroy, extobj_get, extobj_set};
+    use std::mem::ManuallyDrop;
+
+    extobj!(struct Plugin);
+    extobj!(impl Plugin { LOADS: u32 = 1, LABEL: String });
+
+    #[test]
+    fn handle_fields_by_key() {
+        let handle = ExtObjHandle::into_raw(ExtObj::<Plugin>::new());
+
+        unsafe {
+            let loads = extobj_get(handle, c"LOADS".as_ptr()) as *mut u32;
+            *loads += 1;
+            assert!(extobj_get(handle, c"MISSING".as_ptr()).is_null());
+
+            let label = ManuallyDrop::new(String::from("audio"));
+            let size = size_of::<String>();
+            assert!(!extobj_set(
+                handle,
+                c"LABEL".as_ptr(),
+                (&raw const *label).cast(),
+                1
+            ));
+            assert!(extobj_set(
+                handle,
+                c"LABEL".as_ptr(),
+                (&raw const *label).cast(),
+                size
+            ));
+        }
+
+        let plugin = unsafe { ExtObjHandle::from_raw::<Plugin>(handle) };
+        assert_eq!(plugin[*LOADS], 2);
+        assert_eq!(plugin[*LABEL], "audio");
+
+        unsafe { extobj_destroy(ExtObjHandle::into_raw(plugin)) };
+    }
+
+    extobj!(struct Firmware);
+    extobj!(impl Firmware {
+        #[readonly]
+        SERIAL: u32 = 7,
+    });
+
+    #[test]
+    fn set_rejects_readonly_fields() {
+        let handle = ExtObjHandle::into_raw(ExtObj::<Firmware>::new());
+
+        let serial: u32 = 9;
+        assert!(!unsafe { extobj_set(handle, c"SERIAL".as_ptr(), (&raw const serial).cast(), 4) });
+
+        let firmware = unsafe { ExtObjHandle::from_raw::<Firmware>(handle) };
+        assert_eq!(firmware[*SERIAL], 7);
+    }
+}
+
+// Python bindings
+
+#[cfg(feature = "python")]
+mod python {
+    use extobj::{
+        ExtObj, PyExtObj, extobj,
+        pyo3::{self, prelude::*, types::PyDict},
+    };
+
+    extobj!(struct Session);
+    extobj!(impl Session { USER: String, RETRIES: u32 = 3 });
+
+    #[test]
+    fn attributes_map_to_fields() {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            let session = Bound::new(py, PyExtObj::new(ExtObj::<Session>::new())).unwrap();
+            let locals = PyDict::new_bound(py);
+            locals.set_item("session", &session).unwrap();
+
+            py.run_bound(
+                "session.USER = 'ada'\nsession.RETRIES += 1",
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+            assert!(
+                py.run_bound("session.MISSING", None, Some(&locals))
+                    .is_err()
+            );
+
+            let session = session.borrow();
+            let session = session.downcast_ref::<Session>().unwrap();
+            assert_eq!(session[*USER], "ada");
+            assert_eq!(session[*RETRIES], 4);
+        });
+    }
+
+    extobj!(struct Account);
+    extobj!(impl Account {
+        #[readonly]
+        OWNER: String = "ada".into(),
+    });
+
+    #[test]
+    fn readonly_attributes_cannot_be_set() {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            let account = Bound::new(py, PyExtObj::new(ExtObj::<Account>::new())).unwrap();
+            let locals = PyDict::new_bound(py);
+            locals.set_item("account", &account).unwrap();
+
+            assert!(
+                py.run_bound("account.OWNER = 'eve'", None, Some(&locals))
+                    .is_err()
+            );
+
+            let account = account.borrow();
+            assert_eq!(account.downcast_ref::<Account>().unwrap()[*OWNER], "ada");
+        });
+    }
+}
+
+// Scripting
+
+#[cfg(feature = "rhai")]
+mod scripting {
+    use extobj::{
+        ExtObj, RhaiExtObj, extobj,
+        rhai::{Engine, Scope},
+    };
+
+    extobj!(struct Npc);
+    extobj!(impl Npc { HP: i64 = 10, MOOD: String });
+
+    #[test]
+    fn scripts_read_and_write_fields() {
+        let mut engine = Engine::new();
+        RhaiExtObj::<Npc>::register(&mut engine);
+
+        let npc = RhaiExtObj::new(ExtObj::<Npc>::new());
+        let mut scope = Scope::new();
+        scope.push("npc", npc.clone());
+
+        let hp: i64 = engine.eval_with_scope(&mut scope, "npc.HP * 2").unwrap();
+        assert_eq!(hp, 20);
+
+        engine
+            .run_with_scope(&mut scope, r#"npc.HP -= 3; npc.MOOD = "angry";"#)
+            .unwrap();
+        assert!(
+            engine
+                .run_with_scope(&mut scope, "npc.HP = \"x\";")
+                .is_err()
+        );
+
+        assert_eq!(npc.borrow()[*HP], 7);
+        assert_eq!(npc.borrow()[*MOOD], "angry");
+    }
+
+    extobj!(struct Boss);
+    extobj!(impl Boss {
+        #[readonly]
+        PHASES: i64 = 3,
+    });
+
+    #[test]
+    fn scripts_cannot_write_readonly_fields() {
+        let mut engine = Engine::new();
+        RhaiExtObj::<Boss>::register(&mut engine);
+
+        let boss = RhaiExtObj::new(ExtObj::<Boss>::new());
+        let mut scope = Scope::new();
+        scope.push("boss", boss.clone());
+
+        let phases: i64 = engine.eval_with_scope(&mut scope, "boss.PHASES").unwrap();
+        assert_eq!(phases, 3);
+        assert!(
+            engine
+                .run_with_scope(&mut scope, "boss.PHASES = 1;")
+                .is_err()
+        );
+        assert_eq!(boss.borrow()[*PHASES], 3);
+    }
+}
+
+// ECS components
+
+#[cfg(feature = "bevy")]
+mod ecs {
+    use extobj::{DetectFieldChanges, ExtObj, Extended, bevy_ecs::prelude::*, extobj};
+
+    extobj!(struct Stats);
+    extobj!(impl Stats { ARMOR: u32 = 1 });
+
+    #[test]
+    fn set_field_if_neq_only_sets_different_values() {
+        let mut world = World::new();
+        let entity = world.spawn(ExtObj::<Stats>::new()).id();
+
+        let mut stats = world.get_mut::<ExtObj<Stats>>(entity).unwrap();
+        let changed = stats.last_changed();
+        assert!(!stats.set_field_if_neq(*ARMOR, 1));
+        assert_eq!(stats.last_changed(), changed);
+
+        world.increment_change_tick();
+
+        let mut stats = world.get_mut::<ExtObj<Stats>>(entity).unwrap();
+        assert!(stats.set_field_if_neq(*ARMOR, 3));
+        assert_ne!(stats.last_changed(), changed);
+        assert_eq!(world.get::<ExtObj<Stats>>(entity).unwrap()[*ARMOR], 3);
+    }
+
+    #[test]
+    fn extended_is_a_component() {
+        let mut world = World::new();
+        let entity = world
+            .spawn(Extended::<String, Stats>::new("knight".into()))
+            .id();
+
+        let mut knight = world.get_mut::<Extended<String, Stats>>(entity).unwrap();
+        assert!(knight.set_field_if_neq(*ARMOR, 5));
+
+        let mut query = world.query::<&Extended<String, Stats>>();
+        let knight = query.single(&world);
+        assert_eq!(**knight, "knight");
+        assert_eq!(knight.ext()[*ARMOR], 5);
+    }
+}
+
+// Binary snapshots
+
+#[cfg(feature = "binary")]
+mod binary {
+    use super::NotDebug;
+    use extobj::{ExtObj, extobj};
+
+    extobj!(struct SaveGame);
+    extobj!(impl SaveGame {
+        #[version(2)]
+        GOLD: u64,
+        HERO: String = "nobody".into(),
+        CACHE: NotDebug,
+    });
+
+    extobj!(struct Other);
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut o = ExtObj::<SaveGame>::new();
+        o[*GOLD] = 1_000;
+        o[*HERO] = "Ada".into();
+
+        let mut bytes = Vec::new();
+        o.save(&mut bytes).unwrap();
+
+        let loaded = ExtObj::<SaveGame>::load(&bytes[..]).unwrap();
+        assert_eq!(loaded[*GOLD], 1_000);
+        assert_eq!(loaded[*HERO], "Ada");
+    }
+
+    #[test]
+    fn load_rejects_other_objects() {
+        let mut bytes = Vec::new();
+        ExtObj::<SaveGame>::new().save(&mut bytes).unwrap();
+
+        let err = ExtObj::<Other>::load(&bytes[..]).unwrap_err();
+        assert_eq!(err.to_string(), "snapshot of `SaveGame` loaded as `Other`");
+    }
+
+    mod v1 {
+        extobj::extobj!(pub struct Profile);
+        extobj::extobj!(impl Profile {
+            #[version(1)]
+            pub AGE: u8 = 30,
+        });
+    }
+
+    mod v3 {
+        extobj::extobj!(pub struct Profile);
+        extobj::extobj!(impl Profile {
+            #[version(3)]
+            pub AGE: String,
+        });
+    }
+
+    #[test]
+    fn load_chains_migrations() {
+        let mut bytes = Vec::new();
+        ExtObj::<v1::Profile>::new().save(&mut bytes).unwrap();
+
+        extobj::register_migration::<v3::Profile, u8, u16>("tests::AGE", 1, 2, u16::from);
+        extobj::register_migration::<v3::Profile, u16, String>("tests::AGE", 2, 3, |age| {
+            format!("{age} years")
+        });
+
+        let loaded = ExtObj::<v3::Profile>::load(&bytes[..]).unwrap();
+        assert_eq!(loaded[*v3::AGE], "30 years");
+    }
+}
+
+// Equality and hashing
+
+extobj!(struct Keyed, comparable);
+extobj!(impl Keyed {
+    ID: u32,
+    TAGS: Vec<String>,
+});
+
+#[test]
+fn eq_and_hash_dedup() {
+    use std::collections::HashSet;
+
+    let mut a = ExtObj::<Keyed>::new();
+    a[*ID] = 1;
+    a[*TAGS].push("x".into());
+
+    let mut b = ExtObj::<Keyed>::new();
+    b[*ID] = 1;
+    b[*TAGS].push("x".into());
+
+    assert_eq!(a, b);
+    assert_ne!(a, ExtObj::<Keyed>::new());
+
+    let set: HashSet<_> = [a, b, ExtObj::<Keyed>::new()].into_iter().collect();
+    assert_eq!(set.len(), 2);
+}
+
+extobj!(struct Ranked, comparable);
+
+#[test]
+#[should_panic(expected = "the object is comparable")]
+fn comparable_rejects_runtime_fields() {
+    extobj::register_field::<Ranked, u32>("score", || 5);
+}
+
+// Moving values in and out
+
+#[test]
+fn take_replace_set() {
+    let mut o = ExtObj::<TestObj>::new();
+
+    o.set(*VEC, vec!["a".into()]);
+    assert_eq!(o.replace(*VEC, vec!["b".into()]), ["a"]);
+    assert_eq!(o.take(*VEC), ["b"]);
+    assert!(o[*VEC].is_empty());
+}
+
+// Custom default values
+
+extobj!(struct Defaults);
+extobj!(impl Defaults {
+    RETRIES: u32 = 3,
+    LABEL: String = "anon".into(),
+    PLAIN: u8,
+});
+
+#[test]
+fn custom_default_values() {
+    let o = ExtObj::<Defaults>::new();
+    assert_eq!(o[*RETRIES], 3);
+    assert_eq!(o[*LABEL], "anon");
+    assert_eq!(o[*PLAIN], 0);
+}
+
+// Init functions for non-Default types
+
+struct Handle(u32);
+
+fn make_handle() -> Handle {
+    Handle(9)
+}
+
+extobj!(impl Defaults {
+    CLOSURE_HANDLE: Handle = init(|| Handle(7)),
+    FN_HANDLE: Handle = init(make_handle),
+});
+
+#[test]
+fn init_fn_for_non_default_types() {
+    let o = ExtObj::<Defaults>::new();
+    assert_eq!(o[*CLOSURE_HANDLE].0, 7);
+    assert_eq!(o[*FN_HANDLE].0, 9);
+}
+
+// Fallible construction
+
+extobj!(struct Fallible);
+extobj!(impl Fallible {
+    PORT: u16 = try_init(|| Ok("not a port".parse()?)),
+});
+
+#[test]
+fn try_new_reports_failing_field() {
+    let err = ExtObj::<Fallible>::try_new().unwrap_err();
+
+    assert_eq!(err.field(), "PORT");
+    assert!(
+        err.to_string()
+            .starts_with("failed to initialize field `PORT` of `Fallible`")
+    );
+}
+
+#[test]
+#[should_panic(expected = "failed to initialize field `PORT`")]
+fn new_panics_on_failing_field() {
+    ExtObj::<Fallible>::new();
+}
+
+static PARTIAL_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Default)]
+struct PartialDrop;
+
+impl Drop for PartialDrop {
+    fn drop(&mut self) {
+        PARTIAL_DROPS.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+extobj!(struct Partial);
+
+#[test]
+fn try_new_drops_initialized_fields() {
+    // registered at runtime to control the order of the fields
+    extobj::Var::<Partial, PartialDrop>::__new(extobj::__field!(PartialDrop, FIRST));
+    extobj::Var::<Partial, u8>::__new(extobj::__field!(
+        u8,
+        FAILING,
+        try_init = || Err("boom".into())
+    ));
+
+    assert!(ExtObj::<Partial>::try_new().is_err());
+    assert_eq!(PARTIAL_DROPS.load(Ordering::SeqCst), 1);
+}
+
+// Context initializers
+
+struct AppConfig {
+    workers: usize,
+}
+
+extobj!(struct Server);
+extobj!(impl Server {
+    WORKERS: usize = init_with(|ctx| ctx.downcast_ref::<AppConfig>().map_or(1, |c| c.workers)),
+    NAME_LEN: usize = try_init_with(|ctx| {
+        ctx.downcast_ref::<AppConfig>()
+            .map(|c| c.workers * 2)
+            .ok_or_else(|| "missing AppConfig".into())
+    }),
+});
+
+#[test]
+fn new_with_context() {
+    let o = ExtObj::<Server>::new_with(&AppConfig { workers: 4 });
+    assert_eq!(o[*WORKERS], 4);
+    assert_eq!(o[*NAME_LEN], 8);
+
+    let err = ExtObj::<Server>::try_new().unwrap_err();
+    assert_eq!(err.field(), "NAME_LEN");
+}
+
+// Async initializers
+
+/// Polls `future` on the current thread until it completes.
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    use std::task::{Context, Poll, Wake, Waker};
+
+    struct Unpark(std::thread::Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: std::sync::Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(std::sync::Arc::new(Unpark(std::thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+/// Resolves to `value` after returning `Pending` once.
+async fn later<T>(value: T) -> T {
+    let mut yielded = false;
+
+    std::future::poll_fn(|cx| {
+        if yielded {
+            return std::task::Poll::Ready(());
+        }
+
+        yielded = true;
+        cx.waker().wake_by_ref();
+        std::task::Poll::Pending
+    })
+    .await;
+
+    value
+}
+
+extobj!(struct AsyncConn);
+extobj!(impl AsyncConn {
+    URL: String = init_async(|| -> extobj::BoxFuture<'static, String> { Box::pin(later("db://".into())) }),
+    TIMEOUT: u32 = 30,
+});
+
+#[test]
+fn new_async_awaits_fields() {
+    let conn = block_on(ExtObj::<AsyncConn>::new_async());
+    assert_eq!(conn[*URL], "db://");
+    assert_eq!(conn[*TIMEOUT], 30);
+}
+
+#[test]
+fn new_rejects_async_fields() {
+    let err = ExtObj::<AsyncConn>::try_new().unwrap_err();
+    assert_eq!(err.field(), "URL");
+    assert!(err.to_string().contains("`ExtObj::new_async`"));
+}
+
+// Typed accessors
+
+extobj!(struct Stats);
+extobj!(impl Stats {
+    HIT_COUNT: AtomicUsize,
+    LastError: Option<String>,
+}, accessors = StatsExt);
+
+#[test]
+fn typed_accessors() {
+    let mut o = ExtObj::<Stats>::new();
+    o.hit_count().fetch_add(2, Ordering::Relaxed);
+    *o.last_error_mut() = Some("timeout".into());
+
+    assert_eq!(o[*HIT_COUNT].load(Ordering::Relaxed), 2);
+    assert_eq!(o.last_error().as_deref(), Some("timeout"));
+
+    o.set_last_error(None);
+    assert_eq!(o[*LastError], None);
+}
+
+extobj!(struct Limits);
+extobj!(impl Limits {
+    #[validate(|n: &u32| if *n > 0 { Ok(()) } else { Err("zero") })]
+    MAX_CONNECTIONS: u32 = 10,
+}, accessors = LimitsExt);
+
+#[test]
+fn generated_setters_validate_and_track() {
+    let mut o = extobj::TrackedExtObj::<Limits>::new();
+    assert!(o.set_max_connections(0).is_err());
+    assert!(!o.is_dirty(*MAX_CONNECTIONS));
+
+    assert!(o.set_max_connections(20).is_ok());
+    assert!(o.is_dirty(*MAX_CONNECTIONS));
+    assert_eq!(*o.max_connections(), 20);
+}
+
+// Borrow views
+
+extobj!(struct Body);
+extobj!(impl Body {
+    POS_X: f32,
+    POS_Y: f32 = 1.0,
+}, view = Position);
+
+#[test]
+fn borrow_view() {
+    let mut o = ExtObj::<Body>::new();
+    let pos = Position::from(&mut o);
+    std::mem::swap(pos.pos_x, pos.pos_y);
+    *pos.pos_y += 2.0;
+
+    assert_eq!(o[*POS_X], 1.0);
+    assert_eq!(o[*POS_Y], 2.0);
+}
+
+// Field attributes
+
+extobj!(struct Annotated);
+extobj!(impl Annotated {
+    /// A documented field.
+    #[allow(dead_code)]
+    DOCUMENTED: u8 = 7,
+});
+
+#[test]
+fn field_attributes() {
+    assert_eq!(ExtObj::<Annotated>::new()[*DOCUMENTED], 7);
+}
+
+// cfg-gated fields
+
+extobj!(struct Gated);
+extobj!(impl Gated {
+    ALWAYS: u8 = 1,
+    #[cfg(any())]
+    NEVER: NotDefault,
+}, accessors = GatedExt, view = GatedView);
+
+#[test]
+fn cfg_gated_fields() {
+    let mut o = ExtObj::<Gated>::new();
+    assert_eq!(*o.always(), 1);
+    assert_eq!(*GatedView::from(&mut o).always, 1);
+}
+
+// Generic extension objects
+
+extobj!(pub struct Ctx<T: 'static>);
+extobj!(impl Ctx<u8> { SMALL: u32 = 8 });
+extobj!(impl Ctx<u64> { LARGE: u32 = 64, OTHER: bool });
+
+#[test]
+fn generic_registries_per_instantiation() {
+    let small = ExtObj::<Ctx<u8>>::new();
+    let large = ExtObj::<Ctx<u64>>::new();
+
+    assert_eq!(small[*SMALL], 8);
+    assert_eq!(large[*LARGE], 64);
+    assert_eq!(format!("{small:?}"), "Ctx { SMALL: 8 }");
+}
+
+// Field metadata
+
+#[test]
+fn var_metadata() {
+    assert_eq!(FOO.name(), "FOO");
+    assert_eq!(FOO.type_name(), "i32");
+    assert_eq!(FOO.origin(), "tests");
+    assert_eq!(InsiderID.origin(), "tests");
+}
+
+// Untyped variables
+
+#[test]
+fn untyped_vars() {
+    let vars = [FOO.untyped(), (*VEC).into()];
+    let mut o = ExtObj::<TestObj>::new();
+
+    *o.get_dyn_mut(vars[0]).downcast_mut::<i32>().unwrap() = 5;
+    assert_eq!(o.get_dyn(vars[0]).downcast_ref::<i32>(), Some(&5));
+    assert!(o.get_dyn(vars[1]).is::<Vec<String>>());
+
+    assert!(vars[1].is::<Vec<String>>());
+    assert_eq!(vars[0].downcast::<i32>(), Some(*FOO));
+    assert_eq!(vars[0].downcast::<u32>(), None);
+}
+
+// Iteration
+
+#[test]
+fn iter_fields() {
+    let mut o = ExtObj::<Defaults>::new();
+
+    for (name, value) in o.iter_mut() {
+        if let Some(n) = value.downcast_mut::<u32>() {
+            assert_eq!(name, "RETRIES");
+            *n += 1;
+        }
+    }
+
+    let names: Vec<_> = o.iter().map(|(name, _)| name).collect();
+    assert!(names.contains(&"LABEL"));
+    assert_eq!(o[*RETRIES], 4);
+}
+
+// Visitor
+
+struct DebugLines(Vec<String>);
+
+impl extobj::FieldVisitor for DebugLines {
+    fn visit(&mut self, field: extobj::FieldRef<'_>) {
+        if let Some(value) = field.debug() {
+            self.0.push(format!("{}={value:?}", field.name()));
+        }
+    }
+}
+
+#[test]
+fn visit_fields() {
+    let mut o = ExtObj::<Dbg>::new();
+    o[*NUM] = 42;
+
+    let mut lines = DebugLines(Vec::new());
+    o.visit(&mut lines);
+    assert_eq!(lines.0, ["NUM=42"]);
+}
+
+// Registry introspection
+
+#[test]
+fn registry_lists_fields() {
+    let fields: Vec<_> = extobj::registry::<Defaults>().collect();
+    let retries = fields.iter().find(|f| f.name() == "RETRIES").unwrap();
+
+    assert_eq!(retries.type_name(), "u32");
+    assert_eq!((retries.size(), retries.align()), (4, 4));
+    assert_eq!(retries.crate_name(), "tests");
+    assert!(fields.iter().enumerate().all(|(i, f)| f.index() == i));
+}
+
+#[test]
+fn var_by_name_resolves_fields() {
+    let var = extobj::var_by_name::<Defaults>("RETRIES").unwrap();
+    assert_eq!(var.downcast::<u32>(), Some(*RETRIES));
+    assert_eq!(extobj::var_by_name::<Defaults>("tests::RETRIES"), Some(var));
+    assert!(extobj::var_by_name::<Defaults>("NOPE").is_none());
+}
+
+// Inline storage
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Mode {
+    #[default]
+    Idle,
+    Busy(u16),
+}
+
+extobj!(struct Packed);
+extobj!(impl Packed {
+    FLAG: bool,
+    MODE: Mode,
+    WIDE: [u64; 4],
+    SHARED: std::sync::Arc<u8>,
+});
+
+#[test]
+fn small_values_are_inline() {
+    let inline: Vec<_> = extobj::registry::<Packed>()
+        .map(|f| (f.name(), f.is_inline()))
+        .collect();
+
+    assert_eq!(
+        inline,
+        [
+            ("FLAG", true),
+            ("MODE", true),
+            ("WIDE", false),
+            ("SHARED", false)
+        ]
+    );
+
+    let mut o = ExtObj::<Packed>::new();
+    o[*FLAG] = true;
+    o[*MODE] = Mode::Busy(3);
+    o[*WIDE][3] = 9;
+
+    assert!(o[*FLAG]);
+    assert_eq!(o[*MODE], Mode::Busy(3));
+    assert_eq!(o[*WIDE], [0, 0, 0, 9]);
+
+    let shared = std::sync::Arc::new(7);
+    o[*SHARED] = shared.clone();
+    assert_eq!(std::sync::Arc::strong_count(&shared), 2);
+
+    o.reset();
+    assert_eq!(o[*MODE], Mode::Idle);
+    assert_eq!(std::sync::Arc::strong_count(&shared), 1);
+}
+
+// Schema
+
+#[test]
+fn schema_lists_keys() {
+    let schema = extobj::schema::<Keyed>();
+    let keys: Vec<_> = schema.fields.iter().map(|f| f.key.as_str()).collect();
+
+    assert_eq!(schema.object, "Keyed");
+    assert_eq!(keys, ["tests::ID", "tests::TAGS"]);
+}
+
+// Runtime registration
+
+extobj!(struct Plugin);
+
+#[test]
+fn register_field_at_runtime() {
+    let mut before = ExtObj::<Plugin>::new();
+    let score = extobj::register_field::<Plugin, u32>(&format!("score_{}", 1), || 10);
+
+    assert_eq!(before.try_get(score), None);
+    before.upgrade();
+    assert_eq!(before[score], 10);
+    assert_eq!(score.name(), "score_1");
+    assert_eq!(score.origin(), "runtime");
+}
+
+// Shared registry
+
+extobj!(struct HostObj);
+extobj!(impl HostObj { HOST_FIELD: u8 = 1 });
+
+// stands for the copy of `HostObj` in a dynamically loaded plugin
+extobj!(struct PluginObj);
+
+#[test]
+fn attach_to_host_registry() {
+    let raw = extobj::RegistryHandle::<HostObj>::current().into_raw();
+    extobj::attach(unsafe { extobj::RegistryHandle::<PluginObj>::from_raw(raw) });
+
+    let plugin_field = extobj::register_field::<PluginObj, u16>("PLUGIN_FIELD", || 2);
+    let o = ExtObj::<PluginObj>::new();
+
+    assert_eq!(o[plugin_field], 2);
+    assert_eq!(extobj::registry::<HostObj>().count(), 2);
+}
+
+// Explicit registration
+
+extobj!(struct Explicit);
+extobj!(impl Explicit {
+    LAZY_COUNT: u32 = 5,
+}, register = register_explicit_fields);
+
+#[test]
+fn explicit_registration() {
+    register_explicit_fields();
+    register_explicit_fields();
+
+    let o = ExtObj::<Explicit>::new();
+    assert_eq!(o[*LAZY_COUNT], 5);
+    assert_eq!(extobj::registry::<Explicit>().count(), 1);
+}
+
+// linkme backend
+
+#[cfg(feature = "linkme")]
+mod linkme_backend {
+    use extobj::{ExtObj, extobj};
+
+    extobj!(struct Linked);
+    extobj!(impl Linked {
+        FIRST: u8 = 1,
+        SECOND: String = "two".into(),
+    }, backend = linkme);
+
+    #[test]
+    fn fields_registered_at_link_time() {
+        let o = ExtObj::<Linked>::new();
+        assert_eq!(o[*FIRST], 1);
+        assert_eq!(o[*SECOND], "two");
+        assert_eq!(extobj::registry::<Linked>().count(), 2);
+    }
+}
+
+// Optional fields
+
+// no `Default` implementation
+#[derive(Debug, PartialEq)]
+struct Token(&'static str);
+
+extobj!(struct Connection);
+extobj!(impl Connection {
+    #[optional]
+    AUTH: Token,
+});
+
+#[test]
+fn optional_field_starts_empty() {
+    let mut o = ExtObj::<Connection>::new();
+    assert_eq!(o[*AUTH].get(), None);
+
+    o[*AUTH].set(Token("secret"));
+    assert_eq!(o[*AUTH].get(), Some(&Token("secret")));
+    assert_eq!(o[*AUTH].replace(Token("rotated")), Some(Token("secret")));
+
+    assert_eq!(o[*AUTH].take(), Some(Token("rotated")));
+    assert!(!o[*AUTH].is_set());
+    assert!(extobj::registry::<Connection>().all(|f| f.size() == std::mem::size_of::<usize>()));
+}
+
+// Read-only fields
+
+extobj!(struct Published);
+extobj!(impl Published {
+    #[readonly]
+    pub REVISION: u32 = 1,
+}, accessors = PublishedExt);
+
+#[test]
+fn readonly_field_written_through_mut_static() {
+    let mut o = ExtObj::<Published>::new();
+    o[*REVISION_MUT] += 1;
+
+    assert_eq!(o[*REVISION], 2);
+    assert_eq!(*o.revision(), 2);
+    assert_eq!(REVISION.name(), "REVISION");
+}
+
+#[test]
+fn readonly_field_found_by_name_is_not_writable() {
+    let var = extobj::var_by_name::<Published>("REVISION").unwrap();
+    assert_eq!(var.downcast::<u32>(), None);
+    assert!(extobj::registry::<Published>().all(|f| f.is_readonly()));
+
+    let mut o = ExtObj::<Published>::new();
+    assert_eq!(o[var.downcast_read::<u32>().unwrap()], 1);
+
+    let write = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        *o.get_dyn_mut(var).downcast_mut::<u32>().unwrap() = 9;
+    }));
+    assert!(write.is_err());
+    assert_eq!(o[*REVISION], 1);
+
+    // the declaring crate keeps its mutable variable
+    assert_eq!(
+        REVISION_MUT.untyped().downcast::<u32>(),
+        Some(*REVISION_MUT)
+    );
+}
+
+// Validators
+
+#[allow(clippy::ptr_arg)] // validators receive `&T`
+fn non_empty(value: &String) -> Result<(), String> {
+    if value.is_empty() {
+        Err("must not be empty".into())
+    } else {
+        Ok(())
+    }
+}
+
+extobj!(struct ValidatedSettings);
+extobj!(impl ValidatedSettings {
+    #[validate(non_empty)]
+    HOSTNAME: String = "localhost".into(),
+    #[validate(|n: &u8| if *n <= 100 { Ok(()) } else { Err("over 100") })]
+    PERCENT: u8 = 50,
+    UNCHECKED: u8,
+});
+
+#[test]
+fn set_checked_rejects_invalid_values() {
+    let mut o = ExtObj::<ValidatedSettings>::new();
+    assert!(o.set_checked(*PERCENT, 80).is_ok());
+    assert!(o.set_checked(*UNCHECKED, 255).is_ok());
+
+    let err = o.set_checked(*HOSTNAME, String::new()).unwrap_err();
+    assert_eq!(err.field(), "HOSTNAME");
+    assert_eq!(
+        err.to_string(),
+        "invalid value for field `HOSTNAME` of `ValidatedSettings`: must not be empty"
+    );
+    assert_eq!(o[*HOSTNAME], "localhost");
+    assert_eq!(o[*PERCENT], 80);
+}
+
+#[test]
+fn validate_all_reports_first_invalid_field() {
+    let mut o = ExtObj::<ValidatedSettings>::new();
+    assert!(o.validate_all().is_ok());
+
+    o[*PERCENT] = 101;
+    assert_eq!(o.validate_all().unwrap_err().field(), "PERCENT");
+}
+
+// Drop order
+
+static DROP_LOG: std::sync::Mutex<Vec<&str>> = std::sync::Mutex::new(Vec::new());
+
+struct Logged(&'static str);
+
+impl Drop for Logged {
+    fn drop(&mut self) {
+        DROP_LOG.lock().unwrap().push(self.0);
+    }
+}
+
+extobj!(struct Ordered);
+extobj!(impl Ordered {
+    #[drop_priority(1)]
+    POOL: Logged = init(|| Logged("pool")),
+    USER: Logged = init(|| Logged("user")),
+});
+
+#[test]
+fn drop_priority_orders_drops() {
+    drop(ExtObj::<Ordered>::new());
+    assert_eq!(*DROP_LOG.lock().unwrap(), ["user", "pool"]);
+}
+
+// Unwind-safe construction
+
+static UNWIND_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Default)]
+struct CountDrop;
+
+impl Drop for CountDrop {
+    fn drop(&mut self) {
+        UNWIND_DROPS.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+struct PanicDefault;
+
+impl Default for PanicDefault {
+    fn default() -> Self {
+        panic!("no default")
+    }
+}
+
+extobj!(struct Unwind);
+
+#[test]
+fn panicking_default_drops_created_fields() {
+    // registered at runtime to control the order of the fields
+    extobj::Var::<Unwind, CountDrop>::__new(extobj::__field!(CountDrop, COUNTED));
+    extobj::Var::<Unwind, PanicDefault>::__new(extobj::__field!(PanicDefault, PANICS));
+
+    let result = std::panic::catch_unwind(ExtObj::<Unwind>::new);
+
+    assert!(result.is_err());
+    assert_eq!(UNWIND_DROPS.load(Ordering::SeqCst), 1);
+}
+
+// Send/Sync enforcement
+
+extobj!(struct Single, local);
+extobj!(impl Single {
+    SHARED_NAME: std::rc::Rc<str> = "local".into(),
+});
+
+#[test]
+fn local_objects_accept_non_send_fields() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<ExtObj<TestObj>>();
+
+    let o = ExtObj::<Single>::new();
+    assert_eq!(&*o[*SHARED_NAME], "local");
+}
+
+// Local objects
+
+extobj!(impl Single {
+    HISTORY: std::rc::Rc<std::cell::RefCell<Vec<u8>>>,
+}, accessors = SingleExt);
+
+#[test]
+fn local_ext_obj_with_shared_cells() {
+    use extobj::LocalExtObj;
+
+    let o = LocalExtObj::<Single>::new();
+    let history = std::rc::Rc::clone(o.history());
+    history.borrow_mut().push(1);
+
+    assert_eq!(*o[*HISTORY].borrow(), [1]);
+    assert_eq!(std::rc::Rc::strong_count(&o[*HISTORY]), 2);
+}
+
+// Object pools
+
+extobj!(struct Pooled);
+extobj!(impl Pooled {
+    BUFFER: Vec<u8>,
+    ATTEMPTS: u32 = 3,
+});
+
+#[test]
+fn pool_resets_released_objects() {
+    let pool = extobj::ExtObjPool::<Pooled>::new();
+
+    let mut o = pool.acquire();
+    o[*BUFFER].extend_from_slice(b"request");
+    o[*ATTEMPTS] = 0;
+    let slot: *const Vec<u8> = &o[*BUFFER];
+    pool.release(o);
+    assert_eq!(pool.idle(), 1);
+
+    let o = pool.acquire();
+    assert_eq!(pool.idle(), 0);
+    assert!(o[*BUFFER].is_empty());
+    assert_eq!(o[*ATTEMPTS], 3);
+    assert!(std::ptr::eq(&o[*BUFFER], slot));
+}
+
+#[test]
+fn pool_keeps_at_most_max_idle() {
+    let pool = extobj::ExtObjPool::<Pooled>::with_max_idle(1);
+    let (a, b) = (pool.acquire(), pool.acquire());
+
+    pool.release(a);
+    pool.release(b);
+    assert_eq!(pool.idle(), 1);
+
+    pool.clear();
+    assert_eq!(pool.idle(), 0);
+}
+
+// Arena construction
+
+#[cfg(feature = "bumpalo")]
+mod arena {
+    use extobj::{ArenaExtObj, bumpalo::Bump, extobj};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static ARENA_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Default)]
+    struct Tracked(String);
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            ARENA_DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    extobj!(struct Frame);
+    extobj!(impl Frame {
+        LABEL: Tracked,
+        WEIGHT: u64 = 7,
+    });
+
+    #[test]
+    fn fields_allocated_in_arena_are_dropped() {
+        let mut bump = Bump::new();
+        let mut o = ArenaExtObj::<Frame>::new_in(&bump);
+        assert!(bump.allocated_bytes() > 0);
+
+        o[*LABEL].0.push_str("frame");
+        assert_eq!(o[*WEIGHT], 7);
+
+        drop(o);
+        assert_eq!(ARENA_DROPS.load(Ordering::SeqCst), 1);
+        bump.reset();
+    }
+}
+
+// Custom allocators
+
+#[derive(Default)]
+struct CountingAlloc(AtomicUsize);
+
+unsafe impl FieldAllocator for &CountingAlloc {
+    fn alloc(&self, layout: std::alloc::Layout) -> std::ptr::NonNull<u8> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        extobj::Global.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: std::ptr::NonNull<u8>, layout: std::alloc::Layout) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+        unsafe { extobj::Global.dealloc(ptr, layout) }
+    }
+}
+
+extobj!(struct Allocated);
+extobj!(impl Allocated {
+    NAME: String = "alloc".into(),
+    EMPTY: (),
+});
+
+#[test]
+fn fields_allocated_by_custom_allocator() {
+    let counting = CountingAlloc::default();
+    let o = ExtObj::<Allocated, _>::new_in(&counting);
+
+    assert_eq!(o[*NAME], "alloc");
+    // `EMPTY` is stored inline
+    assert_eq!(counting.0.load(Ordering::SeqCst), 1);
+
+    drop(o);
+    assert_eq!(counting.0.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn dyn_obj_in_custom_allocator() {
+    let counting = CountingAlloc::default();
+    let obj = extobj::DynObj::new_in(vec![1, 2, 3], &counting);
+    assert_eq!(counting.0.load(Ordering::SeqCst), 1);
+
+    let v: Vec<i32> = unsafe { obj.into_inner() };
+    assert_eq!(v, [1, 2, 3]);
+    assert_eq!(counting.0.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn cloneable_dyn_obj_in_custom_allocator() {
+    let counting = CountingAlloc::default();
+    let obj = extobj::CloneDynObj::new_in(String::from("a"), &counting);
+
+    let mut copy = obj.clone();
+    unsafe { copy.get_mut::<String>() }.push('b');
+    assert_eq!(counting.0.load(Ordering::SeqCst), 2);
+
+    assert_eq!(unsafe { obj.into_inner::<String>() }, "a");
+    assert_eq!(unsafe { copy.into_inner::<String>() }, "ab");
+    assert_eq!(counting.0.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn dyn_obj_box_round_trip() {
+    let boxed = Box::new(String::from("boxed"));
+    let ptr: *const String = &*boxed;
+
+    let obj = extobj::DynObj::from(boxed);
+    let boxed: Box<String> = unsafe { obj.into_box() };
+    assert!(std::ptr::eq(ptr, &*boxed));
+}
+
+#[test]
+fn dyn_obj_raw_parts() {
+    let obj = extobj::DynObj::new(vec![String::from("a")]);
+    let (data, release) = unsafe { obj.into_raw_parts::<Vec<String>>() };
+
+    let obj = unsafe { extobj::DynObj::from_raw_parts::<Vec<String>>(data) };
+    assert_eq!(unsafe { obj.get::<Vec<String>>() }, &["a"]);
+
+    let (data, _) = unsafe { obj.into_raw_parts::<Vec<String>>() };
+    unsafe { release(data) };
+}
+
+#[test]
+fn local_dyn_obj_holds_rc() {
+    use std::{cell::Cell, rc::Rc};
+
+    let shared = Rc::new(Cell::new(1));
+    let mut obj = extobj::LocalDynObj::new_debug(Rc::clone(&shared));
+    assert!(format!("{obj:?}").starts_with("LocalDynObj { type: "));
+
+    unsafe { obj.get_mut::<Rc<Cell<i32>>>() }.set(2);
+    assert_eq!(shared.get(), 2);
+
+    drop(obj);
+    assert_eq!(Rc::strong_count(&shared), 1);
+}
+
+#[test]
+fn dyn_obj_debug() {
+    let obj = extobj::DynObj::new_debug(String::from("hi"));
+    assert_eq!(
+        format!("{obj:?}"),
+        r#"DynObj { type: "alloc::string::String", value: "hi" }"#
+    );
+    assert_eq!(format!("{:?}", extobj::DynObj::new(1)), "DynObj { .. }");
+}
+
+#[cfg(any(debug_assertions, feature = "checked"))]
+#[test]
+#[should_panic(expected = "Type mismatch in DynObj::get")]
+fn dyn_obj_type_mismatch_panics() {
+    let obj = extobj::DynObj::new(1u8);
+    let _ = unsafe { obj.get::<u16>() };
+}
+
+// Column storage
+
+extobj!(struct Particle);
+extobj!(impl Particle {
+    MASS: f32 = 1.0,
+    TAG: String,
+});
+
+#[test]
+fn ext_obj_vec_stores_fields_as_columns() {
+    let mut particles = extobj::ExtObjVec::<Particle>::new();
+    let a = particles.push();
+    let b = particles.push();
+
+    particles[(b, *MASS)] = 3.0;
+    particles.get_mut(a, *TAG).push_str("first");
+
+    assert_eq!(particles.column(*MASS), [1.0, 3.0]);
+    assert_eq!(particles.len(), 2);
+
+    particles.swap_remove(a);
+    assert_eq!(particles.column(*MASS), [3.0]);
+    assert_eq!(particles[(0, *TAG)], "");
+}
+
+// Inline objects
+
+extobj!(struct Probe);
+extobj!(impl Probe {
+    GAIN: u8 = 3,
+    STATION: String,
+    SAMPLES: [u64; 4],
+});
+
+#[test]
+fn ext_obj_inline_keeps_fields_in_its_buffer() {
+    let mut probe = extobj::ExtObjInline::<Probe, 128>::new();
+    probe[*STATION].push_str("north");
+    probe[*SAMPLES][3] = 9;
+
+    assert_eq!(probe[*GAIN], 3);
+    assert_eq!(probe[*STATION], "north");
+    assert_eq!(probe[*SAMPLES], [0, 0, 0, 9]);
+}
+
+#[test]
+fn ext_obj_inline_rejects_too_small_buffers() {
+    // the first field, in registration order, which does not fit
+    let err = extobj::ExtObjInline::<Probe, 8>::try_new().unwrap_err();
+    assert!(["STATION", "SAMPLES"].contains(&err.field()));
+}
+
+// Parallel columns
+
+#[cfg(feature = "rayon")]
+mod parallel {
+    use extobj::{ExtObj, ExtObjVec, extobj};
+    use rayon::prelude::*;
+
+    extobj!(struct Cell);
+    extobj!(impl Cell { ENERGY: u64 = 1 });
+
+    #[test]
+    fn par_for_each_updates_every_object() {
+        let mut cells = ExtObjVec::<Cell>::new();
+        (0..1000).for_each(|_| {
+            cells.push();
+        });
+
+        cells.par_for_each(*ENERGY, |i, energy| *energy += i as u64);
+        cells
+            .par_column_mut(*ENERGY)
+            .for_each(|energy| *energy *= 2);
+
+        assert_eq!(cells[(10, *ENERGY)], 22);
+        assert_eq!(
+            cells.par_column(*ENERGY).sum::<u64>(),
+            2 * (1000 + 999 * 1000 / 2)
+        );
+    }
+
+    extobj!(struct Report);
+    extobj!(impl Report {
+        TOTAL: u64 = (1..=100).sum(),
+        LABEL: String = "daily".into(),
+    });
+
+    #[test]
+    fn new_parallel_initializes_every_field() {
+        let report = ExtObj::<Report>::new_parallel();
+        assert_eq!(report[*TOTAL], 5050);
+        assert_eq!(report[*LABEL], "daily");
+    }
+
+    extobj!(struct Broken);
+    extobj!(impl Broken {
+        NAME: String = "broken".into(),
+        COUNT: u32 = try_init(|| Ok("many".parse()?)),
+        RATIO: f64 = try_init(|| Ok("half".parse()?)),
+    });
+
+    #[test]
+    fn try_new_parallel_reports_first_failing_field() {
+        let err = ExtObj::<Broken>::try_new_parallel().unwrap_err();
+        assert_eq!(err.field(), "COUNT");
+    }
+}
+
+// Interior mutability
+
+extobj!(struct Scene);
+extobj!(impl Scene {
+    NODES: Vec<u32>,
+    DIRTY: bool,
+});
+
+#[test]
+fn ext_obj_cell_borrows_fields_independently() {
+    let scene = extobj::ExtObjCell::<Scene>::new();
+
+    let mut nodes = scene.borrow_mut(*NODES);
+    *scene.borrow_mut(*DIRTY) = true;
+    nodes.push(1);
+
+    assert!(scene.try_borrow(*NODES).is_err());
+    assert!(*scene.borrow(*DIRTY));
+    let debug = format!("{scene:?}");
+    assert!(debug.contains("NODES: <borrowed>"));
+    assert!(debug.contains("DIRTY: true"));
+
+    drop(nodes);
+    assert_eq!(*scene.borrow(*NODES), [1]);
+    assert_eq!(scene.into_inner()[*NODES], [1]);
+}
+
+#[test]
+#[should_panic(expected = "already mutably borrowed")]
+fn ext_obj_cell_panics_on_conflicting_borrow() {
+    let scene = extobj::ExtObjCell::<Scene>::new();
+    let _nodes = scene.borrow_mut(*NODES);
+    scene.borrow(*NODES);
+}
+
+// Copy-on-write objects
+
+extobj!(struct Snapshot);
+extobj!(impl Snapshot {
+    ROWS: Vec<u32>,
+    VERSION: u32,
+});
+
+#[test]
+fn cow_ext_obj_clones_fields_on_write() {
+    let mut current = extobj::CowExtObj::<Snapshot>::new();
+    current[*ROWS].extend([1, 2, 3]);
+
+    let saved = current.clone();
+    current[*VERSION] += 1;
+
+    assert!(current.is_shared(*ROWS));
+    assert!(!current.is_shared(*VERSION));
+    assert_eq!(saved[*VERSION], 0);
+
+    current[*ROWS].push(4);
+    assert_eq!(saved[*ROWS], [1, 2, 3]);
+
+    let owned: ExtObj<Snapshot> = current.into_ext_obj();
+    assert_eq!(owned[*ROWS], [1, 2, 3, 4]);
+}
+
+// Save points
+
+extobj!(struct Editor);
+extobj!(impl Editor {
+    TEXT: String,
+    CURSOR: usize,
+    CLIPBOARD: std::sync::Mutex<String>,
+});
+
+#[test]
+fn restore_snapshot_of_clone_fields() {
+    let mut editor = ExtObj::<Editor>::new();
+    editor[*TEXT].push_str("hello");
+    editor[*CURSOR] = 5;
+
+    let save = editor.snapshot();
+    assert_eq!(save.len(), 2);
+
+    editor[*TEXT].push_str(" world");
+    editor[*CURSOR] = 11;
+    editor[*CLIPBOARD].lock().unwrap().push_str("copied");
+
+    editor.restore(&save);
+    assert_eq!(editor[*TEXT], "hello");
+    assert_eq!(editor[*CURSOR], 5);
+    assert_eq!(*editor[*CLIPBOARD].lock().unwrap(), "copied");
+}
+
+// Dirty tracking
+
+extobj!(struct Replicated);
+extobj!(impl Replicated {
+    HP: u32 = 10,
+    MANA: u32,
+});
+
+#[test]
+fn tracked_ext_obj_marks_mutated_fields() {
+    let mut o = extobj::TrackedExtObj::<Replicated>::new();
+    assert_eq!(o.dirty_vars().count(), 0);
+
+    assert_eq!(o[*HP], 10);
+    assert!(!o.is_dirty(*HP));
+
+    o.set(*MANA, 5);
+    assert!(o.is_dirty(*MANA));
+    assert_eq!(o.dirty_vars().collect::<Vec<_>>(), [MANA.var_id()]);
+
+    o.clear_dirty();
+    assert!(!o.is_dirty(*MANA));
+}
+
+// Change observers
+
+extobj!(pub struct Observed);
+
+extobj!(impl Observed {
+    CAPTION: String,
+    SIZE: u32,
+});
+
+static CAPTIONS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+#[test]
+fn observers_fire_on_set_and_update() {
+    extobj::on_change(*CAPTION, |caption: &String| {
+        CAPTIONS.write().push(caption.clone())
+    });
+
+    let mut o = ExtObj::<Observed>::new();
+    o.set(*CAPTION, "a".into());
+    o.update(*CAPTION, |caption| caption.push('b'));
+
+    // unobserved field and direct mutation do not fire
+    o.set(*SIZE, 3);
+    o[*CAPTION].push('c');
+
+    assert_eq!(*CAPTIONS.read(), ["a", "ab"]);
+    assert_eq!(o.update(*SIZE, |size| mem::replace(size, 6)), 3);
+}
+
+// Transactions
+
+extobj!(pub struct Ledger);
+
+extobj!(impl Ledger {
+    BALANCE: i64 = 10,
+    ENTRIES: Vec<i64>,
+});
+
+#[test]
+fn transaction_commits_on_ok() {
+    let mut o = ExtObj::<Ledger>::new();
+
+    let balance = o.transaction(|tx| {
+        tx.set(*BALANCE, 4);
+        tx.get_mut(*ENTRIES).push(-6);
+        Ok::<_, ()>(*tx.get(*BALANCE))
+    });
+
+    assert_eq!(balance, Ok(4));
+    assert_eq!(o[*ENTRIES], [-6]);
+}
+
+#[test]
+fn transaction_rolls_back_on_err_and_panic() {
+    let mut o = ExtObj::<Ledger>::new();
+
+    let result = o.transaction(|tx| {
+        tx.set(*BALANCE, -1);
+        tx.update(*ENTRIES, |entries| entries.push(-11));
+        tx.set(*BALANCE, -2);
+        Err::<(), _>("overdrawn")
+    });
+
+    assert_eq!(result, Err("overdrawn"));
+    assert_eq!(o[*BALANCE], 10);
+    assert!(o[*ENTRIES].is_empty());
+
+    let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        o.transaction(|tx| -> Result<(), ()> {
+            tx.set(*BALANCE, 0);
+            panic!("aborted")
+        })
+    }));
+
+    assert!(panicked.is_err());
+    assert_eq!(o[*BALANCE], 10);
+}
+
+// Singletons
+
+extobj!(pub struct Settings, singleton);
+
+extobj!(impl Settings {
+    LEVEL: u8 = 1,
+});
+
+#[test]
+fn singleton_is_shared() {
+    assert!(std::ptr::eq(Settings::global(), Settings::global()));
+
+    Settings::global().write()[*LEVEL] += 1;
+    assert_eq!(Settings::global().read()[*LEVEL], 2);
+}
+
+// Sealed objects
+
+extobj!(struct Internal, sealed);
+
+extobj!(impl Internal {
+    HITS: u64 = 1,
+});
+
+#[test]
+fn sealed_object_fields_from_own_crate() {
+    let mut o = ExtObj::<Internal>::new();
+    o[*HITS] += 1;
+    assert_eq!(o[*HITS], 2);
+}
+
+#[test]
+#[should_panic(expected = "sealed")]
+fn sealed_object_rejects_runtime_fields() {
+    extobj::register_field::<Internal, u8>("MISSES", || 0);
+}
+
+// Keyed objects
+
+mod plugin_api {
+    pub struct Key;
+}
+
+extobj!(struct Host, key = plugin_api::Key);
+
+extobj!(impl Host {
+    LOAD: u32 = 5,
+}, key = plugin_api::Key);
+
+#[test]
+fn keyed_object_fields_with_key() {
+    assert_eq!(ExtObj::<Host>::new()[*LOAD], 5);
+}
+
+// Namespaced statics
+
+extobj!(struct Left);
+extobj!(struct Right);
+
+extobj!(impl Left {
+    SIDE: u8 = 1,
+}, module = left, accessors = LeftExt);
+
+extobj!(impl Right {
+    SIDE: u8 = 2,
+}, module = right);
+
+#[test]
+fn namespaced_statics_do_not_clash() {
+    let mut l = ExtObj::<Left>::new();
+    l.set_side(3);
+
+    assert_eq!(l[*left::SIDE], 3);
+    assert_eq!(ExtObj::<Right>::new()[*right::SIDE], 2);
+}
+
+// Thread-local instances
+
+extobj!(pub struct Scratch, local);
+
+extobj!(impl Scratch {
+    SCRATCH_TEXT: std::cell::RefCell<String>,
+});
+
+#[test]
+fn thread_local_instances() {
+    extobj::thread_local::<Scratch>()[*SCRATCH_TEXT]
+        .borrow_mut()
+        .push_str("main");
+
+    std::thread::spawn(|| {
+        assert!(
+            extobj::thread_local::<Scratch>()[*SCRATCH_TEXT]
+                .borrow()
+                .is_empty()
+        )
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(
+        *extobj::thread_local::<Scratch>()[*SCRATCH_TEXT].borrow(),
+        "main"
+    );
+}
+
+// Extended values
+
+extobj!(pub struct UserExt, comparable);
+
+extobj!(impl UserExt {
+    VISITS: u32,
+});
+
+#[derive(Clone, Debug, PartialEq)]
+struct User {
+    name: String,
+}
+
+#[test]
+fn extended_derefs_to_value() {
+    let mut user = extobj::Extended::<User, UserExt>::new(User { name: "ada".into() });
+    user.name.push('!');
+    user.ext_mut()[*VISITS] = 2;
+
+    let copy = user.clone();
+    assert_eq!(copy, user);
+    assert_eq!(copy.ext()[*VISITS], 2);
+
+    let (value, ext) = user.into_parts();
+    assert_eq!(value.name, "ada!");
+    assert_eq!(ext[*VISITS], 2);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn extended_serde_round_trip() {
+    let mut count = extobj::Extended::<u8, UserExt>::new(7);
+    count.ext_mut()[*VISITS] = 3;
+
+    let value = serde_json::to_value(&count).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({ "value": 7, "ext": { "tests::VISITS": 3 } })
+    );
+
+    let back: extobj::Extended<u8, UserExt> =
+        serde_json::from_value(serde_json::json!({ "value": 7 })).unwrap();
+    assert_eq!((*back, back.ext()[*VISITS]), (7, 0));
+}
+
+// Extensible structs
+
+#[extobj::extensible]
+#[derive(Debug, Default)]
+pub struct Invoice {
+    pub total: u64,
+}
+
+extobj!(impl Invoice {
+    PAID: bool,
+});
+
+#[test]
+fn extensible_struct_carries_extensions() {
+    let mut invoice = Invoice {
+        total: 30,
+        ..Default::default()
+    };
+
+    invoice.ext_mut()[*PAID] = true;
+
+    assert_eq!(invoice.total, 30);
+    assert!(invoice.ext()[*PAID]);
+}
+
+// Foreign keys
+
+extobj!(pub struct Tagged);
+
+extobj!(impl Tagged {
+    NOTE: String,
+});
+
+#[test]
+fn ext_map_attaches_objects_to_keys() {
+    let mut map = extobj::ExtMap::<String, Tagged>::new();
+
+    map.get_or_create("a")[*NOTE].push_str("first");
+    map.entry("b".into()).or_default()[*NOTE].push_str("second");
+    map.get_or_create("a")[*NOTE].push('!');
+
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get("a").unwrap()[*NOTE], "first!");
+
+    map.retain(|key, _| key == "b");
+    let tags: Vec<_> = map.values().map(|obj| obj[*NOTE].clone()).collect();
+    assert_eq!(tags, ["second"]);
+}
+
+// Live instances
+
+extobj!(pub struct Listed);
+
+extobj!(impl Listed {
+    GENERATION: u32,
+});
+
+#[test]
+fn instances_lists_live_objects() {
+    let kept: Vec<_> = (0..3).map(|_| ExtObj::<Listed>::new_live()).collect();
+    let dropped = ExtObj::<Listed>::new().into_live();
+    drop(dropped);
+
+    for obj in extobj::instances::<Listed>() {
+        obj.write()[*GENERATION] += 1;
+    }
+
+    assert_eq!(extobj::instances::<Listed>().count(), 3);
+    assert!(kept.iter().all(|obj| obj.read()[*GENERATION] == 1));
+}
+
+// Lifecycle diagnostics
+
+#[cfg(feature = "diagnostics")]
+mod diagnostics {
+    use extobj::{ExtObj, extobj};
+
+    extobj!(struct Counted);
+    extobj!(impl Counted { HITS: u32 });
+
+    #[test]
+    fn stats_count_created_and_dropped() {
+        let objects: Vec<_> = (0..3).map(|_| ExtObj::<Counted>::new()).collect();
+        drop(ExtObj::<Counted>::new());
+
+        let stats = extobj::stats::<Counted>();
+        assert_eq!((stats.live, stats.created, stats.dropped), (3, 4, 1));
+
+        drop(objects);
+        assert_eq!(extobj::stats::<Counted>().live, 0);
+    }
+}
+
+// Memory usage
+
+extobj!(pub struct Measured);
+
+extobj!(impl Measured {
+    CONTENT: String,
+    LOCK: RwLock<u8>,
+});
+
+#[test]
+fn memory_usage_reports_heap_of_fields() {
+    let mut o = ExtObj::<Measured>::new();
+    o[*CONTENT] = String::with_capacity(100);
+
+    let usage = o.memory_usage();
+
+    // fields are listed in registration order, which is not declaration order
+    let heap = |name| usage.fields.iter().find(|f| f.name == name).unwrap().heap;
+    assert_eq!(heap("CONTENT"), Some(100));
+    assert_eq!(heap("LOCK"), None);
+    assert_eq!(
+        usage.total(),
+        size_of::<String>() + 100 + size_of::<RwLock<u8>>()
+    );
+}
+
+// Secret fields
+
+#[cfg(feature = "zeroize")]
+mod secrets {
+    use extobj::{ExtObj, extobj, zeroize::Zeroize};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static WIPES: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Default)]
+    struct Key([u8; 32]);
+
+    impl Zeroize for Key {
+        fn zeroize(&mut self) {
+            self.0.zeroize();
+            WIPES.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    extobj!(struct Vault);
+    extobj!(impl Vault {
+        #[zeroize]
+        KEY: Key,
+        LABEL: String,
+    });
+
+    #[test]
+    fn secrets_wiped_on_reset_and_drop() {
+        let mut o = ExtObj::<Vault>::new();
+        o[*KEY].0 = [7; 32];
+
+        o.reset();
+        assert_eq!(WIPES.load(Ordering::SeqCst), 1);
+        assert_eq!(o[*KEY].0, [0; 32]);
+
+        drop(o);
+        assert_eq!(WIPES.load(Ordering::SeqCst), 2);
+    }
+}
+
+// Access metrics
+
+#[cfg(feature = "metrics")]
+mod metrics {
+    use extobj::{ExtObj, extobj};
+
+    extobj!(struct Metered);
+    extobj!(impl Metered {
+        HOT: u32,
+        COLD: u32,
+    });
+
+    #[test]
+    fn reads_and_writes_counted_per_field() {
+        let mut o = ExtObj::<Metered>::new();
+        o[*HOT] += 1;
+        o.set(*HOT, 5);
+        let _ = o[*HOT] + o[*HOT];
+
+        let fields: Vec<_> = extobj::registry::<Metered>().collect();
+        assert_eq!((fields[0].reads(), fields[0].writes()), (2, 2));
+        assert_eq!((fields[1].reads(), fields[1].writes()), (0, 0));
+
+        fields[0].reset_access_counts();
+        assert_eq!(fields[0].writes(), 0);
+    }
+}